@@ -0,0 +1,55 @@
+-- Created and Updated helpers
+-- Requires the table to have `updated_at` and `valid_until` fields
+--
+-- The statements are idempotent so that this migration also applies to the
+-- databases created before the migrations existed.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_proc WHERE proname = 'update_modified_column') THEN
+        CREATE FUNCTION update_modified_column()
+        RETURNS TRIGGER AS $function$
+        BEGIN
+            NEW.updated_at = now();
+            NEW.valid_until = now() + make_interval(weeks => 1);
+            RETURN NEW;
+        END;
+        $function$ language 'plpgsql';
+    END IF;
+END
+$$;
+
+-- Use as follows:
+-- CREATE OR REPLACE TRIGGER update_<YOUR_TABLE>_updated_at
+--     BEFORE UPDATE ON <YOUR_TABLE>
+--     FOR EACH ROW EXECUTE FUNCTION update_modified_column();
+-- CREATE INDEX index_<YOUR_TABLE>__created_at ON <YOUR_TABLE>(created_at);
+
+-- -----------------------------------------------------------------------------
+-- REPOSITORIES
+-- -----------------------------------------------------------------------------
+
+-- GitHub username limit is 39 characters
+-- GitHub repo limit is 100 characters
+-- We use 150 to get a bit of margin
+
+CREATE TABLE IF NOT EXISTS repositories (
+    path                VARCHAR(150) UNIQUE NOT NULL,
+    contributors        INTEGER,
+    dependencies        VARCHAR(150) ARRAY,
+    created_at  TIMESTAMP WITH TIME ZONE    DEFAULT now(),
+    updated_at  TIMESTAMP WITH TIME ZONE    DEFAULT now(),
+    valid_until TIMESTAMP WITH TIME ZONE    DEFAULT now() + make_interval(weeks => 1),
+    PRIMARY KEY(path)
+);
+CREATE INDEX IF NOT EXISTS index_repositories__path ON repositories(path);
+CREATE INDEX IF NOT EXISTS index_repositories__created_at ON repositories(created_at);
+CREATE INDEX IF NOT EXISTS index_repositories__updated_at ON repositories(updated_at);
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'update_repositories__updated_at') THEN
+        CREATE TRIGGER update_repositories__updated_at
+            BEFORE UPDATE ON repositories
+            FOR EACH ROW EXECUTE FUNCTION update_modified_column();
+    END IF;
+END
+$$;
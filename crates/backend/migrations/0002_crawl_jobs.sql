@@ -0,0 +1,30 @@
+-- -----------------------------------------------------------------------------
+-- CRAWL JOBS
+-- -----------------------------------------------------------------------------
+
+-- Dependency crawls run by the backend workers, independently of the clients.
+-- `checkpoint` holds the state of the traversal to resume it after a restart,
+-- `heartbeat_at` tells whether the worker running the job is still alive.
+CREATE TABLE IF NOT EXISTS crawl_jobs (
+    id              UUID PRIMARY KEY            DEFAULT gen_random_uuid(),
+    path            VARCHAR(150) NOT NULL,
+    max_depth       INTEGER NOT NULL,
+    max_nodes       INTEGER NOT NULL,
+    status          VARCHAR(16) NOT NULL        DEFAULT 'pending',
+    checkpoint      JSONB,
+    heartbeat_at    TIMESTAMP WITH TIME ZONE,
+    created_at      TIMESTAMP WITH TIME ZONE    DEFAULT now(),
+    updated_at      TIMESTAMP WITH TIME ZONE    DEFAULT now()
+);
+-- Only one active crawl per repository and limits
+CREATE UNIQUE INDEX IF NOT EXISTS index_crawl_jobs__active ON crawl_jobs(path, max_depth, max_nodes)
+    WHERE status IN ('pending', 'running');
+CREATE INDEX IF NOT EXISTS index_crawl_jobs__status ON crawl_jobs(status, created_at);
+
+-- Messages published by a crawl job, in order
+CREATE TABLE IF NOT EXISTS crawl_job_events (
+    job_id          UUID NOT NULL REFERENCES crawl_jobs(id) ON DELETE CASCADE,
+    seq             INTEGER NOT NULL,
+    message         JSONB NOT NULL,
+    PRIMARY KEY(job_id, seq)
+);
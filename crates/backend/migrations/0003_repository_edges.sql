@@ -0,0 +1,21 @@
+-- -----------------------------------------------------------------------------
+-- REPOSITORY EDGES
+-- -----------------------------------------------------------------------------
+
+-- `child` is a dependency of `parent`, one row per dependency so that the
+-- repositories depending on a given one can be found through an index.
+-- `child` has no foreign key since dependencies are saved before being crawled.
+CREATE TABLE IF NOT EXISTS repository_edges (
+    parent          VARCHAR(150) NOT NULL REFERENCES repositories(path) ON DELETE CASCADE,
+    child           VARCHAR(150) NOT NULL,
+    discovered_at   TIMESTAMP WITH TIME ZONE    DEFAULT now(),
+    PRIMARY KEY(parent, child)
+);
+CREATE INDEX IF NOT EXISTS index_repository_edges__child ON repository_edges(child);
+
+-- Edges of the repositories crawled before this table existed
+INSERT INTO repository_edges (parent, child, discovered_at)
+    SELECT path, unnest(dependencies), updated_at
+    FROM repositories
+    WHERE dependencies IS NOT NULL
+    ON CONFLICT DO NOTHING;
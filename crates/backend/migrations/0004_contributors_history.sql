@@ -0,0 +1,19 @@
+-- -----------------------------------------------------------------------------
+-- CONTRIBUTORS HISTORY
+-- -----------------------------------------------------------------------------
+
+-- Every contributors count observed for a repository, while `repositories`
+-- only keeps the last one.
+CREATE TABLE repository_contributors_history (
+    path            VARCHAR(150) NOT NULL REFERENCES repositories(path) ON DELETE CASCADE,
+    contributors    INTEGER NOT NULL,
+    observed_at     TIMESTAMP WITH TIME ZONE    DEFAULT now()
+);
+CREATE INDEX index_repository_contributors_history__path_observed_at
+    ON repository_contributors_history(path, observed_at);
+
+-- Counts observed before this table existed
+INSERT INTO repository_contributors_history (path, contributors, observed_at)
+    SELECT path, contributors, updated_at
+    FROM repositories
+    WHERE contributors IS NOT NULL;
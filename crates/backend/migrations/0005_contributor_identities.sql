@@ -0,0 +1,13 @@
+-- -----------------------------------------------------------------------------
+-- CONTRIBUTOR IDENTITIES
+-- -----------------------------------------------------------------------------
+
+-- The people contributing to a repository, so that someone contributing to
+-- several repositories of a dependency graph is only counted once.
+-- `identity` is the lowercase login of an account, or `anonymous:` followed by
+-- the email of an anonymous contributor.
+CREATE TABLE IF NOT EXISTS repository_contributor_identities (
+    path        VARCHAR(150) NOT NULL REFERENCES repositories(path) ON DELETE CASCADE,
+    identity    VARCHAR(320) NOT NULL,
+    PRIMARY KEY(path, identity)
+);
@@ -0,0 +1,8 @@
+-- -----------------------------------------------------------------------------
+-- BOT CONTRIBUTORS
+-- -----------------------------------------------------------------------------
+
+-- Whether the contributor is a bot, such as `dependabot[bot]`, so that people
+-- and bots are counted apart.
+ALTER TABLE repository_contributor_identities
+    ADD COLUMN IF NOT EXISTS bot BOOLEAN NOT NULL DEFAULT false;
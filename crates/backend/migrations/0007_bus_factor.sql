@@ -0,0 +1,11 @@
+-- -----------------------------------------------------------------------------
+-- BUS FACTOR
+-- -----------------------------------------------------------------------------
+
+-- How much the commits of a repository depend on few people, the bots aside:
+-- the fewest contributors who together authored more than half of the commits,
+-- and the share of the commits of the top contributor, between 0 and 1.
+-- Only known when the contributions are fetched.
+ALTER TABLE repositories
+    ADD COLUMN IF NOT EXISTS bus_factor INTEGER,
+    ADD COLUMN IF NOT EXISTS top_share DOUBLE PRECISION;
@@ -0,0 +1,18 @@
+-- -----------------------------------------------------------------------------
+-- REPOSITORY PACKAGES
+-- -----------------------------------------------------------------------------
+
+-- The packages `parent` depends on, one row per package and manifest.
+-- `repository` is the repository the package resolved to, NULL when it does not
+-- resolve to a GitHub repository. `ecosystem` is the lowercase package manager,
+-- such as `cargo` or `npm`, NULL when unknown.
+CREATE TABLE IF NOT EXISTS repository_packages (
+    id              BIGSERIAL PRIMARY KEY,
+    parent          VARCHAR(150) NOT NULL REFERENCES repositories(path) ON DELETE CASCADE,
+    ecosystem       VARCHAR(50),
+    package_name    VARCHAR(255) NOT NULL,
+    requirements    VARCHAR(255),
+    manifest        VARCHAR(500),
+    repository      VARCHAR(150)
+);
+CREATE INDEX IF NOT EXISTS index_repository_packages__parent ON repository_packages(parent);
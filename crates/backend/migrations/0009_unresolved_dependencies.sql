@@ -0,0 +1,10 @@
+-- -----------------------------------------------------------------------------
+-- UNRESOLVED DEPENDENCIES
+-- -----------------------------------------------------------------------------
+
+-- The repository URL of the package as listed, whatever its host. The packages
+-- whose `repository` is NULL link to something else than a GitHub repository
+-- when it is set, and to no repository at all otherwise.
+ALTER TABLE repository_packages ADD COLUMN IF NOT EXISTS url VARCHAR(500);
+CREATE INDEX IF NOT EXISTS index_repository_packages__unresolved
+    ON repository_packages(parent) WHERE repository IS NULL;
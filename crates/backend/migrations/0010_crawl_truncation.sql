@@ -0,0 +1,9 @@
+-- -----------------------------------------------------------------------------
+-- CRAWL TRUNCATION
+-- -----------------------------------------------------------------------------
+
+-- Whether a finished crawl stopped on its limits before the end of the graph,
+-- NULL until it is done. The last finished crawl of each repository and limits
+-- is kept to tell it.
+ALTER TABLE crawl_jobs
+    ADD COLUMN IF NOT EXISTS truncated BOOLEAN;
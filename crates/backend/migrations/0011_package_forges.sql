@@ -0,0 +1,9 @@
+-- -----------------------------------------------------------------------------
+-- PACKAGE FORGES
+-- -----------------------------------------------------------------------------
+
+-- The packages resolve to the repositories of every supported forge, not only
+-- GitHub: `repository` is a path as `repositories(path)`, `OWNER/REPO` on GitHub
+-- and `HOST/OWNER/REPO` elsewhere.
+COMMENT ON COLUMN repository_packages.repository IS
+    'Path of the repository the package resolved to, NULL when it does not resolve to a repository of a supported forge';
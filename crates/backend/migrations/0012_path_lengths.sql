@@ -0,0 +1,27 @@
+-- -----------------------------------------------------------------------------
+-- PATH LENGTHS
+-- -----------------------------------------------------------------------------
+
+-- The paths of the other forges than GitHub have no bounded length, such as
+-- `HOST/GROUP/SUBGROUP/.../REPO` on GitLab whose groups nest, so that one long
+-- path no longer fails the whole save of the dependencies listing it.
+ALTER TABLE repositories
+    ALTER COLUMN path TYPE TEXT,
+    ALTER COLUMN dependencies TYPE TEXT ARRAY;
+
+ALTER TABLE crawl_jobs
+    ALTER COLUMN path TYPE TEXT;
+
+ALTER TABLE repository_edges
+    ALTER COLUMN parent TYPE TEXT,
+    ALTER COLUMN child TYPE TEXT;
+
+ALTER TABLE repository_contributors_history
+    ALTER COLUMN path TYPE TEXT;
+
+ALTER TABLE repository_contributor_identities
+    ALTER COLUMN path TYPE TEXT;
+
+ALTER TABLE repository_packages
+    ALTER COLUMN parent TYPE TEXT,
+    ALTER COLUMN repository TYPE TEXT;
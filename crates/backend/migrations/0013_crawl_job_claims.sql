@@ -0,0 +1,9 @@
+-- -----------------------------------------------------------------------------
+-- CRAWL JOB CLAIMS
+-- -----------------------------------------------------------------------------
+
+-- Claim of the worker running the job, renewed each time a worker claims it.
+-- A worker whose job was reclaimed as stale no longer saves anything for it,
+-- so that two workers never save messages with the same sequence numbers.
+ALTER TABLE crawl_jobs
+    ADD COLUMN IF NOT EXISTS claim_id UUID;
@@ -0,0 +1,250 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Label of the badges not asking for another one
+const DEFAULT_LABEL: &str = "how many contributors";
+/// Longest label accepted, in characters
+const MAX_LABEL_LENGTH: usize = 64;
+/// Seconds during which the clients may cache the badge of a repository
+/// never crawled, short as its crawl is queued
+pub(crate) const UNKNOWN_MAX_AGE_SEC: u64 = 60;
+
+const LABEL_COLOR: &str = "#555";
+const KNOWN_COLOR: &str = "#4c1";
+const UNKNOWN_COLOR: &str = "#9f9f9f";
+
+/// Look of a badge, named after the shields.io styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum BadgeStyle {
+    #[default]
+    Flat,
+    FlatSquare,
+    ForTheBadge,
+}
+
+impl FromStr for BadgeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "flat" => Ok(BadgeStyle::Flat),
+            "flat-square" => Ok(BadgeStyle::FlatSquare),
+            "for-the-badge" => Ok(BadgeStyle::ForTheBadge),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// A two parts badge, the label on the left and the message on the right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Badge {
+    label: String,
+    message: String,
+    color: &'static str,
+}
+
+impl Badge {
+    /// Badge of a crawled repository.
+    pub(crate) fn known(label: Option<String>, contributors: usize, dependencies: usize) -> Self {
+        let dependencies = match dependencies {
+            1 => "1 dependency".to_string(),
+            count => format!("{} dependencies", metric(count)),
+        };
+        Self {
+            label: Self::label(label),
+            message: format!(
+                "{} contributors across {dependencies}",
+                metric(contributors)
+            ),
+            color: KNOWN_COLOR,
+        }
+    }
+
+    /// Placeholder of a repository never crawled.
+    pub(crate) fn unknown(label: Option<String>) -> Self {
+        Self {
+            label: Self::label(label),
+            message: "unknown".to_string(),
+            color: UNKNOWN_COLOR,
+        }
+    }
+
+    fn label(label: Option<String>) -> String {
+        label
+            .unwrap_or_else(|| DEFAULT_LABEL.to_string())
+            .chars()
+            .take(MAX_LABEL_LENGTH)
+            .collect()
+    }
+
+    /// The SVG image of the badge.
+    pub(crate) fn render(&self, style: BadgeStyle) -> String {
+        let (label, message) = match style {
+            BadgeStyle::ForTheBadge => (self.label.to_uppercase(), self.message.to_uppercase()),
+            _ => (self.label.clone(), self.message.clone()),
+        };
+        let (height, radius, padding, font) = match style {
+            BadgeStyle::Flat => (20, 3, 10, "font-size=\"11\""),
+            BadgeStyle::FlatSquare => (20, 0, 10, "font-size=\"11\""),
+            BadgeStyle::ForTheBadge => (
+                28,
+                0,
+                24,
+                "font-size=\"10\" font-weight=\"bold\" letter-spacing=\"1\"",
+            ),
+        };
+        let spacing = match style {
+            BadgeStyle::ForTheBadge => 1,
+            _ => 0,
+        };
+        let label_width = text_width(&label, spacing) + padding;
+        let message_width = text_width(&message, spacing) + padding;
+        let width = label_width + message_width;
+        let text_y = height / 2 + 4;
+        let title = xml_escape(&format!("{label}: {message}"));
+        let (label, message) = (xml_escape(&label), xml_escape(&message));
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" role=\"img\" aria-label=\"{title}\">"
+        );
+        let _ = write!(svg, "<title>{title}</title>");
+        if style == BadgeStyle::Flat {
+            svg.push_str(concat!(
+                "<linearGradient id=\"s\" x2=\"0\" y2=\"100%\">",
+                "<stop offset=\"0\" stop-color=\"#bbb\" stop-opacity=\".1\"/>",
+                "<stop offset=\"1\" stop-opacity=\".1\"/>",
+                "</linearGradient>"
+            ));
+        }
+        let _ = write!(
+            svg,
+            "<clipPath id=\"r\"><rect width=\"{width}\" height=\"{height}\" rx=\"{radius}\" fill=\"#fff\"/></clipPath>"
+        );
+        let _ = write!(
+            svg,
+            "<g clip-path=\"url(#r)\"><rect width=\"{label_width}\" height=\"{height}\" fill=\"{LABEL_COLOR}\"/><rect x=\"{label_width}\" width=\"{message_width}\" height=\"{height}\" fill=\"{}\"/>",
+            self.color
+        );
+        if style == BadgeStyle::Flat {
+            let _ = write!(
+                svg,
+                "<rect width=\"{width}\" height=\"{height}\" fill=\"url(#s)\"/>"
+            );
+        }
+        svg.push_str("</g>");
+        let _ = write!(
+            svg,
+            "<g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" {font}>"
+        );
+        for (text, x) in [
+            (label, label_width / 2),
+            (message, label_width + message_width / 2),
+        ] {
+            if style == BadgeStyle::Flat {
+                // Shadow of the text
+                let _ = write!(
+                    svg,
+                    "<text x=\"{x}\" y=\"{}\" fill=\"#010101\" fill-opacity=\".3\">{text}</text>",
+                    text_y + 1
+                );
+            }
+            let _ = write!(svg, "<text x=\"{x}\" y=\"{text_y}\">{text}</text>");
+        }
+        svg.push_str("</g></svg>");
+        svg
+    }
+}
+
+/// Approximate width in pixels of the text written in 11px Verdana, there is
+/// no font to measure it with.
+fn text_width(text: &str, letter_spacing: usize) -> usize {
+    text.chars()
+        .map(|c| {
+            let width = match c {
+                'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '\'' | '|' | '!' => 3,
+                'f' | 'r' | 't' | 'I' | ' ' | '(' | ')' | '-' | '/' => 4,
+                'm' | 'w' | 'M' | 'W' => 10,
+                'A'..='Z' => 8,
+                _ => 7,
+            };
+            width + letter_spacing
+        })
+        .sum()
+}
+
+/// Short form of large numbers, `12.3k` or `1.2M`.
+fn metric(count: usize) -> String {
+    match count {
+        0..1_000 => count.to_string(),
+        1_000..1_000_000 => short(count as f64 / 1_000.0, "k"),
+        _ => short(count as f64 / 1_000_000.0, "M"),
+    }
+}
+
+fn short(value: f64, unit: &str) -> String {
+    if value >= 100.0 {
+        format!("{value:.0}{unit}")
+    } else {
+        format!("{value:.1}{unit}").replace(".0", "")
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric() {
+        assert_eq!(metric(0), "0");
+        assert_eq!(metric(999), "999");
+        assert_eq!(metric(1_000), "1k");
+        assert_eq!(metric(12_345), "12.3k");
+        assert_eq!(metric(345_678), "346k");
+        assert_eq!(metric(1_250_000), "1.2M");
+    }
+
+    #[test]
+    fn test_badge_messages() {
+        let badge = Badge::known(None, 12_345, 340);
+        assert_eq!(badge.label, DEFAULT_LABEL);
+        assert_eq!(badge.message, "12.3k contributors across 340 dependencies");
+        assert_eq!(
+            Badge::known(None, 3, 1).message,
+            "3 contributors across 1 dependency"
+        );
+        assert_eq!(Badge::unknown(None).message, "unknown");
+        assert_eq!(
+            Badge::unknown(Some("x".repeat(100))).label.len(),
+            MAX_LABEL_LENGTH
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_label() {
+        let svg = Badge::unknown(Some("<script>&".to_string())).render(BadgeStyle::Flat);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("&lt;script&gt;&amp;"));
+        assert!(!svg.contains("<script>"));
+    }
+
+    #[test]
+    fn test_render_styles() {
+        let badge = Badge::known(Some("deps".to_string()), 10, 2);
+        assert!(badge.render(BadgeStyle::Flat).contains("rx=\"3\""));
+        assert!(badge.render(BadgeStyle::FlatSquare).contains("rx=\"0\""));
+        let for_the_badge = badge.render(BadgeStyle::ForTheBadge);
+        assert!(for_the_badge.contains("height=\"28\""));
+        assert!(for_the_badge.contains(">DEPS</text>"));
+        assert_eq!("flat-square".parse(), Ok(BadgeStyle::FlatSquare));
+        assert!("plastic".parse::<BadgeStyle>().is_err());
+    }
+}
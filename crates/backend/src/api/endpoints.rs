@@ -1,28 +1,33 @@
+use super::badge::{Badge, BadgeStyle, UNKNOWN_MAX_AGE_SEC};
+use super::errors::ApiError;
+use super::protocol::{JSON_SUBPROTOCOL, PROTOCOL_VERSION, Protocol, RecDepError, Session};
 use super::state::AppState;
-use crate::cache::{Cache, CacheError};
+use crate::cache::Cache;
+use crate::crawler::cached_not_found;
+use crate::models::{
+    BadgeQuery, CompareQuery, ComparedRepository, CrawlQueued, DependencyComparison,
+    DependencyReport, ErrorCode, ExportQuery, Link, Package, PackagesQuery, SbomImported,
+    ServerMessage, UnresolvedDependency,
+};
+use crate::database::Database;
 use crate::database::errors::DatabaseError;
-use crate::database::{models::RepositoryInfo, Database};
-use crate::models::{ContributorsChunk, Link};
-use axum::extract::ws::CloseFrame;
-use axum::extract::{ConnectInfo, State};
-use axum::{
-    extract::ws::{Message, Utf8Bytes, WebSocket, WebSocketUpgrade},
-    response::IntoResponse,
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{
+    StatusCode,
+    header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, LOCATION},
+};
+use axum::response::Response;
+use axum::{extract::ws::WebSocketUpgrade, response::IntoResponse};
+use github_scrapper::{
+    DependencyGraph, Ecosystem, Forge, GitHubError, GraphFormat, GraphNode, RepositoryId, Sbom,
 };
-use github_scrapper::{GitHubError, GitHubLink, GitHubLinkDependencies};
-use metrics::counter;
-use rand::Rng;
-use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{Mutex, RwLock};
-use tokio::time::sleep;
-use tracing::{debug, error, info, warn};
-
-/// Sleep after a link has been fetched from GitHub
-/// This only applies on the current WS connection, not across all connections.
-const SLEEP_BETWEEN_FETCHES: Duration = Duration::from_millis(750);
+use tokio::sync::Mutex;
+use metrics::counter;
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 /// Health Check of the API
 pub(crate) async fn ping() -> &'static str {
@@ -36,291 +41,526 @@ pub(crate) async fn leaderboard(State(state): State<AppState>) -> impl IntoRespo
     axum::response::Json(leaderboard)
 }
 
-/// Websocket handler for the API
-pub(crate) async fn ws_handler_dependencies(
+/// Repositories depending on the given one, among the already crawled ones
+pub(crate) async fn dependents(
     State(state): State<AppState>,
-    ws: WebSocketUpgrade,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    axum::extract::Query(link): axum::extract::Query<Link>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| {
-        tokio::spawn(async move {
-            let socket = Arc::new(Mutex::new(socket));
-            dependencies(state, socket, addr, link).await;
-        });
+    Path((owner, repo)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let link = repository_link(&owner, &repo)?;
+    let dependents = state
+        .database
+        .read()
+        .await
+        .repository_dependents(&link)
+        .await?;
+    Ok(axum::response::Json(dependents))
+}
 
-        async {}
-    })
+/// Contributors counts observed for the repository over time
+pub(crate) async fn history(
+    State(state): State<AppState>,
+    Path((owner, repo)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let link = repository_link(&owner, &repo)?;
+    let history = state
+        .database
+        .read()
+        .await
+        .repository_history(&link)
+        .await?;
+    Ok(axum::response::Json(history))
 }
 
-pub(crate) async fn dependencies(
-    state: AppState,
-    socket: Arc<Mutex<WebSocket>>,
-    who: SocketAddr,
-    link: Link,
-) {
-    let socket = socket;
-    // Handshake
-    if socket
-        .lock()
+/// Packages of every manifest of the repository, optionally of one ecosystem
+pub(crate) async fn packages(
+    State(state): State<AppState>,
+    Path((owner, repo)): Path<(String, String)>,
+    Query(query): Query<PackagesQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let link = repository_link(&owner, &repo)?;
+    let ecosystem = query
+        .ecosystem
+        .map(|ecosystem| ecosystem.parse::<Ecosystem>().unwrap().to_string());
+    let packages: Vec<Package> = state
+        .database
+        .read()
         .await
-        .send(Message::Ping(vec![1, 2, 3].into()))
+        .repository_packages(&link, ecosystem.as_deref())
+        .await?
+        .iter()
+        .map(Package::from)
+        .collect();
+    Ok(axum::response::Json(packages))
+}
+
+/// The known dependency graph of the repository, within the server crawl limits
+///
+/// Answers 202 with the queued crawl when the repository was never crawled.
+/// Otherwise a crawl is also queued when the repository is stale, and the
+/// crawl running meanwhile is given as `job_id`.
+pub(crate) async fn report(
+    State(state): State<AppState>,
+    Path((owner, repo)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let link = repository_link(&owner, &repo)?;
+    dependency_report(&state, &link).await
+}
+
+/// Report of the packages of an imported SBOM, see [`report`]
+pub(crate) async fn sbom_report(
+    State(state): State<AppState>,
+    Path((format, id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let link = sbom_link(&state, &format, &id).await?;
+    dependency_report(&state, &link).await
+}
+
+async fn dependency_report(state: &AppState, link: &RepositoryId) -> Result<Response, ApiError> {
+    let limits = state.config.crawl;
+    let graph = state
+        .database
+        .read()
         .await
-        .is_ok()
-    {
-        info!("Client {who} wants to connect");
-    } else {
-        warn!("Could not ping {who}");
-        return;
+        .dependency_graph(link, limits.max_depth as i32)
+        .await?;
+
+    let root_crawled = graph.nodes.first().is_some_and(|node| node.crawled);
+    if !root_crawled {
+        let job_id = queue_crawl(state, link).await?;
+        return Ok((
+            StatusCode::ACCEPTED,
+            [(LOCATION, report_location(link))],
+            axum::response::Json(CrawlQueued { job_id }),
+        )
+            .into_response());
     }
 
-    let Ok(link) = GitHubLink::try_from(link.link.clone()) else {
-        let _ = socket
-            .lock()
-            .await
-            .send(Message::Close(Some(CloseFrame {
-                code: axum::extract::ws::close_code::INVALID,
-                reason: Utf8Bytes::from("INVALID_LINK"),
-            })))
-            .await;
-        warn!("Invalid link: {}", link.link);
-        return;
+    let now = chrono::Utc::now();
+    let never_crawled: Vec<String> = graph
+        .nodes
+        .iter()
+        .filter(|node| !node.crawled && (node.depth as usize) < limits.max_depth)
+        .map(|node| node.path.clone())
+        .collect();
+    let stale = graph.nodes[0]
+        .valid_until
+        .is_some_and(|valid_until| valid_until < now);
+    let last_crawl = state
+        .database
+        .read()
+        .await
+        .last_crawl_job(&link.path(), limits.max_depth as i32, limits.max_nodes as i32)
+        .await?;
+    // The repositories a crawl stopped at stay never crawled, crawling again
+    // would stop at the same place
+    let job_id = match &last_crawl {
+        Some(job) if !job.done => Some(job.id),
+        _ if stale => Some(state.crawls.enqueue(link, limits, state).await?),
+        _ => None,
     };
-    if let Err(e) = link.fetch_contributors().await
-        && matches!(e, GitHubError::NotFound(_)) {
-            let _ = socket
-                .lock()
-                .await
-                .send(Message::Close(Some(CloseFrame {
-                    code: axum::extract::ws::close_code::INVALID,
-                    reason: Utf8Bytes::from("NOT_FOUND"),
-                })))
-                .await;
-            warn!("Repo does not exist: {}", link);
-            return;
-        }
 
-    info!("Client {who} connected");
+    let paths: Vec<String> = graph.nodes.iter().map(|node| node.path.clone()).collect();
+    let unique_contributors = state
+        .database
+        .read()
+        .await
+        .unique_contributors(&paths)
+        .await?;
+    let unresolved: Vec<UnresolvedDependency> = state
+        .database
+        .read()
+        .await
+        .unresolved_dependencies(&paths)
+        .await?
+        .into_iter()
+        .filter_map(|(parent, dependency)| UnresolvedDependency::new(parent, &dependency))
+        .collect();
 
-    let treated: Arc<RwLock<HashSet<GitHubLink>>> = Arc::new(RwLock::new(HashSet::new()));
+    Ok(axum::response::Json(DependencyReport {
+        repository: link.path(),
+        repositories: graph.nodes.len(),
+        contributors: graph
+            .nodes
+            .iter()
+            .filter_map(|node| node.contributors)
+            .map(|contributors| contributors as usize)
+            .sum(),
+        unique_contributors: (unique_contributors.repositories > 0).then_some(unique_contributors),
+        low_bus_factor: DependencyReport::low_bus_factor(&graph.nodes),
+        nodes: graph.nodes,
+        edges: graph.edges,
+        never_crawled,
+        unresolved_packages: UnresolvedDependency::distinct(&unresolved),
+        unresolved,
+        truncated: last_crawl
+            .filter(|job| job.done)
+            .and_then(|job| job.truncated),
+        job_id,
+    })
+    .into_response())
+}
 
-    match dependencies_iterative(link, treated, state, socket.clone()).await {
-        Ok(_) => {
-            let _ = socket.lock().await.send(Message::Close(None)).await;
-            // let _ = socket.into_inner().close().await;
-            info!("Client {who} end of session");
-        }
-        Err(RecDepError::Disconnected) => {
-            info!("Client {who} disconnected during session");
-        }
-    }
+/// The known dependency graph of the repository, within the server crawl
+/// limits, as a file in the requested format
+pub(crate) async fn export(
+    State(state): State<AppState>,
+    Path((owner, repo)): Path<(String, String)>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let link = repository_link(&owner, &repo)?;
+    graph_export(&state, &link, query).await
 }
 
-enum RecDepError {
-    Disconnected,
+/// Graph of the packages of an imported SBOM, see [`export`]
+pub(crate) async fn sbom_export(
+    State(state): State<AppState>,
+    Path((format, id)): Path<(String, String)>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let link = sbom_link(&state, &format, &id).await?;
+    graph_export(&state, &link, query).await
 }
 
-async fn dependencies_iterative(
-    initial_link: GitHubLink,
-    treated: Arc<RwLock<HashSet<GitHubLink>>>,
-    state: AppState,
-    socket: Arc<Mutex<WebSocket>>,
-) -> Result<(), RecDepError> {
-    let mut stack = vec![initial_link];
-
-    while let Some(link) = stack.pop() {
-        let mut dependencies: HashSet<GitHubLink> = HashSet::new();
-
-        if treated.write().await.insert(link.clone()) {
-            let contributors = cached_fetch(&link, state.clone()).await;
-            let chunk = ContributorsChunk::new(link.path(), contributors).to_string();
-            let chunk = format!("{chunk}\n");
-            if socket
-                .lock()
-                .await
-                .send(Message::Text(chunk.into()))
-                .await
-                .is_err()
-            {
-                return Err(RecDepError::Disconnected);
-            }
-            counter!("ws_sent").increment(1);
-        }
+async fn graph_export(
+    state: &AppState,
+    link: &RepositoryId,
+    query: ExportQuery,
+) -> Result<Response, ApiError> {
+    let format = match query.format {
+        Some(format) => format
+            .parse::<GraphFormat>()
+            .map_err(|_| ApiError::UnsupportedFormat(format))?,
+        None => GraphFormat::default(),
+    };
+    let graph = state
+        .database
+        .read()
+        .await
+        .dependency_graph(link, state.config.crawl.max_depth as i32)
+        .await?;
+    if !graph.nodes.first().is_some_and(|node| node.crawled) {
+        return Err(ApiError::NotCrawled(link.path()));
+    }
 
-        let mut dep_iterator: GitHubLinkDependencies = get_from_database(&link, state.clone())
-            .await
-            .and_then(|repo_info| dependencies_from_repository_info(&repo_info))
-            .map(GitHubLinkDependencies::from_precomputed)
-            .or(Some(link.dependencies()))
-            .expect("You fucked up.");
-
-        if dep_iterator.is_precomputed() {
-            info!("Using cached dependencies for {link}");
-            counter!("cache_hit", "status" => "hit", "from" => "dependencies").increment(1);
-        } else {
-            counter!("cache_hit", "status" => "miss", "from" => "dependencies").increment(1);
-        }
+    let graph = DependencyGraph {
+        root: link.path(),
+        nodes: graph
+            .nodes
+            .into_iter()
+            .map(|node| GraphNode {
+                path: node.path,
+                contributors: node.contributors.map(|contributors| contributors as usize),
+                depth: node.depth as usize,
+            })
+            .collect(),
+        edges: graph.edges,
+    };
+    let filename = format!("{}.{}", link.path().replace('/', "-"), format.extension());
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        graph.export(format),
+    )
+        .into_response())
+}
 
-        while let Some(dep) = dep_iterator.next().await {
-            if let Ok(l) = dep {
-                debug!("Found dependency {}", l.path());
-                dependencies.insert(l.clone());
-                if treated.write().await.insert(l.clone()) {
-                    debug!("{} not treated yet", l.path());
-                    let contributors = cached_fetch(&l, state.clone()).await;
-                    let chunk = ContributorsChunk::new(l.path(), contributors).to_string();
-                    let chunk = format!("{chunk}\n");
-                    if socket
-                        .lock()
-                        .await
-                        .send(Message::Text(chunk.into()))
-                        .await
-                        .is_err()
-                    {
-                        return Err(RecDepError::Disconnected);
-                    }
-                    counter!("ws_sent").increment(1);
-                } else {
-                    debug!("{} already treated", l.path());
-                }
-            } else {
-                error!("Dependency fetching error: {:?}", dep.unwrap_err());
-                counter!("errors").increment(1);
-            }
-        }
+/// Badge of the contributors and dependencies of the repository, for READMEs
+///
+/// A repository never crawled gets an "unknown" badge, not cached, and its
+/// crawl is queued unless the server is configured otherwise.
+pub(crate) async fn badge(
+    State(state): State<AppState>,
+    Path((owner, file)): Path<(String, String)>,
+    Query(query): Query<BadgeQuery>,
+) -> Result<Response, ApiError> {
+    let Some(repo) = file.strip_suffix(".svg") else {
+        return Err(ApiError::InvalidRepository(format!("{owner}/{file}")));
+    };
+    let link = repository_link(&owner, repo)?;
+    let style = match query.style {
+        Some(style) => style
+            .parse::<BadgeStyle>()
+            .map_err(ApiError::UnsupportedStyle)?,
+        None => BadgeStyle::default(),
+    };
+    let limits = state.config.crawl;
+    let graph = state
+        .database
+        .read()
+        .await
+        .dependency_graph(&link, limits.max_depth as i32)
+        .await?;
 
-        if !dep_iterator.is_precomputed() {
-            let vec: Vec<_> = dependencies.iter().cloned().collect();
-            set_dependencies_to_database(&link, &vec[..], state.clone()).await;
+    let (badge, cache_control) = if graph.nodes.first().is_some_and(|node| node.crawled) {
+        let contributors = graph
+            .nodes
+            .iter()
+            .filter_map(|node| node.contributors)
+            .map(|contributors| contributors as usize)
+            .sum();
+        (
+            Badge::known(query.label, contributors, graph.nodes.len() - 1),
+            format!("public, max-age={}", state.config.badge.max_age_sec),
+        )
+    } else {
+        // Never waits for the forge, the crawl tells whether the repository
+        // exists
+        if state.config.badge.crawl
+            && !cached_not_found(&link, &state).await
+            && let Err(e) = state.crawls.enqueue(&link, limits, &state).await
+        {
+            // The badge is still served
+            error!("Could not queue the crawl of {link} for its badge: {e}");
+            counter!("errors").increment(1);
         }
+        (
+            Badge::unknown(query.label),
+            format!("public, max-age={UNKNOWN_MAX_AGE_SEC}"),
+        )
+    };
 
-        stack.extend(dependencies);
-    }
-
-    Ok(())
+    Ok((
+        [
+            (CONTENT_TYPE, "image/svg+xml".to_string()),
+            (CACHE_CONTROL, cache_control),
+        ],
+        badge.render(style),
+    )
+        .into_response())
 }
 
-async fn cached_fetch(link: &GitHubLink, state: AppState) -> usize {
-    match get_from_cache(link, state.clone()).await {
-        Some(c) => {
-            counter!("cache_hit", "status" => "hit", "from" => "contributors").increment(1);
-            c
-        }
-        None => {
-            counter!("cache_hit", "status" => "miss", "from" => "contributors").increment(1);
-            let contributors = link.fetch_contributors().await.unwrap_or(1);
-            let _ = set_to_cache(link, contributors, state.clone()).await;
-            set_contributors_to_database(link, contributors, state).await;
-            // To be respectful with GitHub API rate limits
-            sleep(SLEEP_BETWEEN_FETCHES).await;
-            contributors
-        }
-    }
+/// Shared and unique transitive dependencies of two repositories, within the
+/// server crawl limits
+///
+/// The crawl of a repository never crawled is queued, its side stays empty
+/// until it is done.
+pub(crate) async fn compare(
+    State(state): State<AppState>,
+    Query(query): Query<CompareQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (left, left_nodes) = compared_repository(&state, &query.left).await?;
+    let (right, right_nodes) = compared_repository(&state, &query.right).await?;
+    Ok(axum::response::Json(DependencyComparison::new(
+        left,
+        &left_nodes,
+        right,
+        &right_nodes,
+    )))
 }
 
-async fn get_from_cache(link: &GitHubLink, state: AppState) -> Option<usize> {
-    let guard = state.cache.read().await;
-    match guard.get::<usize>(link.to_string().as_str()).await {
-        Ok(contributors) => {
-            debug!("Using cached contributors for {link}");
-            Some(contributors)
-        }
-        Err(_) => None,
-    }
+/// The repository of the path, `owner/repo` on GitHub or `host/owner/repo` on
+/// the other forges, and the nodes of its known dependency graph. Its crawl is
+/// queued if it was never crawled. The imported SBOMs are not compared.
+async fn compared_repository(
+    state: &AppState,
+    path: &str,
+) -> Result<(ComparedRepository, Vec<crate::database::models::GraphNode>), ApiError> {
+    let link = RepositoryId::from_path(path)
+        .ok()
+        .filter(|link| *link.forge() != Forge::Sbom)
+        .ok_or_else(|| ApiError::InvalidRepository(path.to_string()))?;
+    let nodes = state
+        .database
+        .read()
+        .await
+        .dependency_graph(&link, state.config.crawl.max_depth as i32)
+        .await?
+        .nodes;
+    let crawled = nodes.first().is_some_and(|node| node.crawled);
+    let job_id = if crawled {
+        None
+    } else {
+        Some(queue_crawl(state, &link).await?)
+    };
+    let repository = ComparedRepository {
+        repository: link.path(),
+        crawled,
+        job_id,
+    };
+    Ok((repository, nodes))
 }
 
-async fn set_to_cache(
-    link: &GitHubLink,
-    contributors: usize,
-    state: AppState,
-) -> Result<(), CacheError> {
-    insert_leaderboard(link, contributors, state.clone()).await;
-    let mut guard = state.cache.write().await;
-    let lifetime: Option<Duration>;
-    {
-        let mut rng = rand::rng();
-        lifetime = Some(Duration::from_secs(
-            rng.random_range(state.config.cache.ttl_sec_min..state.config.cache.ttl_sec_max) as u64,
-        ));
-    }
-    match guard
-        .set::<usize>(link.to_string().as_str(), &contributors, lifetime)
-        .await
-    {
-        Ok(_) => {
-            info!("Setting cached value for {link}:{contributors} {lifetime:?}");
-            Ok(())
-        }
-        Err(e) => {
-            error!("Error setting cached value for {link}:{contributors} {e}");
-            counter!("errors").increment(1);
-            Err(e)
-        }
+/// Queues the crawl of a repository, or returns the one already queued or
+/// running. The crawl tells whether the repository exists, those it found
+/// missing are refused until the mark expires.
+async fn queue_crawl(state: &AppState, link: &RepositoryId) -> Result<Uuid, ApiError> {
+    if cached_not_found(link, state).await {
+        return Err(ApiError::RepositoryNotFound(link.path()));
     }
+    Ok(state.crawls.enqueue(link, state.config.crawl, state).await?)
 }
 
-async fn insert_leaderboard(link: &GitHubLink, contributors: usize, state: AppState) {
-    debug!("Inserting {link} in leaderboard with weight {contributors}");
-    let _ = state
-        .cache
+/// Imports an SPDX or CycloneDX JSON SBOM, such as the one of a private
+/// project, and queues the crawl of the repositories of its packages.
+///
+/// Answers 202 with the queued crawl, its report being available at the
+/// `Location` once done, see [`sbom_report`].
+pub(crate) async fn import_sbom(
+    State(state): State<AppState>,
+    body: String,
+) -> Result<Response, ApiError> {
+    let sbom = Sbom::parse(&body).map_err(|e| ApiError::InvalidSbom(e.to_string()))?;
+    if sbom.packages.is_empty() {
+        return Err(ApiError::InvalidSbom("no packages".to_string()));
+    }
+    let link = RepositoryId::sbom(sbom.format, &Uuid::new_v4().simple().to_string())
+        .map_err(|e| ApiError::Unexpected(e.into()))?;
+    info!("Importing {} packages as {link}", sbom.packages.len());
+    // Resolved to their repositories by the crawl, see `fetch_dependencies`
+    state
+        .database
         .write()
         .await
-        .set_leaderboard(link.path().as_str(), contributors as i32)
-        .await;
+        .insert_repository_dependencies(&link, &sbom.packages)
+        .await?;
+    let job_id = state.crawls.enqueue(&link, state.config.crawl, &state).await?;
+    Ok((
+        StatusCode::ACCEPTED,
+        [(LOCATION, report_location(&link))],
+        axum::response::Json(SbomImported {
+            repository: link.path(),
+            packages: sbom.packages.len(),
+            job_id,
+        }),
+    )
+        .into_response())
 }
 
-async fn get_from_database(link: &GitHubLink, state: AppState) -> Option<RepositoryInfo> {
-    let guard = state.database.read().await;
-    match guard.repository_info(link).await {
-        Ok(info) => Some(info),
-        Err(DatabaseError::NotFound(_)) => None,
-        Err(e) => {
-            error!("Error getting repository {link} info from database: {e:?}");
-            counter!("errors").increment(1);
-            None
-        }
+/// The root of an imported SBOM, which must exist.
+async fn sbom_link(state: &AppState, format: &str, id: &str) -> Result<RepositoryId, ApiError> {
+    let link = format
+        .parse()
+        .and_then(|format| RepositoryId::sbom(format, id))
+        .map_err(|_| ApiError::RepositoryNotFound(format!("sbom/{format}/{id}")))?;
+    match state.database.read().await.repository_info(&link).await {
+        Ok(_) => Ok(link),
+        Err(DatabaseError::NotFound(_)) => Err(ApiError::RepositoryNotFound(link.path())),
+        Err(e) => Err(e.into()),
     }
 }
 
-fn dependencies_from_repository_info(info: &RepositoryInfo) -> Option<Vec<GitHubLink>> {
-    if let Some(dependencies) = &info.dependencies {
-        if dependencies.is_empty() || info.valid_until < chrono::Utc::now() {
-            return None;
-        }
-        return Some(
-            dependencies
-                .iter()
-                .map(|path| GitHubLink::try_from(format!("https://github.com/{path}")).unwrap())
-                .collect(),
-        );
+/// Where the report of the repository is served.
+fn report_location(link: &RepositoryId) -> String {
+    match link.forge() {
+        Forge::Sbom => format!("/api/{}/report", link.path()),
+        _ => format!("/api/repositories/{}/report", link.path()),
     }
-    None
 }
 
-async fn set_contributors_to_database(link: &GitHubLink, contributors: usize, state: AppState) {
-    info!("Saving {contributors} contributors for {link} in database");
-    let guard = state.database.write().await;
-    if let Err(e) = guard
-        .insert_repository_contributors(link, contributors as i32)
-        .await
-    {
-        error!("Error setting repository {link} contributors to database: {e}");
-        counter!("errors").increment(1);
-    };
+fn repository_link(owner: &str, repo: &str) -> Result<RepositoryId, ApiError> {
+    let path = format!("{owner}/{repo}");
+    RepositoryId::from_path(&path).map_err(|_| ApiError::InvalidRepository(path))
+}
+
+/// Websocket handler for the API
+///
+/// The messages follow the protocol negotiated through the `protocol` query
+/// parameter or the WebSocket subprotocol, see [`Protocol`].
+pub(crate) async fn ws_handler_dependencies(
+    State(state): State<AppState>,
+    ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    axum::extract::Query(link): axum::extract::Query<Link>,
+) -> Result<impl IntoResponse, ApiError> {
+    let ws = ws.protocols([JSON_SUBPROTOCOL]);
+    let protocol = Protocol::negotiate(link.protocol, ws.selected_protocol())
+        .map_err(ApiError::UnsupportedProtocol)?;
+    Ok(ws.on_upgrade(move |socket| {
+        tokio::spawn(async move {
+            let socket = Arc::new(Mutex::new(socket));
+            dependencies(state, Session::new(socket, protocol), addr, link).await;
+        });
+
+        async {}
+    }))
 }
 
-async fn set_dependencies_to_database(
-    link: &GitHubLink,
-    dependencies: &[GitHubLink],
+pub(crate) async fn dependencies(
     state: AppState,
+    mut session: Session,
+    who: SocketAddr,
+    link: Link,
 ) {
-    info!("Saving dependencies for {link} in database");
-    let guard = state.database.write().await;
-    if let Err(e) = guard
-        .insert_repository_dependencies(link, dependencies)
-        .await
+    // Handshake
+    if session.ping().await.is_ok() {
+        info!("Client {who} wants to connect");
+    } else {
+        warn!("Could not ping {who}");
+        return;
+    }
+
+    let result = match link
+        .session_id
+        .filter(|_| session.protocol() == Protocol::Json)
+        .and_then(|id| state.sessions.resume(id))
     {
-        error!("Error setting repository {link} total contributors to database: {e}");
-        counter!("errors").increment(1);
+        Some(journal) => {
+            info!("Client {who} resumed session {}", journal.id);
+            session = Session::resume(session.socket(), journal, link.last_seq);
+            session.run().await
+        }
+        None => start(&state, &mut session, who, link).await,
+    };
+
+    match result {
+        Ok(_) => {
+            info!("Client {who} end of session");
+        }
+        Err(RecDepError::Disconnected) => {
+            info!("Client {who} disconnected during session");
+            if session.protocol() == Protocol::Json {
+                let grace = Duration::from_secs(state.config.session_grace_sec);
+                state.sessions.detach(session.into_journal(), grace);
+            }
+        }
+    }
+}
+
+/// Starts a new session, following the crawl of the repository.
+async fn start(
+    state: &AppState,
+    session: &mut Session,
+    who: SocketAddr,
+    link: Link,
+) -> Result<(), RecDepError> {
+    let (max_depth, max_nodes) = (link.max_depth, link.max_nodes);
+    let Ok(link) = RepositoryId::try_from(link.link.clone()) else {
+        warn!("Invalid link: {}", link.link);
+        return session
+            .send(ServerMessage::Error {
+                code: ErrorCode::InvalidLink,
+                message: format!(
+                    "{} is not a link to a repository of a supported forge",
+                    link.link
+                ),
+            })
+            .await;
+    };
+    if let Err(e) = state.forges.fetch_contributors(&link).await
+        && matches!(e, GitHubError::NotFound(_)) {
+            warn!("Repo does not exist: {}", link);
+            return session
+                .send(ServerMessage::Error {
+                    code: ErrorCode::NotFound,
+                    message: format!("{} does not exist", link.path()),
+                })
+                .await;
+        }
+
+    info!("Client {who} connected");
+    let hello = ServerMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        repository: link.path(),
+        session_id: session.id(),
     };
+    session.send(hello).await?;
+
+    let limits = state.config.crawl.clamp(max_depth, max_nodes);
+    let subscription = state.crawls.subscribe(&link, limits, state).await;
+    session.forward(subscription).await
 }
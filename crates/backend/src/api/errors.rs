@@ -1,4 +1,5 @@
 use crate::cache::errors::CacheError;
+use crate::database::errors::DatabaseError;
 use axum::{http::StatusCode, response::IntoResponse};
 use log::error;
 use metrics::counter;
@@ -64,10 +65,26 @@ pub(crate) enum ApiError {
     IoError(#[from] std::io::Error),
     #[error(transparent)]
     Cache(#[from] CacheError),
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
     // #[error("Serialization error")]
     // Serde(#[from] serde::err),
     #[error("Unexpected Error")]
     Unexpected(#[from] anyhow::Error),
+    #[error("Unsupported WebSocket protocol version: {0}")]
+    UnsupportedProtocol(u32),
+    #[error("Invalid repository: {0}")]
+    InvalidRepository(String),
+    #[error("Repository not found: {0}")]
+    RepositoryNotFound(String),
+    #[error("Repository never crawled: {0}")]
+    NotCrawled(String),
+    #[error("Unsupported export format: {0}")]
+    UnsupportedFormat(String),
+    #[error("Unsupported badge style: {0}")]
+    UnsupportedStyle(String),
+    #[error("Invalid SBOM: {0}")]
+    InvalidSbom(String),
 }
 
 impl From<ApiError> for ApiErrorResponse {
@@ -76,6 +93,42 @@ impl From<ApiError> for ApiErrorResponse {
             ApiError::IoError(_) => ApiErrorResponse::unexpected(),
             ApiError::Unexpected(e) => e.into(),
             ApiError::Cache(e) => e.into(),
+            ApiError::Database(_) => ApiErrorResponse::unexpected(),
+            ApiError::UnsupportedProtocol(version) => ApiErrorResponse::new(
+                "UNSUPPORTED_PROTOCOL",
+                format!("Protocol version {version} is not supported."),
+                StatusCode::BAD_REQUEST,
+            ),
+            ApiError::InvalidRepository(path) => ApiErrorResponse::new(
+                "INVALID_LINK",
+                format!("{path} is not a GitHub repository."),
+                StatusCode::BAD_REQUEST,
+            ),
+            ApiError::RepositoryNotFound(path) => ApiErrorResponse::new(
+                "NOT_FOUND",
+                format!("{path} does not exist."),
+                StatusCode::NOT_FOUND,
+            ),
+            ApiError::NotCrawled(path) => ApiErrorResponse::new(
+                "NOT_CRAWLED",
+                format!("{path} was never crawled."),
+                StatusCode::NOT_FOUND,
+            ),
+            ApiError::UnsupportedFormat(format) => ApiErrorResponse::new(
+                "UNSUPPORTED_FORMAT",
+                format!("{format} is not a supported export format."),
+                StatusCode::BAD_REQUEST,
+            ),
+            ApiError::UnsupportedStyle(style) => ApiErrorResponse::new(
+                "UNSUPPORTED_STYLE",
+                format!("{style} is not a supported badge style."),
+                StatusCode::BAD_REQUEST,
+            ),
+            ApiError::InvalidSbom(reason) => ApiErrorResponse::new(
+                "INVALID_SBOM",
+                format!("Not an SPDX or CycloneDX JSON document: {reason}."),
+                StatusCode::BAD_REQUEST,
+            ),
         }
     }
 }
@@ -1,4 +1,7 @@
+mod badge;
 mod errors;
 mod endpoints;
+mod protocol;
+mod sessions;
 pub mod routes;
 pub mod state;
\ No newline at end of file
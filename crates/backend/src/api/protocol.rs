@@ -0,0 +1,277 @@
+use super::sessions::SessionJournal;
+use crate::crawler::Subscription;
+use crate::models::{ContributorsChunk, ServerMessage};
+use axum::extract::ws::{CloseFrame, Message, Utf8Bytes, WebSocket, close_code};
+use axum::http::HeaderValue;
+use metrics::counter;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::error;
+use uuid::Uuid;
+
+/// Version of the JSON protocol, sent in the `hello` message.
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+/// Subprotocol selecting the JSON protocol during the WebSocket handshake.
+pub(crate) const JSON_SUBPROTOCOL: &str = "hmc.v1";
+
+/// Format of the messages of a dependencies session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    /// Version 0: a `path:contributors` line per repository, failures are
+    /// reported as close reasons. Kept for the existing clients.
+    Legacy,
+    /// Version 1: tagged JSON [`ServerMessage`]s.
+    Json,
+}
+
+impl Protocol {
+    /// Negotiates the protocol from the `protocol` query parameter, or else from
+    /// the subprotocol selected during the handshake.
+    ///
+    /// Returns the requested version when it is not supported.
+    pub(crate) fn negotiate(
+        requested: Option<u32>,
+        subprotocol: Option<&HeaderValue>,
+    ) -> Result<Self, u32> {
+        match requested {
+            Some(0) => Ok(Protocol::Legacy),
+            Some(PROTOCOL_VERSION) => Ok(Protocol::Json),
+            Some(version) => Err(version),
+            None if subprotocol.is_some_and(|p| p == JSON_SUBPROTOCOL) => Ok(Protocol::Json),
+            None => Ok(Protocol::Legacy),
+        }
+    }
+}
+
+pub(crate) enum RecDepError {
+    Disconnected,
+}
+
+/// A message of the JSON protocol along with its sequence number in the
+/// session, which a client gives back to resume the session.
+#[derive(Serialize)]
+struct Sequenced<'a> {
+    seq: usize,
+    #[serde(flatten)]
+    message: &'a ServerMessage,
+}
+
+/// Sends the messages of a dependencies session in its negotiated protocol.
+pub(crate) struct Session {
+    socket: Arc<Mutex<WebSocket>>,
+    protocol: Protocol,
+    journal: SessionJournal,
+    /// Number of journal messages sent through this socket
+    delivered: usize,
+}
+
+impl Session {
+    pub(crate) fn new(socket: Arc<Mutex<WebSocket>>, protocol: Protocol) -> Self {
+        Self {
+            socket,
+            protocol,
+            journal: SessionJournal::new(),
+            delivered: 0,
+        }
+    }
+
+    /// Resumes a session on a new socket, after the message `last_seq` that
+    /// the client already received.
+    pub(crate) fn resume(
+        socket: Arc<Mutex<WebSocket>>,
+        journal: SessionJournal,
+        last_seq: Option<usize>,
+    ) -> Self {
+        let delivered = last_seq.map_or(0, |seq| seq + 1).min(journal.messages.len());
+        Self {
+            socket,
+            protocol: Protocol::Json,
+            journal,
+            delivered,
+        }
+    }
+
+    pub(crate) fn id(&self) -> Uuid {
+        self.journal.id
+    }
+
+    pub(crate) fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    pub(crate) fn socket(&self) -> Arc<Mutex<WebSocket>> {
+        self.socket.clone()
+    }
+
+    /// What is left of the session once its client disconnected.
+    pub(crate) fn into_journal(self) -> SessionJournal {
+        self.journal
+    }
+
+    pub(crate) async fn ping(&self) -> Result<(), RecDepError> {
+        self.socket
+            .lock()
+            .await
+            .send(Message::Ping(vec![1, 2, 3].into()))
+            .await
+            .map_err(|_| RecDepError::Disconnected)
+    }
+
+    /// Sends the message, and closes the session if it is the last one.
+    pub(crate) async fn send(&mut self, message: ServerMessage) -> Result<(), RecDepError> {
+        self.journal.messages.push(message);
+        self.flush().await.map(|_| ())
+    }
+
+    /// Forwards the messages of a crawl, starting with the ones it sent before
+    /// the subscription, and closes the session once the crawl ends.
+    pub(crate) async fn forward(&mut self, subscription: Subscription) -> Result<(), RecDepError> {
+        self.journal.messages.extend(subscription.backlog);
+        self.journal.receiver = Some(subscription.receiver);
+        self.run().await
+    }
+
+    /// Sends what the client did not receive yet, then forwards the crawl
+    /// messages until it ends.
+    pub(crate) async fn run(&mut self) -> Result<(), RecDepError> {
+        if self.flush().await? {
+            return Ok(());
+        }
+        while let Some(receiver) = self.journal.receiver.as_mut() {
+            match receiver.recv().await {
+                Some(message) => {
+                    self.journal.messages.push(message);
+                    if self.flush().await? {
+                        return Ok(());
+                    }
+                }
+                None => self.journal.receiver = None,
+            }
+        }
+        // The crawl stopped without a last message
+        let _ = self.socket.lock().await.send(Message::Close(None)).await;
+        Ok(())
+    }
+
+    /// Sends the journal messages not delivered yet, returning whether one of
+    /// them ended the session.
+    async fn flush(&mut self) -> Result<bool, RecDepError> {
+        while let Some(message) = self.journal.messages.get(self.delivered).cloned() {
+            self.write(self.delivered, &message).await?;
+            self.delivered += 1;
+            if matches!(
+                message,
+                ServerMessage::Done { .. } | ServerMessage::Error { .. }
+            ) {
+                self.close(&message).await;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Writes the message to the socket, the legacy protocol only knows about
+    /// repositories.
+    async fn write(&self, seq: usize, message: &ServerMessage) -> Result<(), RecDepError> {
+        let text = match (self.protocol, message) {
+            (
+                Protocol::Legacy,
+                ServerMessage::Repository {
+                    path, contributors, ..
+                },
+            ) => {
+                format!("{}\n", ContributorsChunk::new(path.clone(), *contributors))
+            }
+            (Protocol::Legacy, _) => return Ok(()),
+            (Protocol::Json, message) => match serde_json::to_string(&Sequenced { seq, message }) {
+                Ok(text) => text,
+                Err(e) => {
+                    error!("Could not serialize {message:?}: {e}");
+                    counter!("errors").increment(1);
+                    return Ok(());
+                }
+            },
+        };
+        self.socket
+            .lock()
+            .await
+            .send(Message::Text(text.into()))
+            .await
+            .map_err(|_| RecDepError::Disconnected)?;
+        counter!("ws_sent").increment(1);
+        Ok(())
+    }
+
+    /// Closes the session after its last message. An error is also given as
+    /// the close reason, which is all the legacy protocol receives.
+    async fn close(&self, message: &ServerMessage) {
+        let frame = match message {
+            ServerMessage::Error { code, .. } => Some(CloseFrame {
+                code: close_code::INVALID,
+                reason: Utf8Bytes::from(code.to_string()),
+            }),
+            _ => None,
+        };
+        let _ = self.socket.lock().await.send(Message::Close(frame)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ErrorCode;
+
+    #[test]
+    fn test_negotiate() {
+        let json = HeaderValue::from_static(JSON_SUBPROTOCOL);
+        let other = HeaderValue::from_static("other");
+
+        assert_eq!(Protocol::negotiate(None, None), Ok(Protocol::Legacy));
+        assert_eq!(
+            Protocol::negotiate(None, Some(&other)),
+            Ok(Protocol::Legacy)
+        );
+        assert_eq!(Protocol::negotiate(None, Some(&json)), Ok(Protocol::Json));
+        assert_eq!(Protocol::negotiate(Some(1), None), Ok(Protocol::Json));
+        assert_eq!(
+            Protocol::negotiate(Some(0), Some(&json)),
+            Ok(Protocol::Legacy)
+        );
+        assert_eq!(Protocol::negotiate(Some(42), None), Err(42));
+    }
+
+    #[test]
+    fn test_messages_are_tagged() {
+        let message = ServerMessage::Repository {
+            path: "tokio-rs/tokio".to_string(),
+            contributors: 842,
+            depth: 1,
+            humans: None,
+            bots: None,
+            bus_factor: None,
+            top_share: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"type":"repository","path":"tokio-rs/tokio","contributors":842,"depth":1}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Sequenced {
+                seq: 3,
+                message: &message
+            })
+            .unwrap(),
+            r#"{"seq":3,"type":"repository","path":"tokio-rs/tokio","contributors":842,"depth":1}"#
+        );
+
+        let message = ServerMessage::Error {
+            code: ErrorCode::NotFound,
+            message: "Repository not found".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"type":"error","code":"NOT_FOUND","message":"Repository not found"}"#
+        );
+    }
+}
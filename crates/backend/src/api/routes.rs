@@ -1,6 +1,10 @@
 use crate::{api::endpoints::ping, api::state::AppState};
 
-use axum::{Router, routing::get};
+use axum::extract::DefaultBodyLimit;
+use axum::{
+    Router,
+    routing::{get, post},
+};
 use axum_prometheus::metrics_exporter_prometheus::PrometheusHandle;
 use std::time::Duration;
 use std::{future::ready, sync::Arc};
@@ -14,9 +18,14 @@ use tower_http::{
 };
 use tracing::info;
 
-use super::endpoints::{leaderboard, ws_handler_dependencies};
+use super::endpoints::{
+    badge, compare, dependents, export, history, import_sbom, leaderboard, packages, report,
+    sbom_export, sbom_report, ws_handler_dependencies,
+};
 
 const TIMEOUT_SEC: u64 = 20;
+/// SBOMs of large projects list thousands of packages
+const SBOM_MAX_BYTES: usize = 16 * 1024 * 1024;
 
 /// Public routes that are exposed to the world
 pub(crate) fn public_routes(app_state: &AppState) -> Router {
@@ -35,6 +44,7 @@ pub(crate) fn public_routes(app_state: &AppState) -> Router {
         }
     });
 
+    #[allow(deprecated)]
     let middleware_service = ServiceBuilder::new()
         // Add logging from axum
         .layer(TraceLayer::new_for_http())
@@ -53,6 +63,22 @@ pub(crate) fn public_routes(app_state: &AppState) -> Router {
         .route("/", get(ping))
         .route("/api/dependencies", get(ws_handler_dependencies))
         .route("/api/leaderboard", get(leaderboard))
+        .route("/api/badge/{owner}/{file}", get(badge))
+        .route("/api/compare", get(compare))
+        .route(
+            "/api/repositories/{owner}/{repo}/dependents",
+            get(dependents),
+        )
+        .route("/api/repositories/{owner}/{repo}/history", get(history))
+        .route("/api/repositories/{owner}/{repo}/packages", get(packages))
+        .route("/api/repositories/{owner}/{repo}/report", get(report))
+        .route("/api/repositories/{owner}/{repo}/export", get(export))
+        .route(
+            "/api/sbom",
+            post(import_sbom).layer(DefaultBodyLimit::max(SBOM_MAX_BYTES)),
+        )
+        .route("/api/sbom/{format}/{id}/report", get(sbom_report))
+        .route("/api/sbom/{format}/{id}/export", get(sbom_export))
         .layer(middleware_service)
         .with_state(app_state.clone())
 }
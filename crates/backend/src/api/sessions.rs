@@ -0,0 +1,102 @@
+use crate::models::ServerMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::sleep;
+use tracing::info;
+use uuid::Uuid;
+
+/// Everything a dependencies session sent or has to send, the sequence number
+/// of a message being its index.
+pub(crate) struct SessionJournal {
+    pub(crate) id: Uuid,
+    pub(crate) messages: Vec<ServerMessage>,
+    /// The next messages of the crawl, None once it ended
+    pub(crate) receiver: Option<UnboundedReceiver<ServerMessage>>,
+}
+
+impl SessionJournal {
+    pub(crate) fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            messages: vec![],
+            receiver: None,
+        }
+    }
+}
+
+/// The sessions whose client disconnected, kept for a grace period so that the
+/// client can reconnect and receive only what it missed.
+///
+/// The crawl messages keep piling up in the journal receiver meanwhile.
+#[derive(Default)]
+pub(crate) struct SessionRegistry {
+    detached: Mutex<HashMap<Uuid, (SessionJournal, Instant)>>,
+}
+
+impl SessionRegistry {
+    /// Keeps the session until it is resumed or the grace period ends.
+    pub(crate) fn detach(self: &Arc<Self>, journal: SessionJournal, grace: Duration) {
+        let id = journal.id;
+        self.insert(journal);
+        let sessions = self.clone();
+        tokio::spawn(async move {
+            sleep(grace).await;
+            sessions.expire(id, grace);
+        });
+    }
+
+    /// Takes back a detached session, if it did not expire.
+    pub(crate) fn resume(&self, id: Uuid) -> Option<SessionJournal> {
+        self.detached
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .map(|(journal, _)| journal)
+    }
+
+    fn insert(&self, journal: SessionJournal) {
+        self.detached
+            .lock()
+            .unwrap()
+            .insert(journal.id, (journal, Instant::now()));
+    }
+
+    /// Forgets the session if it is still detached since the grace period,
+    /// it may have been resumed and detached again meanwhile.
+    fn expire(&self, id: Uuid, grace: Duration) {
+        let mut detached = self.detached.lock().unwrap();
+        if detached
+            .get(&id)
+            .is_some_and(|(_, since)| since.elapsed() >= grace)
+        {
+            detached.remove(&id);
+            info!("Session {id} expired");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detached_sessions_expire() {
+        let sessions = SessionRegistry::default();
+        let journal = SessionJournal::new();
+        let id = journal.id;
+
+        sessions.insert(journal);
+        sessions.expire(id, Duration::from_secs(60));
+        assert!(sessions.resume(id).is_some());
+        assert!(sessions.resume(id).is_none());
+
+        sessions.insert(SessionJournal {
+            id,
+            ..SessionJournal::new()
+        });
+        sessions.expire(id, Duration::ZERO);
+        assert!(sessions.resume(id).is_none());
+    }
+}
@@ -1,9 +1,12 @@
+use crate::crawler::{CrawlRegistry, InFlight};
 use crate::{cache::RedisCache, config::Config, database::database::PostgresDatabase};
 
+use github_scrapper::{Forges, GitHubClient, ManifestResolver};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use super::errors::ApiError;
+use super::sessions::SessionRegistry;
 
 // Notes:
 // dyn trait are not supported for async functions.
@@ -46,6 +49,14 @@ pub(crate) struct AppState {
     pub cache: Arc<RwLock<RedisCache>>,
     pub config: Arc<Config>,
     pub database: Arc<RwLock<PostgresDatabase>>,
+    pub github: Arc<GitHubClient>,
+    /// Counts the contributors of the repositories of every forge, sharing [`AppState::github`]
+    pub forges: Arc<Forges>,
+    /// Lists the dependencies of the repositories from their manifests
+    pub resolver: Arc<ManifestResolver>,
+    pub crawls: Arc<CrawlRegistry>,
+    pub in_flight: Arc<InFlight>,
+    pub sessions: Arc<SessionRegistry>,
 }
 
 impl AppState {
@@ -54,10 +65,21 @@ impl AppState {
         cache: RedisCache,
         database: PostgresDatabase,
     ) -> Result<Self, ApiError> {
+        let github = Arc::new(
+            GitHubClient::with_tokens(config.github.fetch_mode, config.github.tokens.clone())
+                .with_web_url(&config.github.web_url)
+                .with_api_url(&config.github.api_url),
+        );
         Ok(Self {
             cache: Arc::new(RwLock::new(cache)),
             config: Arc::new(config.clone()),
             database: Arc::new(RwLock::new(database)),
+            forges: Arc::new(Forges::new(github.clone())),
+            resolver: Arc::new(ManifestResolver::default()),
+            github,
+            crawls: Arc::new(CrawlRegistry::default()),
+            in_flight: Arc::new(InFlight::default()),
+            sessions: Arc::new(SessionRegistry::default()),
         })
     }
 }
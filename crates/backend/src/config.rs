@@ -1,5 +1,6 @@
 use crate::errors::ConfigParsingError;
 use clap::Parser;
+use github_scrapper::{BotClassifier, FetchMode, Forge, GITHUB_API_URL, GITHUB_WEB_URL, GitHubToken, RateLimiterConfig};
 use log::warn;
 use serde::Deserialize;
 use std::fs;
@@ -28,6 +29,19 @@ const DEFAULT_CONFIG_FILE_PATH: &str = ".config.yaml";
 
 const DEFAULT_LEADERBOARD_SIZE: usize = 500;
 
+const DEFAULT_GITHUB_FETCH_MODE: FetchMode = FetchMode::Html;
+const DEFAULT_GITHUB_REQUESTS_PER_SECOND: f64 = 4.0 / 3.0;
+const DEFAULT_GITHUB_BURST: u32 = 5;
+const DEFAULT_GITHUB_MAX_RETRIES: u32 = 4;
+
+const DEFAULT_CRAWL_MAX_DEPTH: usize = 10;
+const DEFAULT_CRAWL_MAX_NODES: usize = 5000;
+const DEFAULT_CRAWL_WORKERS: usize = 2;
+
+const DEFAULT_SESSION_GRACE_SEC: u64 = 120;
+
+const DEFAULT_BADGE_MAX_AGE_SEC: u64 = 3600;
+
 /* ======================================================================================
 FULL CONFIG FROM USER
 ====================================================================================== */
@@ -112,6 +126,115 @@ struct CliConfig {
     #[arg(long, env, default_value_t = DEFAULT_DATABASE_PASSWORD.to_string())]
     pub(crate) database_password: String,
 
+    /* ===============
+    GITHUB
+    ================ */
+    /// How GitHub is queried: "html" scrapes the public pages, "api" uses the
+    /// official REST and GraphQL APIs, "auto" uses the APIs and scrapes the pages
+    /// when they fail.
+    #[arg(long, env, default_value_t = DEFAULT_GITHUB_FETCH_MODE)]
+    pub(crate) github_fetch_mode: FetchMode,
+
+    /// GitHub website root URL
+    #[arg(long, env, default_value_t = GITHUB_WEB_URL.to_string())]
+    pub(crate) github_web_url: String,
+
+    /// GitHub API root URL
+    #[arg(long, env, default_value_t = GITHUB_API_URL.to_string())]
+    pub(crate) github_api_url: String,
+
+    /// Sustained number of requests per second sent to GitHub, shared by all
+    /// the connections.
+    #[arg(long, env, default_value_t = DEFAULT_GITHUB_REQUESTS_PER_SECOND)]
+    pub(crate) github_requests_per_second: f64,
+
+    /// Number of requests that can be sent to GitHub at once after a quiet period
+    #[arg(long, env, default_value_t = DEFAULT_GITHUB_BURST)]
+    pub(crate) github_burst: u32,
+
+    /// Number of retries of a request rate limited by GitHub
+    #[arg(long, env, default_value_t = DEFAULT_GITHUB_MAX_RETRIES)]
+    pub(crate) github_max_retries: u32,
+
+    /// Comma separated GitHub tokens authenticating the API requests, the one
+    /// with the most quota left is used for each request.
+    #[arg(long, env, value_delimiter = ',', hide_env_values = true)]
+    #[serde(default)]
+    pub(crate) github_tokens: Vec<GitHubToken>,
+
+    /// Also fetch who the contributors are and their commits through the API, to
+    /// count the distinct people of a dependency graph and the bus factor of each
+    /// repository. Costs one API request per 100 contributors.
+    #[arg(long, env, default_value_t = false)]
+    pub(crate) github_fetch_identities: bool,
+
+    /// Read the dependencies of a repository from its manifests when its
+    /// dependency graph lists none, resolving their packages through crates.io,
+    /// npm, PyPI and the Go module proxy. Costs one request per package.
+    #[arg(long, env, default_value_t = false)]
+    pub(crate) github_manifests: bool,
+
+    /// Comma separated suffixes of the logins of bots, in addition to `[bot]`.
+    /// Bots are counted apart from the people when the identities are fetched.
+    #[arg(long, env, value_delimiter = ',')]
+    #[serde(default)]
+    pub(crate) github_bot_suffixes: Vec<String>,
+
+    /// Comma separated logins of bots, in addition to the well known ones
+    #[arg(long, env, value_delimiter = ',')]
+    #[serde(default)]
+    pub(crate) github_bot_logins: Vec<String>,
+
+    /* ===============
+    FORGES
+    ================ */
+    /// Comma separated hosts of self-hosted GitLab instances whose repositories
+    /// are counted, in addition to gitlab.com and the well known ones
+    #[arg(long, env, value_delimiter = ',')]
+    #[serde(default)]
+    pub(crate) forge_gitlab_hosts: Vec<String>,
+
+    /// Comma separated hosts of self-hosted Gitea or Forgejo instances whose
+    /// repositories are counted, in addition to codeberg.org and gitea.com
+    #[arg(long, env, value_delimiter = ',')]
+    #[serde(default)]
+    pub(crate) forge_gitea_hosts: Vec<String>,
+
+    /* ===============
+    CRAWL
+    ================ */
+    /// Highest depth of a dependency crawl, requests asking for more are clamped
+    #[arg(long, env, default_value_t = DEFAULT_CRAWL_MAX_DEPTH)]
+    pub(crate) crawl_max_depth: usize,
+
+    /// Highest number of repositories of a dependency crawl, requests asking for
+    /// more are clamped
+    #[arg(long, env, default_value_t = DEFAULT_CRAWL_MAX_NODES)]
+    pub(crate) crawl_max_nodes: usize,
+
+    /// Number of crawls run at the same time, the other ones wait in the queue
+    #[arg(long, env, default_value_t = DEFAULT_CRAWL_WORKERS)]
+    pub(crate) crawl_workers: usize,
+
+    /* ===============
+    SESSIONS
+    ================ */
+    /// Seconds during which a disconnected dependencies session can be resumed
+    #[arg(long, env, default_value_t = DEFAULT_SESSION_GRACE_SEC)]
+    pub(crate) session_grace_sec: u64,
+
+    /* ===============
+    BADGES
+    ================ */
+    /// Seconds during which the clients may cache the badge of a crawled repository
+    #[arg(long, env, default_value_t = DEFAULT_BADGE_MAX_AGE_SEC)]
+    pub(crate) badge_max_age_sec: u64,
+
+    /// Do not queue the crawl of the repositories never crawled when their badge
+    /// is requested
+    #[arg(long, env, default_value_t = false)]
+    pub(crate) badge_no_crawl: bool,
+
     /* ===============
     PROMETHEUS
     ================ */
@@ -182,6 +305,46 @@ pub(crate) struct Cache {
     pub(crate) ttl_sec_max: usize,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct GitHubConfig {
+    pub(crate) fetch_mode: FetchMode,
+    pub(crate) web_url: String,
+    pub(crate) api_url: String,
+    pub(crate) rate_limit: RateLimiterConfig,
+    pub(crate) tokens: Vec<GitHubToken>,
+    /// Whether the identities and commits of the contributors are fetched along
+    /// with their number
+    pub(crate) fetch_identities: bool,
+    /// Whether the manifests are read when the dependency graph lists nothing
+    pub(crate) manifests: bool,
+    /// Tells the bots apart among the identities
+    pub(crate) bots: BotClassifier,
+}
+
+/// Limits of a dependency crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct CrawlConfig {
+    pub(crate) max_depth: usize,
+    pub(crate) max_nodes: usize,
+}
+
+impl CrawlConfig {
+    /// The limits requested by a client, within the server ones.
+    pub(crate) fn clamp(&self, max_depth: Option<usize>, max_nodes: Option<usize>) -> Self {
+        Self {
+            max_depth: max_depth.unwrap_or(self.max_depth).min(self.max_depth),
+            max_nodes: max_nodes.unwrap_or(self.max_nodes).clamp(1, self.max_nodes),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BadgeConfig {
+    pub(crate) max_age_sec: u64,
+    /// Whether the badge of a repository never crawled queues its crawl
+    pub(crate) crawl: bool,
+}
+
 type ServerBindingConfig = BindingConfig;
 type PrometheusConfig = BindingConfig;
 
@@ -206,6 +369,13 @@ pub(crate) struct Config {
     pub(crate) server: ServerBindingConfig,
     pub(crate) cache: Cache,
     pub(crate) postgres: PostgresConfig,
+    pub(crate) github: GitHubConfig,
+    /// Self-hosted instances whose repositories are counted, see [`Forge::allow`]
+    pub(crate) forges: Vec<Forge>,
+    pub(crate) crawl: CrawlConfig,
+    pub(crate) crawl_workers: usize,
+    pub(crate) session_grace_sec: u64,
+    pub(crate) badge: BadgeConfig,
     pub(crate) prometheus: Option<PrometheusConfig>,
     pub(crate) leaderboard_size: usize,
 }
@@ -214,6 +384,15 @@ impl Config {
     pub(crate) fn parse() -> Result<Self, ConfigParsingError> {
         Self::try_from(CliConfig::parse_with_file()?)
     }
+
+    /// The configuration told by the environment only, for the tests needing
+    /// the database or the cache.
+    #[cfg(test)]
+    pub(crate) fn from_env() -> Result<Self, ConfigParsingError> {
+        let config = CliConfig::try_parse_from(["backend"])
+            .map_err(|e| ConfigParsingError::Error(e.to_string()))?;
+        Self::try_from(config)
+    }
 }
 
 impl TryFrom<CliConfig> for Config {
@@ -252,6 +431,39 @@ impl TryFrom<CliConfig> for Config {
                 user: value.database_user,
                 password: value.database_password,
             },
+            github: GitHubConfig {
+                fetch_mode: value.github_fetch_mode,
+                web_url: value.github_web_url,
+                api_url: value.github_api_url,
+                rate_limit: RateLimiterConfig {
+                    requests_per_second: value.github_requests_per_second,
+                    burst: value.github_burst,
+                    max_retries: value.github_max_retries,
+                    ..Default::default()
+                },
+                tokens: value.github_tokens,
+                fetch_identities: value.github_fetch_identities,
+                manifests: value.github_manifests,
+                bots: BotClassifier::default()
+                    .with_suffixes(value.github_bot_suffixes)
+                    .with_logins(value.github_bot_logins),
+            },
+            forges: value
+                .forge_gitlab_hosts
+                .into_iter()
+                .map(Forge::GitLab)
+                .chain(value.forge_gitea_hosts.into_iter().map(Forge::Gitea))
+                .collect(),
+            crawl: CrawlConfig {
+                max_depth: value.crawl_max_depth,
+                max_nodes: value.crawl_max_nodes,
+            },
+            crawl_workers: value.crawl_workers,
+            session_grace_sec: value.session_grace_sec,
+            badge: BadgeConfig {
+                max_age_sec: value.badge_max_age_sec,
+                crawl: !value.badge_no_crawl,
+            },
             leaderboard_size: value.leaderboard_size,
             prometheus,
         })
@@ -271,6 +483,18 @@ impl Config {
             ));
         }
 
+        if cli_config.github_requests_per_second <= 0.0 || cli_config.github_burst == 0 {
+            return Err(ConfigParsingError::Error(
+                "GitHub requests per second and burst must be positive".to_string(),
+            ));
+        }
+
+        if cli_config.crawl_max_nodes == 0 || cli_config.crawl_workers == 0 {
+            return Err(ConfigParsingError::Error(
+                "Crawl max nodes and workers must be positive".to_string(),
+            ));
+        }
+
         if cli_config.no_prometheus
             && (cli_config.prometheus_ip != DEFAULT_PROMETHEUS_IP
                 || cli_config.prometheus_port != DEFAULT_PROMETHEUS_PORT)
@@ -304,6 +528,25 @@ mod test {
                 database_name: DEFAULT_DATABASE_NAME.to_string(),
                 database_user: DEFAULT_DATABASE_USER.to_string(),
                 database_password: DEFAULT_DATABASE_PASSWORD.to_string(),
+                github_fetch_mode: DEFAULT_GITHUB_FETCH_MODE,
+                github_web_url: GITHUB_WEB_URL.to_string(),
+                github_api_url: GITHUB_API_URL.to_string(),
+                github_requests_per_second: DEFAULT_GITHUB_REQUESTS_PER_SECOND,
+                github_burst: DEFAULT_GITHUB_BURST,
+                github_max_retries: DEFAULT_GITHUB_MAX_RETRIES,
+                github_tokens: vec![],
+                github_fetch_identities: false,
+                github_manifests: false,
+                github_bot_suffixes: vec![],
+                github_bot_logins: vec![],
+                forge_gitlab_hosts: vec![],
+                forge_gitea_hosts: vec![],
+                crawl_max_depth: DEFAULT_CRAWL_MAX_DEPTH,
+                crawl_max_nodes: DEFAULT_CRAWL_MAX_NODES,
+                crawl_workers: DEFAULT_CRAWL_WORKERS,
+                session_grace_sec: DEFAULT_SESSION_GRACE_SEC,
+                badge_max_age_sec: DEFAULT_BADGE_MAX_AGE_SEC,
+                badge_no_crawl: false,
                 prometheus_ip: DEFAULT_PROMETHEUS_IP,
                 prometheus_port: DEFAULT_PROMETHEUS_PORT,
                 no_prometheus: false,
@@ -340,4 +583,62 @@ mod test {
         assert!(config.is_ok());
         assert!(config.unwrap().prometheus.is_none());
     }
+
+    #[test]
+    #[allow(
+        clippy::field_reassign_with_default,
+        reason = "Allow modifying attributes for test case"
+    )]
+    fn test_validate_github_rate_limit() {
+        let mut cli_config = CliConfig::default();
+        cli_config.github_requests_per_second = 0.0;
+
+        assert!(Config::validate(&cli_config).is_err());
+    }
+
+    #[test]
+    fn test_github_tokens_are_not_displayed() {
+        let cli_config =
+            CliConfig::try_parse_from(["backend", "--github-tokens", "ghp_first,ghp_second"])
+                .unwrap();
+        assert_eq!(cli_config.github_tokens.len(), 2);
+
+        let config = Config::try_from(cli_config).unwrap();
+        assert!(!format!("{config:?}").contains("ghp_"));
+    }
+
+    #[test]
+    fn test_forge_hosts() {
+        let cli_config = CliConfig::try_parse_from([
+            "backend",
+            "--forge-gitlab-hosts",
+            "gitlab.gnome.org,gitlab.example.org",
+            "--forge-gitea-hosts",
+            "git.example.org",
+        ])
+        .unwrap();
+
+        let config = Config::try_from(cli_config).unwrap();
+        assert_eq!(
+            config.forges,
+            vec![
+                Forge::GitLab("gitlab.gnome.org".to_string()),
+                Forge::GitLab("gitlab.example.org".to_string()),
+                Forge::Gitea("git.example.org".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crawl_limits_are_clamped() {
+        let crawl = CrawlConfig {
+            max_depth: 3,
+            max_nodes: 100,
+        };
+
+        assert_eq!(crawl.clamp(None, None), crawl);
+        assert_eq!(crawl.clamp(Some(1), Some(10_000)).max_depth, 1);
+        assert_eq!(crawl.clamp(Some(1), Some(10_000)).max_nodes, 100);
+        assert_eq!(crawl.clamp(Some(0), Some(0)).max_nodes, 1);
+    }
 }
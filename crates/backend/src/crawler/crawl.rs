@@ -0,0 +1,889 @@
+use super::registry::CrawlHandle;
+use super::single_flight::SingleFlight;
+use crate::api::state::AppState;
+use crate::cache::{Cache, CacheError};
+use crate::config::CrawlConfig;
+use crate::database::errors::DatabaseError;
+use crate::database::Database;
+use crate::database::models::{CrawlCheckpoint, CrawlJob, RepositoryInfo, UniqueContributors};
+use crate::models::{ErrorCode, ServerMessage, UnresolvedDependency};
+use github_scrapper::{
+    Concentration, Dependency, Forge, GitHubError, GitHubLink, GitHubLinkDependencies,
+    RepositoryId,
+};
+use metrics::counter;
+use rand::Rng;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// How long a repository missing from its forge is remembered as such.
+const NOT_FOUND_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// The dependencies of a repository, and the errors met while listing them.
+#[derive(Debug, Default)]
+pub(crate) struct FetchedDependencies {
+    /// Repositories the packages resolved to, each listed once
+    pub(crate) links: Vec<RepositoryId>,
+    /// Every package of every manifest, unknown when the dependencies were
+    /// saved before the packages were
+    pub(crate) packages: Vec<Dependency>,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Fetches shared by all the crawls, so that a repository is only fetched
+/// once at a time whatever the number of crawls needing it.
+#[derive(Default)]
+pub(crate) struct InFlight {
+    contributors: SingleFlight<RepositoryId, Option<usize>>,
+    dependencies: SingleFlight<RepositoryId, Arc<FetchedDependencies>>,
+}
+
+/// State of a traversal, saved along with its messages after each explored
+/// repository.
+struct Traversal {
+    /// Repositories left to explore, with their depth, the closest to the root
+    /// first so that each repository is reached at its shortest depth
+    queue: VecDeque<(RepositoryId, usize)>,
+    treated: HashSet<RepositoryId>,
+    processed: usize,
+    contributors: usize,
+    /// Whether the crawl stopped on its limits before the end of the graph
+    truncated: bool,
+}
+
+impl Traversal {
+    fn new(link: RepositoryId) -> Self {
+        Self {
+            queue: VecDeque::from([(link, 0)]),
+            treated: HashSet::new(),
+            processed: 0,
+            contributors: 0,
+            truncated: false,
+        }
+    }
+
+    fn from_checkpoint(checkpoint: &CrawlCheckpoint) -> Self {
+        let link = |path: &String| RepositoryId::from_path(path).ok();
+        Self {
+            queue: checkpoint
+                .queue
+                .iter()
+                .filter_map(|(path, depth)| Some((link(path)?, *depth)))
+                .collect(),
+            treated: checkpoint.treated.iter().filter_map(link).collect(),
+            processed: checkpoint.processed,
+            contributors: checkpoint.contributors,
+            truncated: checkpoint.truncated,
+        }
+    }
+
+    fn checkpoint(&self) -> CrawlCheckpoint {
+        CrawlCheckpoint {
+            queue: self
+                .queue
+                .iter()
+                .map(|(link, depth)| (link.path(), *depth))
+                .collect(),
+            treated: self.treated.iter().map(RepositoryId::path).collect(),
+            processed: self.processed,
+            contributors: self.contributors,
+            truncated: self.truncated,
+        }
+    }
+}
+
+/// Crawls the dependency graph of the repository, publishing what it finds
+/// until it is done, and tells whether it stopped on its limits.
+///
+/// The crawl of a job resumes from its checkpoint, and saves a new one after
+/// each explored repository.
+pub(crate) async fn crawl(
+    link: RepositoryId,
+    limits: CrawlConfig,
+    job: Option<&CrawlJob>,
+    state: &AppState,
+    handle: &CrawlHandle,
+) -> bool {
+    let traversal = match job.and_then(|job| job.checkpoint.as_ref()) {
+        Some(checkpoint) => {
+            info!("Resuming the crawl of {link} with {limits:?}");
+            Traversal::from_checkpoint(checkpoint)
+        }
+        None => {
+            // Checked by the crawl rather than by the requests queuing it, as
+            // the contributors of the root are fetched anyway
+            if cached_fetch(&link, state).await.is_none() {
+                warn!("Not crawling {link}, it does not exist");
+                handle.publish(ServerMessage::Error {
+                    code: ErrorCode::NotFound,
+                    message: format!("{} does not exist", link.path()),
+                });
+                if let Some(job) = job {
+                    save_checkpoint(job, &Traversal::new(link), state, handle).await;
+                }
+                return false;
+            }
+            info!("Crawling {link} with {limits:?}");
+            Traversal::new(link.clone())
+        }
+    };
+    let traversal = dependencies_iterative(traversal, limits, state, handle, job).await;
+    let paths: Vec<String> = traversal.treated.iter().map(RepositoryId::path).collect();
+    let unique = unique_contributors(&paths, state).await;
+    handle.publish(ServerMessage::Done {
+        repositories: traversal.treated.len(),
+        contributors: traversal.contributors,
+        unique_contributors: unique.map(|unique| unique.contributors),
+        unique_bots: unique.map(|unique| unique.bots),
+        unresolved: unresolved_packages(&paths, state).await,
+        truncated: traversal.truncated,
+    });
+    if let Some(job) = job {
+        save_checkpoint(job, &traversal, state, handle).await;
+    }
+    info!("Crawl of {link} done");
+    traversal.truncated
+}
+
+/// Crawls the dependency graph within the limits, breadth first.
+///
+/// Each repository is sent with the depth at which it was first reached, its
+/// shortest one, and
+/// each dependency with an edge from the repository depending on it. The
+/// repositories at the maximum depth are not explored, and the crawl stops
+/// once the maximum number of repositories is reached.
+async fn dependencies_iterative(
+    mut traversal: Traversal,
+    limits: CrawlConfig,
+    state: &AppState,
+    handle: &CrawlHandle,
+    job: Option<&CrawlJob>,
+) -> Traversal {
+    'crawl: while let Some((link, depth)) = traversal.queue.pop_front() {
+        if traversal.treated.insert(link.clone()) {
+            let contributors = cached_fetch(&link, state).await.unwrap_or(1);
+            traversal.contributors += contributors;
+            handle.publish(repository_message(&link, contributors, depth, state).await);
+        }
+
+        if depth >= limits.max_depth {
+            debug!("Not exploring {link}, maximum depth reached");
+            traversal.truncated = true;
+            continue;
+        }
+
+        let dependencies = fetch_dependencies(&link, state).await;
+        for message in &dependencies.warnings {
+            handle.publish(ServerMessage::Warning {
+                path: link.path(),
+                message: message.clone(),
+            });
+        }
+        for package in &dependencies.packages {
+            handle.publish(ServerMessage::package(&link, package));
+        }
+
+        for l in &dependencies.links {
+            if traversal.treated.contains(l) {
+                debug!("{} already treated", l.path());
+            } else if traversal.treated.len() >= limits.max_nodes {
+                info!("Stopping the crawl of {link}, maximum number of repositories reached");
+                traversal.truncated = true;
+                break 'crawl;
+            } else {
+                debug!("{} not treated yet", l.path());
+                traversal.treated.insert(l.clone());
+                let contributors = cached_fetch(l, state).await.unwrap_or(1);
+                traversal.contributors += contributors;
+                handle.publish(repository_message(l, contributors, depth + 1, state).await);
+                traversal.queue.push_back((l.clone(), depth + 1));
+            }
+            handle.publish(ServerMessage::Edge {
+                parent: link.path(),
+                child: l.path(),
+            });
+        }
+
+        traversal.processed += 1;
+        handle.publish(ServerMessage::Progress {
+            discovered: traversal.treated.len(),
+            processed: traversal.processed,
+        });
+        if let Some(job) = job {
+            save_checkpoint(job, &traversal, state, handle).await;
+        }
+    }
+
+    traversal
+}
+
+/// Saves the traversal with the messages published since the last checkpoint.
+///
+/// On failure the messages are kept to be saved with the next checkpoint.
+async fn save_checkpoint(
+    job: &CrawlJob,
+    traversal: &Traversal,
+    state: &AppState,
+    handle: &CrawlHandle,
+) {
+    let (first_seq, messages) = handle.unsaved();
+    let saved = state
+        .database
+        .read()
+        .await
+        .checkpoint_crawl_job(
+            job.id,
+            job.claim_id,
+            &traversal.checkpoint(),
+            first_seq,
+            &messages,
+        )
+        .await;
+    match saved {
+        Ok(true) => handle.mark_saved(messages.len()),
+        // Its messages are saved by the worker which claimed it since
+        Ok(false) => warn!("Not saving checkpoint of crawl {}, claimed again", job.id),
+        Err(e) => {
+            error!("Error saving checkpoint of crawl {}: {e}", job.id);
+            counter!("errors").increment(1);
+        }
+    }
+}
+
+/// The dependencies of the repository, from the database when still valid or
+/// else from GitHub, fetched once for all the concurrent crawls. Those of the
+/// repositories of the other forges are unknown, saved without any so that
+/// the repositories count as crawled.
+async fn fetch_dependencies(link: &RepositoryId, state: &AppState) -> Arc<FetchedDependencies> {
+    let (dependencies, joined) = state
+        .in_flight
+        .dependencies
+        .run(link, || fetch_dependencies_uncached(link, state))
+        .await;
+    if joined {
+        counter!("single_flight_joined", "from" => "dependencies").increment(1);
+    }
+    dependencies
+}
+
+async fn fetch_dependencies_uncached(
+    link: &RepositoryId,
+    state: &AppState,
+) -> Arc<FetchedDependencies> {
+    if *link.forge() == Forge::Sbom {
+        return Arc::new(sbom_dependencies(link, state).await);
+    }
+    let Some(github) = link.github() else {
+        debug!("Not listing the dependencies of {link}, only GitHub shows them");
+        set_dependencies_to_database(link, &[], state.clone()).await;
+        return Arc::new(FetchedDependencies::default());
+    };
+    if let Some(links) = get_from_database(link, state.clone())
+        .await
+        .and_then(|repo_info| dependencies_from_repository_info(&repo_info))
+    {
+        info!("Using cached dependencies for {link}");
+        counter!("cache_hit", "status" => "hit", "from" => "dependencies").increment(1);
+        let packages = match state
+            .database
+            .read()
+            .await
+            .repository_packages(link, None)
+            .await
+        {
+            Ok(packages) => packages,
+            Err(e) => {
+                error!("Error getting repository {link} packages from database: {e}");
+                counter!("errors").increment(1);
+                vec![]
+            }
+        };
+        return Arc::new(FetchedDependencies {
+            links,
+            packages,
+            warnings: vec![],
+        });
+    }
+    counter!("cache_hit", "status" => "miss", "from" => "dependencies").increment(1);
+
+    let mut fetched = FetchedDependencies::default();
+    let mut dep_iterator: GitHubLinkDependencies = state.github.dependencies(&github);
+    while let Some(dep) = dep_iterator.next().await {
+        match dep {
+            Ok(dependency) => {
+                debug!("Found dependency {}", dependency.package_name);
+                if let Some(l) = dependency.repository_id()
+                    && !fetched.links.contains(&l)
+                {
+                    fetched.links.push(l);
+                }
+                fetched.packages.push(dependency);
+            }
+            Err(e) => {
+                error!("Dependency fetching error: {:?}", e);
+                counter!("errors").increment(1);
+                fetched.warnings.push(e.to_string());
+            }
+        }
+    }
+    if fetched.packages.is_empty() && state.config.github.manifests {
+        info!("No dependency graph for {link}, reading its manifests");
+        match state.resolver.dependencies(&state.github, &github).await {
+            Ok(packages) => {
+                for l in packages.iter().filter_map(Dependency::repository_id) {
+                    if !fetched.links.contains(&l) {
+                        fetched.links.push(l);
+                    }
+                }
+                fetched.packages = packages;
+            }
+            Err(e) => {
+                error!("Manifest reading error: {:?}", e);
+                counter!("errors").increment(1);
+                fetched.warnings.push(e.to_string());
+            }
+        }
+    }
+    set_dependencies_to_database(link, &fetched.packages, state.clone()).await;
+    Arc::new(fetched)
+}
+
+/// The packages of an imported SBOM, saved when it was, resolved to their
+/// repositories through their registries. Only the packages resolved on a
+/// previous crawl are not requested again.
+async fn sbom_dependencies(link: &RepositoryId, state: &AppState) -> FetchedDependencies {
+    let packages = match state
+        .database
+        .read()
+        .await
+        .repository_packages(link, None)
+        .await
+    {
+        Ok(packages) => packages,
+        Err(e) => {
+            error!("Error getting SBOM {link} packages from database: {e}");
+            counter!("errors").increment(1);
+            return FetchedDependencies::default();
+        }
+    };
+    let mut fetched = FetchedDependencies::default();
+    for package in packages {
+        let package = state.resolver.resolve(package).await;
+        if let Some(l) = package.repository_id()
+            && !fetched.links.contains(&l)
+        {
+            fetched.links.push(l);
+        }
+        fetched.packages.push(package);
+    }
+    set_dependencies_to_database(link, &fetched.packages, state.clone()).await;
+    fetched
+}
+
+/// The contributors of the repository, from the cache or else from its forge,
+/// fetched once for all the concurrent crawls. `None` when the repository
+/// does not exist, 1 when they could not be fetched.
+async fn cached_fetch(link: &RepositoryId, state: &AppState) -> Option<usize> {
+    if *link.forge() == Forge::Sbom {
+        // Neither cached nor in the leaderboard, as the name of a private project
+        return Some(0);
+    }
+    let (contributors, joined) = state
+        .in_flight
+        .contributors
+        .run(link, || cached_fetch_uncached(link, state.clone()))
+        .await;
+    if joined {
+        counter!("single_flight_joined", "from" => "contributors").increment(1);
+    }
+    contributors
+}
+
+async fn cached_fetch_uncached(link: &RepositoryId, state: AppState) -> Option<usize> {
+    match get_from_cache(link, state.clone()).await {
+        Some(c) => {
+            counter!("cache_hit", "status" => "hit", "from" => "contributors").increment(1);
+            Some(c)
+        }
+        None if cached_not_found(link, &state).await => {
+            counter!("cache_hit", "status" => "hit", "from" => "not_found").increment(1);
+            None
+        }
+        None => {
+            counter!("cache_hit", "status" => "miss", "from" => "contributors").increment(1);
+            // Requests to each forge are paced by the scrapper's rate limiters
+            let contributors = match state.forges.fetch_contributors(link).await {
+                Ok(contributors) => contributors,
+                Err(GitHubError::NotFound(_)) => {
+                    set_not_found_to_cache(link, &state).await;
+                    return None;
+                }
+                Err(e) => {
+                    // Do not save a placeholder that would hide the real count,
+                    // nor show as a drop in its history
+                    warn!("Could not fetch {link} contributors: {e}");
+                    return Some(1);
+                }
+            };
+            let _ = set_to_cache(link, contributors, state.clone()).await;
+            set_contributors_to_database(link, contributors, state.clone()).await;
+            if state.config.github.fetch_identities
+                && let Some(github) = link.github()
+            {
+                fetch_contributions(&github, state).await;
+            }
+            Some(contributors)
+        }
+    }
+}
+
+/// Fetches and saves who the contributors of the repository are, which of
+/// them are bots, and how concentrated the commits of the people are.
+async fn fetch_contributions(link: &GitHubLink, state: AppState) {
+    let contributions = match state.github.fetch_contributions(link).await {
+        Ok(contributions) => contributions,
+        Err(e) => {
+            warn!("Could not fetch {link} contributions: {e}");
+            return;
+        }
+    };
+    let bots = &state.config.github.bots;
+    let identities: Vec<(String, bool)> = contributions
+        .iter()
+        .map(|contribution| {
+            let contributor = &contribution.contributor;
+            (contributor.identity(), bots.is_bot(contributor))
+        })
+        .collect();
+    // Bots such as dependabot author many commits without making a repository
+    // any less fragile
+    let concentration = Concentration::from_commits(
+        contributions
+            .iter()
+            .zip(&identities)
+            .filter(|(_, (_, bot))| !bot)
+            .map(|(contribution, _)| contribution.commits),
+    );
+
+    info!("Saving {} contributor identities for {link} in database", identities.len());
+    let id = RepositoryId::from(link);
+    let database = state.database.write().await;
+    if let Err(e) = database.insert_repository_identities(&id, &identities).await {
+        error!("Error setting repository {link} contributor identities to database: {e}");
+        counter!("errors").increment(1);
+    }
+    if let Err(e) = database.insert_repository_concentration(&id, concentration).await {
+        error!("Error setting repository {link} bus factor to database: {e}");
+        counter!("errors").increment(1);
+    }
+}
+
+/// The distinct people and bots among the contributors of the repositories,
+/// when the identities of any of them are known.
+async fn unique_contributors(paths: &[String], state: &AppState) -> Option<UniqueContributors> {
+    match state.database.read().await.unique_contributors(paths).await {
+        Ok(unique) if unique.repositories > 0 => Some(unique),
+        Ok(_) => None,
+        Err(e) => {
+            error!("Error counting unique contributors: {e}");
+            counter!("errors").increment(1);
+            None
+        }
+    }
+}
+
+/// The number of distinct packages of the repositories that do not resolve to a
+/// GitHub repository, `None` when it can not be told.
+async fn unresolved_packages(paths: &[String], state: &AppState) -> Option<usize> {
+    match state.database.read().await.unresolved_dependencies(paths).await {
+        Ok(dependencies) => {
+            let unresolved: Vec<UnresolvedDependency> = dependencies
+                .into_iter()
+                .filter_map(|(parent, dependency)| UnresolvedDependency::new(parent, &dependency))
+                .collect();
+            Some(UnresolvedDependency::distinct(&unresolved))
+        }
+        Err(e) => {
+            error!("Error counting unresolved dependencies: {e}");
+            counter!("errors").increment(1);
+            None
+        }
+    }
+}
+
+/// The repository and its contributors, along with the people, the bots and
+/// the bus factor among them when the contributions are fetched.
+async fn repository_message(
+    link: &RepositoryId,
+    contributors: usize,
+    depth: usize,
+    state: &AppState,
+) -> ServerMessage {
+    let (identified, info) = if state.config.github.fetch_identities {
+        (
+            unique_contributors(&[link.path()], state).await,
+            get_from_database(link, state.clone()).await,
+        )
+    } else {
+        (None, None)
+    };
+    ServerMessage::Repository {
+        path: link.path(),
+        contributors,
+        depth,
+        humans: identified.map(|identified| identified.contributors),
+        bots: identified.map(|identified| identified.bots),
+        bus_factor: info
+            .as_ref()
+            .and_then(|info| info.bus_factor)
+            .map(|bus_factor| bus_factor as usize),
+        top_share: info.and_then(|info| info.top_share),
+    }
+}
+
+async fn get_from_cache(link: &RepositoryId, state: AppState) -> Option<usize> {
+    let guard = state.cache.read().await;
+    match guard.get::<usize>(link.to_string().as_str()).await {
+        Ok(contributors) => {
+            debug!("Using cached contributors for {link}");
+            Some(contributors)
+        }
+        Err(_) => None,
+    }
+}
+
+/// Key of the mark of a repository missing from its forge.
+fn not_found_key(link: &RepositoryId) -> String {
+    format!("not_found:{link}")
+}
+
+/// Whether a crawl found the repository missing from its forge lately.
+pub(crate) async fn cached_not_found(link: &RepositoryId, state: &AppState) -> bool {
+    let guard = state.cache.read().await;
+    guard.get::<bool>(&not_found_key(link)).await.is_ok()
+}
+
+async fn set_not_found_to_cache(link: &RepositoryId, state: &AppState) {
+    let mut guard = state.cache.write().await;
+    if let Err(e) = guard
+        .set::<bool>(&not_found_key(link), &true, Some(NOT_FOUND_LIFETIME))
+        .await
+    {
+        error!("Error caching that {link} does not exist: {e}");
+        counter!("errors").increment(1);
+    }
+}
+
+async fn set_to_cache(
+    link: &RepositoryId,
+    contributors: usize,
+    state: AppState,
+) -> Result<(), CacheError> {
+    insert_leaderboard(link, contributors, state.clone()).await;
+    let mut guard = state.cache.write().await;
+    let lifetime: Option<Duration>;
+    {
+        let mut rng = rand::rng();
+        lifetime = Some(Duration::from_secs(
+            rng.random_range(state.config.cache.ttl_sec_min..state.config.cache.ttl_sec_max) as u64,
+        ));
+    }
+    match guard
+        .set::<usize>(link.to_string().as_str(), &contributors, lifetime)
+        .await
+    {
+        Ok(_) => {
+            info!("Setting cached value for {link}:{contributors} {lifetime:?}");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Error setting cached value for {link}:{contributors} {e}");
+            counter!("errors").increment(1);
+            Err(e)
+        }
+    }
+}
+
+async fn insert_leaderboard(link: &RepositoryId, contributors: usize, state: AppState) {
+    debug!("Inserting {link} in leaderboard with weight {contributors}");
+    let _ = state
+        .cache
+        .write()
+        .await
+        .set_leaderboard(link.path().as_str(), contributors as i32)
+        .await;
+}
+
+async fn get_from_database(link: &RepositoryId, state: AppState) -> Option<RepositoryInfo> {
+    let guard = state.database.read().await;
+    match guard.repository_info(link).await {
+        Ok(info) => Some(info),
+        Err(DatabaseError::NotFound(_)) => None,
+        Err(e) => {
+            error!("Error getting repository {link} info from database: {e:?}");
+            counter!("errors").increment(1);
+            None
+        }
+    }
+}
+
+fn dependencies_from_repository_info(info: &RepositoryInfo) -> Option<Vec<RepositoryId>> {
+    if let Some(dependencies) = &info.dependencies {
+        if dependencies.is_empty() || info.valid_until < chrono::Utc::now() {
+            return None;
+        }
+        return Some(
+            dependencies
+                .iter()
+                .filter_map(|path| RepositoryId::from_path(path).ok())
+                .collect(),
+        );
+    }
+    None
+}
+
+async fn set_contributors_to_database(link: &RepositoryId, contributors: usize, state: AppState) {
+    info!("Saving {contributors} contributors for {link} in database");
+    let guard = state.database.write().await;
+    if let Err(e) = guard
+        .insert_repository_contributors(link, contributors as i32)
+        .await
+    {
+        error!("Error setting repository {link} contributors to database: {e}");
+        counter!("errors").increment(1);
+    };
+}
+
+async fn set_dependencies_to_database(
+    link: &RepositoryId,
+    dependencies: &[Dependency],
+    state: AppState,
+) {
+    info!("Saving dependencies for {link} in database");
+    let guard = state.database.write().await;
+    if let Err(e) = guard
+        .insert_repository_dependencies(link, dependencies)
+        .await
+    {
+        error!("Error setting repository {link} total contributors to database: {e}");
+        counter!("errors").increment(1);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::RedisCache;
+    use crate::config::Config;
+    use crate::database::database::PostgresDatabase;
+    use github_scrapper::{Forges, GitLabClient, GiteaClient};
+    use std::collections::HashMap;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// The state of a backend on the database of the environment, whose forges
+    /// are all mocked by the server.
+    async fn state(server: &MockServer) -> (Config, AppState) {
+        let mut config = Config::from_env().unwrap();
+        config.github.web_url = server.uri();
+        config.github.api_url = server.uri();
+        let mut database = PostgresDatabase::from(&config).await.unwrap();
+        database.init(&config).await.unwrap();
+        let cache = RedisCache::try_from(&config).await.unwrap();
+        let mut state = AppState::try_new(&config, cache, database).unwrap();
+        state.forges = Arc::new(Forges::with_providers(
+            state.github.clone(),
+            GitLabClient::default().with_api_url(&server.uri()),
+            GiteaClient::default(),
+        ));
+        (config, state)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the Postgres database told by the DATABASE_* variables"]
+    async fn test_gitlab_root_is_crawled() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).insert_header("x-total", "3"))
+            .mount(&server)
+            .await;
+        let (config, state) = state(&server).await;
+        // Never crawled by a previous run
+        let link = RepositoryId::try_from(format!(
+            "https://gitlab.com/bus-factor/{}",
+            uuid::Uuid::new_v4()
+        ))
+        .unwrap();
+
+        crawl(
+            link.clone(),
+            config.crawl,
+            None,
+            &state,
+            &CrawlHandle::default(),
+        )
+        .await;
+
+        let graph = state
+            .database
+            .read()
+            .await
+            .dependency_graph(&link, config.crawl.max_depth as i32)
+            .await
+            .unwrap();
+        let root = graph.nodes.first().unwrap();
+        assert_eq!(root.path, link.path());
+        assert!(root.crawled);
+        assert_eq!(root.contributors, Some(3));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the Postgres database told by the DATABASE_* variables"]
+    async fn test_missing_root_is_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let (config, state) = state(&server).await;
+        let link = RepositoryId::try_from(format!(
+            "https://gitlab.com/bus-factor/{}",
+            uuid::Uuid::new_v4()
+        ))
+        .unwrap();
+        let handle = CrawlHandle::default();
+
+        crawl(link.clone(), config.crawl, None, &state, &handle).await;
+
+        assert!(matches!(
+            handle.unsaved().1.as_slice(),
+            [ServerMessage::Error {
+                code: ErrorCode::NotFound,
+                ..
+            }]
+        ));
+        let graph = state
+            .database
+            .read()
+            .await
+            .dependency_graph(&link, config.crawl.max_depth as i32)
+            .await
+            .unwrap();
+        assert!(graph.nodes.first().is_none_or(|root| !root.crawled));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the Postgres database told by the DATABASE_* variables"]
+    async fn test_long_path_is_saved() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-total", "2")
+                    .set_body_string("[]"),
+            )
+            .mount(&server)
+            .await;
+        let (config, state) = state(&server).await;
+        let owner = uuid::Uuid::new_v4().simple().to_string();
+        let root = RepositoryId::from_path(&format!("{owner}/root")).unwrap();
+        // Nested GitLab groups
+        let nested = RepositoryId::from_path(&format!(
+            "gitlab.com/{owner}/{}repo",
+            "subgroup/".repeat(20)
+        ))
+        .unwrap();
+        assert!(nested.path().len() > 200);
+        let package = Dependency {
+            ecosystem: None,
+            package_name: "nested".to_string(),
+            requirements: None,
+            manifest: None,
+            repository: Some(nested.clone()),
+            url: None,
+        };
+        state
+            .database
+            .write()
+            .await
+            .insert_repository_dependencies(&root, &[package])
+            .await
+            .unwrap();
+
+        crawl(root.clone(), config.crawl, None, &state, &CrawlHandle::default()).await;
+
+        let graph = state
+            .database
+            .read()
+            .await
+            .dependency_graph(&root, config.crawl.max_depth as i32)
+            .await
+            .unwrap();
+        let node = graph
+            .nodes
+            .iter()
+            .find(|node| node.path == nested.path())
+            .unwrap();
+        assert!(node.crawled);
+        assert_eq!(node.contributors, Some(2));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the Postgres database told by the DATABASE_* variables"]
+    async fn test_diamond_reached_at_shortest_depth() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+            .mount(&server)
+            .await;
+        let (_, state) = state(&server).await;
+        // Never crawled by a previous run
+        let owner = uuid::Uuid::new_v4().simple().to_string();
+        let id = |repo: &str| RepositoryId::from_path(&format!("{owner}/{repo}")).unwrap();
+        // `shared` is two repositories away through `short`, three through
+        // `long` which is listed last, and so explored first depth first
+        for (parent, children) in [
+            ("root", vec!["short", "long"]),
+            ("short", vec!["shared"]),
+            ("long", vec!["longer"]),
+            ("longer", vec!["shared"]),
+            ("shared", vec!["leaf"]),
+        ] {
+            let packages: Vec<Dependency> = children
+                .into_iter()
+                .map(|child| Dependency::from(id(child).github().unwrap()))
+                .collect();
+            state
+                .database
+                .write()
+                .await
+                .insert_repository_dependencies(&id(parent), &packages)
+                .await
+                .unwrap();
+        }
+        let limits = CrawlConfig {
+            max_depth: 3,
+            max_nodes: 100,
+        };
+        let handle = CrawlHandle::default();
+
+        crawl(id("root"), limits, None, &state, &handle).await;
+
+        let depths: HashMap<String, usize> = handle
+            .unsaved()
+            .1
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::Repository { path, depth, .. } => Some((path, depth)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(depths.get(&id("shared").path()), Some(&2));
+        assert_eq!(depths.get(&id("longer").path()), Some(&2));
+        // Explored from `shared` at its shortest depth
+        assert_eq!(depths.get(&id("leaf").path()), Some(&3));
+        assert_eq!(depths.len(), 6);
+    }
+}
@@ -0,0 +1,8 @@
+mod crawl;
+mod registry;
+mod single_flight;
+mod worker;
+
+pub(crate) use crawl::{InFlight, cached_not_found};
+pub(crate) use registry::{CrawlRegistry, Subscription};
+pub(crate) use worker::spawn_workers;
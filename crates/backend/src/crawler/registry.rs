@@ -0,0 +1,383 @@
+use crate::api::state::AppState;
+use crate::config::CrawlConfig;
+use crate::database::Database;
+use crate::database::errors::DatabaseError;
+use crate::models::ServerMessage;
+use github_scrapper::RepositoryId;
+use metrics::counter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::time::interval;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::crawl::crawl;
+
+/// How often the messages of a crawl run by another backend are looked for.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+struct Journal {
+    messages: Vec<ServerMessage>,
+    /// Number of messages already saved with the last checkpoint
+    saved: usize,
+    subscribers: Vec<UnboundedSender<ServerMessage>>,
+    /// Whether a worker of this backend runs the crawl, publishing its
+    /// messages itself
+    claimed: bool,
+    finished: bool,
+}
+
+impl Journal {
+    fn publish(&mut self, message: ServerMessage) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(message.clone()).is_ok());
+        self.messages.push(message);
+    }
+}
+
+/// A crawl, publishing its messages to all its subscribers.
+///
+/// The messages are kept so that a late subscriber first receives everything
+/// that was already discovered.
+#[derive(Default)]
+pub(crate) struct CrawlHandle {
+    journal: Mutex<Journal>,
+}
+
+impl CrawlHandle {
+    /// Sends the message to the current subscribers.
+    pub(crate) fn publish(&self, message: ServerMessage) {
+        self.journal.lock().unwrap().publish(message);
+    }
+
+    /// Tells that a worker of this backend runs the crawl, so that its
+    /// messages are no longer relayed from the database, see [`CrawlHandle::relay`].
+    pub(crate) fn claim(&self) {
+        self.journal.lock().unwrap().claimed = true;
+    }
+
+    /// Publishes the messages of a crawl resumed after a restart, which are
+    /// already saved, but the ones already relayed.
+    pub(crate) fn restore(&self, messages: Vec<ServerMessage>) {
+        let mut journal = self.journal.lock().unwrap();
+        let published = journal.messages.len();
+        for message in messages.into_iter().skip(published) {
+            journal.publish(message);
+        }
+        journal.saved = journal.messages.len();
+    }
+
+    /// Number of messages published so far, which is the sequence number of
+    /// the next one.
+    fn next_seq(&self) -> usize {
+        self.journal.lock().unwrap().messages.len()
+    }
+
+    /// Publishes the saved messages of a crawl run by another backend, the
+    /// first one having the sequence number `first_seq`, skipping the ones
+    /// already published.
+    ///
+    /// `false` once the crawl is run by this backend or finished, nothing
+    /// being published anymore.
+    fn relay(&self, first_seq: usize, messages: Vec<ServerMessage>) -> bool {
+        let mut journal = self.journal.lock().unwrap();
+        if journal.claimed || journal.finished {
+            return false;
+        }
+        let published = journal.messages.len();
+        for message in messages
+            .into_iter()
+            .skip(published.saturating_sub(first_seq))
+        {
+            journal.publish(message);
+        }
+        journal.saved = journal.messages.len();
+        true
+    }
+
+    /// The messages published since the last checkpoint, and the sequence
+    /// number of the first one.
+    pub(crate) fn unsaved(&self) -> (usize, Vec<ServerMessage>) {
+        let journal = self.journal.lock().unwrap();
+        (journal.saved, journal.messages[journal.saved..].to_vec())
+    }
+
+    pub(crate) fn mark_saved(&self, count: usize) {
+        self.journal.lock().unwrap().saved += count;
+    }
+
+    /// The messages published so far, and a receiver for the next ones.
+    fn subscribe(&self) -> Subscription {
+        let mut journal = self.journal.lock().unwrap();
+        let (sender, receiver) = unbounded_channel();
+        if !journal.finished {
+            journal.subscribers.push(sender);
+        }
+        Subscription {
+            backlog: journal.messages.clone(),
+            receiver,
+        }
+    }
+
+    fn finish(&self) {
+        let mut journal = self.journal.lock().unwrap();
+        journal.finished = true;
+        journal.subscribers.clear();
+    }
+}
+
+/// What a crawl sent before subscribing, and what it sends afterwards.
+pub(crate) struct Subscription {
+    pub(crate) backlog: Vec<ServerMessage>,
+    pub(crate) receiver: UnboundedReceiver<ServerMessage>,
+}
+
+type CrawlKey = (RepositoryId, CrawlConfig);
+
+/// The crawls queued or running in this backend, so that the sessions
+/// searching for the same repository with the same limits share its crawl.
+///
+/// The crawls themselves are run by the workers, see [`spawn_workers`](super::spawn_workers).
+#[derive(Default)]
+pub(crate) struct CrawlRegistry {
+    crawls: Mutex<HashMap<CrawlKey, Arc<CrawlHandle>>>,
+    /// Wakes up the workers when a crawl is queued
+    pub(crate) queued: Notify,
+}
+
+impl CrawlRegistry {
+    /// Subscribes to the crawl of the repository, queueing it if needed.
+    pub(crate) async fn subscribe(
+        &self,
+        link: &RepositoryId,
+        limits: CrawlConfig,
+        state: &AppState,
+    ) -> Subscription {
+        let (handle, created) = self.handle(&(link.clone(), limits));
+        let subscription = handle.subscribe();
+        if !created {
+            info!("Joining the crawl of {link}");
+            return subscription;
+        }
+
+        match self.enqueue(link, limits, state).await {
+            // Any backend may run it
+            Ok(id) => {
+                tokio::spawn(follow(id, (link.clone(), limits), handle, state.clone()));
+            }
+            Err(e) => {
+                // Still serve the client, without surviving a restart
+                error!("Could not queue the crawl of {link}, running it now: {e}");
+                counter!("errors").increment(1);
+                let state = state.clone();
+                let link = link.clone();
+                tokio::spawn(async move {
+                    handle.claim();
+                    crawl(link.clone(), limits, None, &state, &handle).await;
+                    state.crawls.finish(&(link, limits), &handle);
+                });
+            }
+        }
+        subscription
+    }
+
+    /// Queues the crawl of the repository for the workers, unless it is
+    /// already queued or running.
+    pub(crate) async fn enqueue(
+        &self,
+        link: &RepositoryId,
+        limits: CrawlConfig,
+        state: &AppState,
+    ) -> Result<Uuid, DatabaseError> {
+        let id = state
+            .database
+            .read()
+            .await
+            .enqueue_crawl_job(&link.path(), limits.max_depth as i32, limits.max_nodes as i32)
+            .await?;
+        info!("Queued crawl {id} of {link}");
+        self.queued.notify_one();
+        Ok(id)
+    }
+
+    /// The handle of the crawl, and whether it was just created.
+    pub(crate) fn handle(&self, key: &CrawlKey) -> (Arc<CrawlHandle>, bool) {
+        let mut crawls = self.crawls.lock().unwrap();
+        if let Some(handle) = crawls.get(key) {
+            return (handle.clone(), false);
+        }
+        let handle = Arc::new(CrawlHandle::default());
+        crawls.insert(key.clone(), handle.clone());
+        (handle, true)
+    }
+
+    /// Forgets about the crawl, its subscribers receive nothing more.
+    pub(crate) fn finish(&self, key: &CrawlKey, handle: &Arc<CrawlHandle>) {
+        {
+            // Removed first so that no one joins a crawl that will not publish
+            // anymore, unless a new crawl already replaced it
+            let mut crawls = self.crawls.lock().unwrap();
+            match crawls.get(key) {
+                Some(current) if Arc::ptr_eq(current, handle) => {
+                    crawls.remove(key);
+                }
+                _ => warn!("Finishing the unknown crawl of {}", key.0),
+            }
+        }
+        handle.finish();
+    }
+}
+
+/// Relays the messages of the job saved in the database to the subscribers of
+/// this backend, until the job is done, in case another backend runs it.
+///
+/// Stops as soon as a worker of this backend claims the job, which then
+/// publishes its messages itself, see [`CrawlHandle::claim`].
+async fn follow(id: Uuid, key: CrawlKey, handle: Arc<CrawlHandle>, state: AppState) {
+    let mut ticks = interval(FOLLOW_INTERVAL);
+    loop {
+        ticks.tick().await;
+        // Read before the messages, so that none is missed once it is done
+        let done = match state.database.read().await.crawl_job_done(id).await {
+            Ok(done) => done,
+            Err(e) => {
+                error!("Error getting the status of crawl {id}: {e}");
+                counter!("errors").increment(1);
+                continue;
+            }
+        };
+        let first_seq = handle.next_seq();
+        let messages = match state
+            .database
+            .read()
+            .await
+            .crawl_job_events(id, first_seq)
+            .await
+        {
+            Ok(messages) => messages,
+            Err(e) => {
+                error!("Error getting the messages of crawl {id}: {e}");
+                counter!("errors").increment(1);
+                continue;
+            }
+        };
+        if !handle.relay(first_seq, messages) {
+            debug!("Not following crawl {id} anymore, run by this backend");
+            return;
+        }
+        if done {
+            info!("Crawl {id} of {} done by another backend", key.0);
+            state.crawls.finish(&key, &handle);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository(path: &str) -> ServerMessage {
+        ServerMessage::Repository {
+            path: path.to_string(),
+            contributors: 1,
+            depth: 0,
+            humans: None,
+            bots: None,
+            bus_factor: None,
+            top_share: None,
+        }
+    }
+
+    #[test]
+    fn test_late_subscriber_receives_backlog() {
+        let handle = CrawlHandle::default();
+        let mut first = handle.subscribe();
+        handle.publish(repository("tokio-rs/tokio"));
+
+        let mut second = handle.subscribe();
+        handle.publish(repository("tokio-rs/loom"));
+        handle.finish();
+
+        assert!(first.backlog.is_empty());
+        assert_eq!(first.receiver.try_recv().unwrap(), repository("tokio-rs/tokio"));
+        assert_eq!(first.receiver.try_recv().unwrap(), repository("tokio-rs/loom"));
+        assert_eq!(second.backlog, vec![repository("tokio-rs/tokio")]);
+        assert_eq!(second.receiver.try_recv().unwrap(), repository("tokio-rs/loom"));
+        assert!(second.receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_relayed_messages() {
+        let handle = CrawlHandle::default();
+        let mut subscription = handle.subscribe();
+        assert!(handle.relay(0, vec![repository("tokio-rs/tokio")]));
+        // Read again from the first one
+        assert!(handle.relay(
+            0,
+            vec![repository("tokio-rs/tokio"), repository("tokio-rs/loom")]
+        ));
+        assert_eq!(handle.next_seq(), 2);
+        assert_eq!(
+            subscription.receiver.try_recv().unwrap(),
+            repository("tokio-rs/tokio")
+        );
+        assert_eq!(
+            subscription.receiver.try_recv().unwrap(),
+            repository("tokio-rs/loom")
+        );
+
+        // Resumed here, only the messages not relayed yet are published
+        handle.claim();
+        assert!(!handle.relay(2, vec![repository("tokio-rs/bytes")]));
+        handle.restore(vec![
+            repository("tokio-rs/tokio"),
+            repository("tokio-rs/loom"),
+            repository("tokio-rs/mio"),
+        ]);
+        assert_eq!(
+            subscription.receiver.try_recv().unwrap(),
+            repository("tokio-rs/mio")
+        );
+        assert!(subscription.receiver.try_recv().is_err());
+        assert_eq!(handle.unsaved(), (3, vec![]));
+    }
+
+    #[test]
+    fn test_finish_keeps_a_newer_crawl() {
+        let registry = CrawlRegistry::default();
+        let key = (
+            RepositoryId::from_path("tokio-rs/tokio").unwrap(),
+            CrawlConfig {
+                max_depth: 1,
+                max_nodes: 10,
+            },
+        );
+        let (old, created) = registry.handle(&key);
+        assert!(created);
+        registry.finish(&key, &old);
+        let (new, created) = registry.handle(&key);
+        assert!(created);
+
+        // Such as a follower noticing late that the first crawl is done
+        registry.finish(&key, &old);
+        let (current, created) = registry.handle(&key);
+        assert!(!created);
+        assert!(Arc::ptr_eq(&current, &new));
+    }
+
+    #[test]
+    fn test_unsaved_messages() {
+        let handle = CrawlHandle::default();
+        handle.restore(vec![repository("tokio-rs/tokio")]);
+        handle.publish(repository("tokio-rs/loom"));
+
+        assert_eq!(handle.unsaved(), (1, vec![repository("tokio-rs/loom")]));
+        handle.mark_saved(1);
+        assert_eq!(handle.unsaved(), (2, vec![]));
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Runs a single computation per key at a time, concurrent callers for the
+/// same key await the result of the one already underway.
+pub(crate) struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> SingleFlight<K, V> {
+    /// The result of `f` for this key, computed by this call or by a concurrent one.
+    ///
+    /// Returns whether the result was shared with a concurrent call as well.
+    pub(crate) async fn run<F, Fut>(&self, key: &K, f: F) -> (V, bool)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let mut computed = false;
+        let value = cell
+            .get_or_init(|| {
+                computed = true;
+                f()
+            })
+            .await
+            .clone();
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            in_flight.remove(key);
+        }
+        (value, !computed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_are_merged() {
+        let flights: SingleFlight<&str, usize> = SingleFlight::default();
+        let calls = AtomicUsize::new(0);
+        let compute = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            42
+        };
+
+        let (first, second) = tokio::join!(
+            flights.run(&"tokio-rs/tokio", compute),
+            flights.run(&"tokio-rs/tokio", compute)
+        );
+        assert_eq!(first.0, 42);
+        assert_eq!(second.0, 42);
+        assert!(first.1 != second.1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Once done, the next call computes the value again
+        flights.run(&"tokio-rs/tokio", compute).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,147 @@
+use super::crawl::crawl;
+use crate::api::state::AppState;
+use crate::config::CrawlConfig;
+use crate::database::Database;
+use crate::database::models::CrawlJob;
+use github_scrapper::RepositoryId;
+use metrics::counter;
+use std::time::Duration;
+use tokio::time::{interval, sleep};
+use tracing::{error, info, warn};
+
+/// How often a worker tells that it is still running its job.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// How long without heartbeat before a running job is considered abandoned,
+/// for example by a backend that stopped, and claimed by another worker.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+/// How often an idle worker looks for jobs queued by another backend.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long the messages of a finished job are kept.
+const FINISHED_JOBS_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Spawns the workers running the queued crawls, and the task deleting the
+/// old ones.
+pub(crate) fn spawn_workers(state: &AppState) {
+    for worker in 0..state.config.crawl_workers {
+        tokio::spawn(work(worker, state.clone()));
+    }
+    tokio::spawn(clean(state.clone()));
+}
+
+async fn work(worker: usize, state: AppState) {
+    info!("Crawl worker {worker} started");
+    loop {
+        let claimed = state
+            .database
+            .read()
+            .await
+            .claim_crawl_job(STALE_AFTER)
+            .await;
+        match claimed {
+            Ok(Some(job)) => {
+                info!("Worker {worker} claimed crawl {} of {}", job.id, job.path);
+                run(job, &state).await;
+            }
+            Ok(None) => {
+                tokio::select! {
+                    _ = state.crawls.queued.notified() => {}
+                    _ = sleep(POLL_INTERVAL) => {}
+                }
+            }
+            Err(e) => {
+                error!("Worker {worker} could not claim a crawl: {e}");
+                counter!("errors").increment(1);
+                sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Runs the crawl of the job, publishing its messages to the sessions
+/// subscribed in this backend.
+async fn run(job: CrawlJob, state: &AppState) {
+    let Ok(link) = RepositoryId::from_path(&job.path) else {
+        warn!(
+            "Dropping crawl {} of invalid repository {}",
+            job.id, job.path
+        );
+        finish(&job, false, state).await;
+        return;
+    };
+    let limits = CrawlConfig {
+        max_depth: job.max_depth as usize,
+        max_nodes: job.max_nodes as usize,
+    };
+    let key = (link.clone(), limits);
+    let (handle, _) = state.crawls.handle(&key);
+    handle.claim();
+
+    if job.checkpoint.is_some() {
+        match state.database.read().await.crawl_job_events(job.id, 0).await {
+            Ok(messages) => handle.restore(messages),
+            Err(e) => {
+                // The sessions will only receive what comes next
+                error!("Error getting the messages of crawl {}: {e}", job.id);
+                counter!("errors").increment(1);
+            }
+        }
+    }
+
+    let truncated = tokio::select! {
+        truncated = crawl(link, limits, Some(&job), state, &handle) => truncated,
+        _ = heartbeat(&job, state) => false,
+    };
+    finish(&job, truncated, state).await;
+    state.crawls.finish(&key, &handle);
+}
+
+async fn heartbeat(job: &CrawlJob, state: &AppState) {
+    let mut ticks = interval(HEARTBEAT_INTERVAL);
+    loop {
+        ticks.tick().await;
+        if let Err(e) = state
+            .database
+            .read()
+            .await
+            .heartbeat_crawl_job(job.id, job.claim_id)
+            .await
+        {
+            error!("Error saving heartbeat of crawl {}: {e}", job.id);
+            counter!("errors").increment(1);
+        }
+    }
+}
+
+async fn finish(job: &CrawlJob, truncated: bool, state: &AppState) {
+    if let Err(e) = state
+        .database
+        .read()
+        .await
+        .finish_crawl_job(job.id, job.claim_id, truncated)
+        .await
+    {
+        error!("Error finishing crawl {}: {e}", job.id);
+        counter!("errors").increment(1);
+    }
+}
+
+async fn clean(state: AppState) {
+    let mut ticks = interval(CLEANUP_INTERVAL);
+    loop {
+        ticks.tick().await;
+        match state
+            .database
+            .read()
+            .await
+            .delete_finished_crawl_jobs(FINISHED_JOBS_LIFETIME)
+            .await
+        {
+            Ok(deleted) => info!("Deleted {deleted} finished crawls"),
+            Err(e) => {
+                error!("Error deleting finished crawls: {e}");
+                counter!("errors").increment(1);
+            }
+        }
+    }
+}
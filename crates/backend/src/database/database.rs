@@ -1,14 +1,21 @@
 use super::errors::DatabaseError;
-use super::models::RepositoryInfo;
+use super::migrations;
+use super::models::{
+    CrawlCheckpoint, CrawlJob, DependencyGraph, GraphNode, LastCrawlJob, RepositoryInfo,
+    UniqueContributors,
+};
 use crate::config::Config;
+use crate::models::ServerMessage;
 use chrono::{DateTime, Utc};
 use deadpool_postgres::{Config as DpConfig, ManagerConfig, Pool, RecyclingMethod, Runtime};
-use github_scrapper::GitHubLink;
+use github_scrapper::{Concentration, Dependency, Ecosystem, RepositoryId};
 use log::warn;
 use std::future::Future;
-use tokio_postgres::types::ToSql;
+use std::time::Duration;
+use tokio_postgres::types::{Json, ToSql};
 use tokio_postgres::{NoTls, Row};
 use tracing::{debug, info};
+use uuid::Uuid;
 
 // TODO: Require SSL when communicating across the internet
 
@@ -20,18 +27,135 @@ pub trait Database {
     ) -> impl Future<Output = Result<&mut Self, DatabaseError>> + Send;
     fn repository_info(
         &self,
-        link: &GitHubLink,
+        link: &RepositoryId,
     ) -> impl Future<Output = Result<RepositoryInfo, DatabaseError>> + Send;
     fn insert_repository_contributors(
         &self,
-        link: &GitHubLink,
+        link: &RepositoryId,
         contributors: i32,
     ) -> impl Future<Output = Result<(), DatabaseError>> + Send;
+    /// Replaces the packages the repository depends on, and the edges to the
+    /// repositories they resolved to.
     fn insert_repository_dependencies(
         &self,
-        link: &GitHubLink,
-        dependencies: &[GitHubLink],
+        link: &RepositoryId,
+        dependencies: &[Dependency],
     ) -> impl Future<Output = Result<(), DatabaseError>> + Send;
+    /// The packages the repository depends on, of the given ecosystem only
+    /// when there is one, in the order they were listed.
+    fn repository_packages(
+        &self,
+        link: &RepositoryId,
+        ecosystem: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<Dependency>, DatabaseError>> + Send;
+    /// The packages of the given repositories that do not resolve to a GitHub
+    /// repository, with the repository depending on them.
+    fn unresolved_dependencies(
+        &self,
+        paths: &[String],
+    ) -> impl Future<Output = Result<Vec<(String, Dependency)>, DatabaseError>> + Send;
+    /// The repositories known to depend on the given one, with their
+    /// contributors when known, most contributors first.
+    fn repository_dependents(
+        &self,
+        link: &RepositoryId,
+    ) -> impl Future<Output = Result<Vec<(String, Option<i32>)>, DatabaseError>> + Send;
+    /// The known dependency graph of the repository up to the given depth,
+    /// the repository itself being the first node if it is known.
+    fn dependency_graph(
+        &self,
+        link: &RepositoryId,
+        max_depth: i32,
+    ) -> impl Future<Output = Result<DependencyGraph, DatabaseError>> + Send;
+    /// Every contributors count observed for the repository, oldest first.
+    fn repository_history(
+        &self,
+        link: &RepositoryId,
+    ) -> impl Future<Output = Result<Vec<(DateTime<Utc>, i32)>, DatabaseError>> + Send;
+    /// Replaces the identities of the contributors of the repository, see
+    /// [`Contributor::identity`](github_scrapper::Contributor::identity), each
+    /// with whether the contributor is a bot.
+    fn insert_repository_identities(
+        &self,
+        link: &RepositoryId,
+        identities: &[(String, bool)],
+    ) -> impl Future<Output = Result<(), DatabaseError>> + Send;
+    /// Sets how much the commits of the repository depend on few people,
+    /// unknown when `None`.
+    fn insert_repository_concentration(
+        &self,
+        link: &RepositoryId,
+        concentration: Option<Concentration>,
+    ) -> impl Future<Output = Result<(), DatabaseError>> + Send;
+    /// The number of distinct people, and of distinct bots, among the
+    /// contributors of the given repositories whose identities are known.
+    fn unique_contributors(
+        &self,
+        paths: &[String],
+    ) -> impl Future<Output = Result<UniqueContributors, DatabaseError>> + Send;
+    /// Queues a crawl, or returns the crawl already queued or running with the
+    /// same repository and limits.
+    fn enqueue_crawl_job(
+        &self,
+        path: &str,
+        max_depth: i32,
+        max_nodes: i32,
+    ) -> impl Future<Output = Result<Uuid, DatabaseError>> + Send;
+    /// The last crawl queued for the repository with the same limits, if any
+    /// is still known.
+    fn last_crawl_job(
+        &self,
+        path: &str,
+        max_depth: i32,
+        max_nodes: i32,
+    ) -> impl Future<Output = Result<Option<LastCrawlJob>, DatabaseError>> + Send;
+    /// Takes the oldest queued crawl, or a running one whose worker has not
+    /// given news for a while, under a new claim. The writes of the workers
+    /// which claimed it before are then dropped.
+    fn claim_crawl_job(
+        &self,
+        stale_after: Duration,
+    ) -> impl Future<Output = Result<Option<CrawlJob>, DatabaseError>> + Send;
+    /// The saved messages of a crawl, from the one with the sequence number
+    /// `first_seq`.
+    fn crawl_job_events(
+        &self,
+        id: Uuid,
+        first_seq: usize,
+    ) -> impl Future<Output = Result<Vec<ServerMessage>, DatabaseError>> + Send;
+    /// Whether the crawl is done, or was deleted once done for long.
+    fn crawl_job_done(&self, id: Uuid) -> impl Future<Output = Result<bool, DatabaseError>> + Send;
+    /// Saves the state of the crawl along with the messages it published since
+    /// the previous checkpoint, the first one having the sequence number `first_seq`.
+    /// `false` when nothing was saved, the crawl being claimed by another worker.
+    fn checkpoint_crawl_job(
+        &self,
+        id: Uuid,
+        claim_id: Uuid,
+        checkpoint: &CrawlCheckpoint,
+        first_seq: usize,
+        events: &[ServerMessage],
+    ) -> impl Future<Output = Result<bool, DatabaseError>> + Send;
+    fn heartbeat_crawl_job(
+        &self,
+        id: Uuid,
+        claim_id: Uuid,
+    ) -> impl Future<Output = Result<(), DatabaseError>> + Send;
+    /// Marks the crawl as done, telling whether it stopped on its limits,
+    /// unless it is claimed by another worker.
+    fn finish_crawl_job(
+        &self,
+        id: Uuid,
+        claim_id: Uuid,
+        truncated: bool,
+    ) -> impl Future<Output = Result<(), DatabaseError>> + Send;
+    /// Deletes the crawls finished for longer than the given duration, but the
+    /// last one of each repository and limits, of which only the messages are
+    /// deleted.
+    fn delete_finished_crawl_jobs(
+        &self,
+        older_than: Duration,
+    ) -> impl Future<Output = Result<u64, DatabaseError>> + Send;
 }
 
 #[derive(Clone)]
@@ -94,7 +218,7 @@ impl TryInto<RepositoryInfo> for Row {
     type Error = DatabaseError;
 
     fn try_into(self) -> Result<RepositoryInfo, Self::Error> {
-        const EXPECTED_LENGTH: usize = 6;
+        const EXPECTED_LENGTH: usize = 8;
         if self.is_empty() {
             return Err(DatabaseError::NotFound("".to_string()));
         };
@@ -115,64 +239,679 @@ impl TryInto<RepositoryInfo> for Row {
             created_at,
             updated_at,
             valid_until,
+            bus_factor: self.get(6),
+            top_share: self.get(7),
         })
     }
 }
 
+/// A package read from the `ecosystem, package_name, requirements, manifest,
+/// repository, url` columns of the row, starting at `first`.
+fn package_from_row(row: &Row, first: usize) -> Dependency {
+    Dependency {
+        ecosystem: row
+            .get::<_, Option<String>>(first)
+            .and_then(|ecosystem| ecosystem.parse().ok()),
+        package_name: row.get(first + 1),
+        requirements: row.get(first + 2),
+        manifest: row.get(first + 3),
+        repository: row
+            .get::<_, Option<String>>(first + 4)
+            .and_then(|path| RepositoryId::from_path(&path).ok()),
+        url: row.get(first + 5),
+    }
+}
+
 impl Database for PostgresDatabase {
     async fn init(&mut self, config: &Config) -> Result<&mut Self, DatabaseError> {
         let _ = config;
+        let mut client = self.pool.get().await?;
+        migrations::migrate(&mut client).await?;
+        info!("Database schema at version {}", migrations::latest_version());
         Ok(self)
     }
 
-    async fn repository_info(&self, link: &GitHubLink) -> Result<RepositoryInfo, DatabaseError> {
+    async fn repository_info(&self, link: &RepositoryId) -> Result<RepositoryInfo, DatabaseError> {
         let path = link.path();
         let path = path.as_str();
         debug!("Getting repository {} from database", path);
-        self.query_one_cached("SELECT * FROM repositories WHERE path = $1", &[&path])
+        self.query_one_cached(
+            "SELECT path, contributors, dependencies, created_at, updated_at, valid_until,
+                bus_factor, top_share
+            FROM repositories WHERE path = $1",
+            &[&path],
+        )
             .await?
             .try_into()
     }
 
     async fn insert_repository_contributors(
         &self,
-        link: &GitHubLink,
+        link: &RepositoryId,
         contributors: i32,
     ) -> Result<(), DatabaseError> {
         let path = link.path();
         let path = path.as_str();
         debug!("Setting {} contributors for repository {} in database", contributors, &path);
-        self.execute_cached(
-            "INSERT INTO repositories (path, contributors)
-            VALUES ($1, $2)
-            ON CONFLICT (path) DO UPDATE
-            SET path = $1, contributors = $2",
-            &[&path, &contributors],
-        )
-        .await?;
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        let upsert = transaction
+            .prepare_cached(
+                "INSERT INTO repositories (path, contributors)
+                VALUES ($1, $2)
+                ON CONFLICT (path) DO UPDATE
+                SET path = $1, contributors = $2",
+            )
+            .await?;
+        transaction.execute(&upsert, &[&path, &contributors]).await?;
+        let history = transaction
+            .prepare_cached(
+                "INSERT INTO repository_contributors_history (path, contributors)
+                VALUES ($1, $2)",
+            )
+            .await?;
+        transaction.execute(&history, &[&path, &contributors]).await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn insert_repository_dependencies(
         &self,
-        link: &GitHubLink,
-        dependencies: &[GitHubLink],
+        link: &RepositoryId,
+        packages: &[Dependency],
     ) -> Result<(), DatabaseError> {
         let path = link.path();
         let path = path.as_str();
-        let dependencies = dependencies
-            .iter()
-            .map(|l| l.path())
-            .collect::<Vec<String>>();
+        let mut dependencies: Vec<String> = vec![];
+        for repository in packages.iter().filter_map(Dependency::repository_id) {
+            if !dependencies.contains(&repository.path()) {
+                dependencies.push(repository.path());
+            }
+        }
         debug!("Setting {:?} dependencies for repository {} in database", &dependencies, path);
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        let upsert = transaction
+            .prepare_cached(
+                "INSERT INTO repositories (path, dependencies)
+                VALUES ($1, $2)
+                ON CONFLICT (path) DO UPDATE
+                SET path = $1, dependencies = $2",
+            )
+            .await?;
+        transaction.execute(&upsert, &[&path, &dependencies]).await?;
+        // Existing edges keep the date they were first discovered
+        let delete_edges = transaction
+            .prepare_cached(
+                "DELETE FROM repository_edges
+                WHERE parent = $1 AND NOT (child = ANY($2))",
+            )
+            .await?;
+        transaction
+            .execute(&delete_edges, &[&path, &dependencies])
+            .await?;
+        let insert_edges = transaction
+            .prepare_cached(
+                "INSERT INTO repository_edges (parent, child)
+                SELECT $1, unnest($2::TEXT[])
+                ON CONFLICT DO NOTHING",
+            )
+            .await?;
+        transaction
+            .execute(&insert_edges, &[&path, &dependencies])
+            .await?;
+
+        let delete_packages = transaction
+            .prepare_cached("DELETE FROM repository_packages WHERE parent = $1")
+            .await?;
+        transaction.execute(&delete_packages, &[&path]).await?;
+        let insert_packages = transaction
+            .prepare_cached(
+                "INSERT INTO repository_packages
+                    (parent, ecosystem, package_name, requirements, manifest, repository, url)
+                SELECT $1, ecosystem, package_name, requirements, manifest, repository, url
+                FROM unnest(
+                    $2::VARCHAR(50)[], $3::VARCHAR(255)[], $4::VARCHAR(255)[],
+                    $5::VARCHAR(500)[], $6::TEXT[], $7::VARCHAR(500)[]
+                ) WITH ORDINALITY
+                    AS t(ecosystem, package_name, requirements, manifest, repository, url, n)
+                ORDER BY n",
+            )
+            .await?;
+        let ecosystems: Vec<Option<String>> = packages
+            .iter()
+            .map(|package| package.ecosystem.as_ref().map(Ecosystem::to_string))
+            .collect();
+        let names: Vec<&str> = packages.iter().map(|package| package.package_name.as_str()).collect();
+        let requirements: Vec<Option<&str>> = packages
+            .iter()
+            .map(|package| package.requirements.as_deref())
+            .collect();
+        let manifests: Vec<Option<&str>> = packages
+            .iter()
+            .map(|package| package.manifest.as_deref())
+            .collect();
+        let repositories: Vec<Option<String>> = packages
+            .iter()
+            .map(|package| package.repository_id().as_ref().map(RepositoryId::path))
+            .collect();
+        let urls: Vec<Option<&str>> = packages
+            .iter()
+            .map(|package| package.url.as_deref())
+            .collect();
+        transaction
+            .execute(
+                &insert_packages,
+                &[&path, &ecosystems, &names, &requirements, &manifests, &repositories, &urls],
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn repository_packages(
+        &self,
+        link: &RepositoryId,
+        ecosystem: Option<&str>,
+    ) -> Result<Vec<Dependency>, DatabaseError> {
+        let path = link.path();
+        let path = path.as_str();
+        debug!("Getting packages of repository {} from database", path);
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT ecosystem, package_name, requirements, manifest, repository, url
+                FROM repository_packages
+                WHERE parent = $1 AND ($2::VARCHAR IS NULL OR ecosystem = $2)
+                ORDER BY id",
+            )
+            .await?;
+        Ok(client
+            .query(&statement, &[&path, &ecosystem])
+            .await?
+            .iter()
+            .map(|row| package_from_row(row, 0))
+            .collect())
+    }
+
+    async fn unresolved_dependencies(
+        &self,
+        paths: &[String],
+    ) -> Result<Vec<(String, Dependency)>, DatabaseError> {
+        debug!("Getting unresolved dependencies of {} repositories from database", paths.len());
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT parent, ecosystem, package_name, requirements, manifest, repository, url
+                FROM repository_packages
+                WHERE parent = ANY($1) AND repository IS NULL
+                ORDER BY parent, id",
+            )
+            .await?;
+        Ok(client
+            .query(&statement, &[&paths])
+            .await?
+            .iter()
+            .map(|row| (row.get(0), package_from_row(row, 1)))
+            .collect())
+    }
+
+    async fn repository_dependents(
+        &self,
+        link: &RepositoryId,
+    ) -> Result<Vec<(String, Option<i32>)>, DatabaseError> {
+        let path = link.path();
+        let path = path.as_str();
+        debug!("Getting dependents of repository {} from database", path);
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT edges.parent, repositories.contributors
+                FROM repository_edges edges
+                JOIN repositories ON repositories.path = edges.parent
+                WHERE edges.child = $1
+                ORDER BY repositories.contributors DESC NULLS LAST, edges.parent",
+            )
+            .await?;
+        Ok(client
+            .query(&statement, &[&path])
+            .await?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect())
+    }
+
+    async fn dependency_graph(
+        &self,
+        link: &RepositoryId,
+        max_depth: i32,
+    ) -> Result<DependencyGraph, DatabaseError> {
+        let path = link.path();
+        let path = path.as_str();
+        debug!("Getting dependency graph of repository {} from database", path);
+        let client = self.pool.get().await?;
+        let nodes = client
+            .prepare_cached(
+                "WITH RECURSIVE graph(path, depth) AS (
+                    SELECT $1::TEXT, 0
+                    UNION
+                    SELECT edges.child, graph.depth + 1
+                    FROM graph
+                    JOIN repository_edges edges ON edges.parent = graph.path
+                    WHERE graph.depth < $2
+                )
+                SELECT graph.path, MIN(graph.depth) AS depth, repositories.contributors,
+                    repositories.dependencies IS NOT NULL, repositories.updated_at,
+                    repositories.valid_until, repositories.bus_factor, repositories.top_share
+                FROM graph
+                LEFT JOIN repositories ON repositories.path = graph.path
+                GROUP BY graph.path, repositories.path
+                ORDER BY depth, graph.path",
+            )
+            .await?;
+        let nodes: Vec<GraphNode> = client
+            .query(&nodes, &[&path, &max_depth])
+            .await?
+            .into_iter()
+            .map(|row| GraphNode {
+                path: row.get(0),
+                depth: row.get(1),
+                contributors: row.get(2),
+                crawled: row.get::<_, Option<bool>>(3).unwrap_or(false),
+                updated_at: row.get(4),
+                valid_until: row.get(5),
+                bus_factor: row.get(6),
+                top_share: row.get(7),
+            })
+            .collect();
+
+        let paths: Vec<&str> = nodes.iter().map(|node| node.path.as_str()).collect();
+        let edges = client
+            .prepare_cached(
+                "SELECT parent, child FROM repository_edges
+                WHERE parent = ANY($1) AND child = ANY($1)
+                ORDER BY parent, child",
+            )
+            .await?;
+        let edges = client
+            .query(&edges, &[&paths])
+            .await?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect();
+        Ok(DependencyGraph { nodes, edges })
+    }
+
+    async fn repository_history(
+        &self,
+        link: &RepositoryId,
+    ) -> Result<Vec<(DateTime<Utc>, i32)>, DatabaseError> {
+        let path = link.path();
+        let path = path.as_str();
+        debug!("Getting contributors history of repository {} from database", path);
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT observed_at, contributors
+                FROM repository_contributors_history
+                WHERE path = $1
+                ORDER BY observed_at",
+            )
+            .await?;
+        Ok(client
+            .query(&statement, &[&path])
+            .await?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect())
+    }
+
+    async fn insert_repository_identities(
+        &self,
+        link: &RepositoryId,
+        identities: &[(String, bool)],
+    ) -> Result<(), DatabaseError> {
+        let path = link.path();
+        let path = path.as_str();
+        debug!("Setting {} contributor identities for repository {} in database", identities.len(), path);
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        let insert_repository = transaction
+            .prepare_cached(
+                "INSERT INTO repositories (path) VALUES ($1)
+                ON CONFLICT (path) DO NOTHING",
+            )
+            .await?;
+        transaction.execute(&insert_repository, &[&path]).await?;
+        let delete = transaction
+            .prepare_cached("DELETE FROM repository_contributor_identities WHERE path = $1")
+            .await?;
+        transaction.execute(&delete, &[&path]).await?;
+        let insert = transaction
+            .prepare_cached(
+                "INSERT INTO repository_contributor_identities (path, identity, bot)
+                SELECT $1, identity, bot
+                FROM unnest($2::VARCHAR(320)[], $3::BOOLEAN[]) AS t(identity, bot)
+                ON CONFLICT DO NOTHING",
+            )
+            .await?;
+        let (names, bots): (Vec<&str>, Vec<bool>) = identities
+            .iter()
+            .map(|(identity, bot)| (identity.as_str(), *bot))
+            .unzip();
+        transaction.execute(&insert, &[&path, &names, &bots]).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_repository_concentration(
+        &self,
+        link: &RepositoryId,
+        concentration: Option<Concentration>,
+    ) -> Result<(), DatabaseError> {
+        let path = link.path();
+        let path = path.as_str();
+        debug!("Setting contributions concentration {concentration:?} for repository {path} in database");
+        let bus_factor = concentration.map(|concentration| concentration.bus_factor as i32);
+        let top_share = concentration.map(|concentration| concentration.top_share);
         self.execute_cached(
-            "INSERT INTO repositories (path, dependencies)
-            VALUES ($1, $2)
+            "INSERT INTO repositories (path, bus_factor, top_share)
+            VALUES ($1, $2, $3)
             ON CONFLICT (path) DO UPDATE
-            SET path = $1, dependencies = $2",
-            &[&path, &dependencies],
+            SET bus_factor = $2, top_share = $3",
+            &[&path, &bus_factor, &top_share],
         )
         .await?;
         Ok(())
     }
+
+    async fn unique_contributors(
+        &self,
+        paths: &[String],
+    ) -> Result<UniqueContributors, DatabaseError> {
+        debug!("Counting unique contributors of {} repositories from database", paths.len());
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT COUNT(DISTINCT identity) FILTER (WHERE NOT bot),
+                    COUNT(DISTINCT identity) FILTER (WHERE bot),
+                    COUNT(DISTINCT path)
+                FROM repository_contributor_identities
+                WHERE path = ANY($1)",
+            )
+            .await?;
+        let row = client.query_one(&statement, &[&paths]).await?;
+        Ok(UniqueContributors {
+            contributors: row.get::<_, i64>(0) as usize,
+            bots: row.get::<_, i64>(1) as usize,
+            repositories: row.get::<_, i64>(2) as usize,
+        })
+    }
+
+    async fn enqueue_crawl_job(
+        &self,
+        path: &str,
+        max_depth: i32,
+        max_nodes: i32,
+    ) -> Result<Uuid, DatabaseError> {
+        debug!("Queueing crawl of {path} in database");
+        // Updating the active crawl returns it in the same statement, even if
+        // it finishes meanwhile
+        let row = self
+            .query_one_cached(
+                "INSERT INTO crawl_jobs (path, max_depth, max_nodes)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (path, max_depth, max_nodes) WHERE status IN ('pending', 'running')
+                DO UPDATE SET path = EXCLUDED.path
+                RETURNING id",
+                &[&path, &max_depth, &max_nodes],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    async fn last_crawl_job(
+        &self,
+        path: &str,
+        max_depth: i32,
+        max_nodes: i32,
+    ) -> Result<Option<LastCrawlJob>, DatabaseError> {
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT id, status, truncated FROM crawl_jobs
+                WHERE path = $1 AND max_depth = $2 AND max_nodes = $3
+                ORDER BY created_at DESC
+                LIMIT 1",
+            )
+            .await?;
+        Ok(client
+            .query_opt(&statement, &[&path, &max_depth, &max_nodes])
+            .await?
+            .map(|row| LastCrawlJob {
+                id: row.get(0),
+                done: row.get::<_, String>(1) == "done",
+                truncated: row.get(2),
+            }))
+    }
+
+    async fn claim_crawl_job(&self, stale_after: Duration) -> Result<Option<CrawlJob>, DatabaseError> {
+        let stale_after = stale_after.as_secs_f64();
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "UPDATE crawl_jobs
+                SET status = 'running', claim_id = gen_random_uuid(), heartbeat_at = now(),
+                    updated_at = now()
+                WHERE id = (
+                    SELECT id FROM crawl_jobs
+                    WHERE status = 'pending'
+                    OR (status = 'running' AND heartbeat_at < now() - make_interval(secs => $1))
+                    ORDER BY created_at
+                    LIMIT 1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, claim_id, path, max_depth, max_nodes, checkpoint",
+            )
+            .await?;
+        let Some(row) = client.query_opt(&statement, &[&stale_after]).await? else {
+            return Ok(None);
+        };
+        let checkpoint: Option<Json<CrawlCheckpoint>> = row.get(5);
+        Ok(Some(CrawlJob {
+            id: row.get(0),
+            claim_id: row.get(1),
+            path: row.get(2),
+            max_depth: row.get(3),
+            max_nodes: row.get(4),
+            checkpoint: checkpoint.map(|Json(checkpoint)| checkpoint),
+        }))
+    }
+
+    async fn crawl_job_events(
+        &self,
+        id: Uuid,
+        first_seq: usize,
+    ) -> Result<Vec<ServerMessage>, DatabaseError> {
+        let first_seq = first_seq as i32;
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT message FROM crawl_job_events
+                WHERE job_id = $1 AND seq >= $2
+                ORDER BY seq",
+            )
+            .await?;
+        Ok(client
+            .query(&statement, &[&id, &first_seq])
+            .await?
+            .into_iter()
+            .map(|row| row.get::<_, Json<ServerMessage>>(0).0)
+            .collect())
+    }
+
+    async fn crawl_job_done(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let client = self.pool.get().await?;
+        let statement = client
+            .prepare_cached("SELECT status FROM crawl_jobs WHERE id = $1")
+            .await?;
+        Ok(client
+            .query_opt(&statement, &[&id])
+            .await?
+            .is_none_or(|row| row.get::<_, String>(0) == "done"))
+    }
+
+    async fn checkpoint_crawl_job(
+        &self,
+        id: Uuid,
+        claim_id: Uuid,
+        checkpoint: &CrawlCheckpoint,
+        first_seq: usize,
+        events: &[ServerMessage],
+    ) -> Result<bool, DatabaseError> {
+        debug!("Saving checkpoint of crawl {id} in database");
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        // Updated first, so that the job cannot be claimed again until the
+        // messages are saved
+        let claimed = transaction
+            .execute(
+                "UPDATE crawl_jobs
+                SET checkpoint = $3, heartbeat_at = now(), updated_at = now()
+                WHERE id = $1 AND claim_id = $2",
+                &[&id, &claim_id, &Json(checkpoint)],
+            )
+            .await?;
+        if claimed == 0 {
+            return Ok(false);
+        }
+        let insert = transaction
+            .prepare_cached("INSERT INTO crawl_job_events (job_id, seq, message) VALUES ($1, $2, $3)")
+            .await?;
+        for (offset, message) in events.iter().enumerate() {
+            let seq = (first_seq + offset) as i32;
+            transaction
+                .execute(&insert, &[&id, &seq, &Json(message)])
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    async fn heartbeat_crawl_job(&self, id: Uuid, claim_id: Uuid) -> Result<(), DatabaseError> {
+        self.execute_cached(
+            "UPDATE crawl_jobs SET heartbeat_at = now() WHERE id = $1 AND claim_id = $2",
+            &[&id, &claim_id],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn finish_crawl_job(
+        &self,
+        id: Uuid,
+        claim_id: Uuid,
+        truncated: bool,
+    ) -> Result<(), DatabaseError> {
+        debug!("Crawl {id} done");
+        self.execute_cached(
+            "UPDATE crawl_jobs
+            SET status = 'done', truncated = $3, updated_at = now()
+            WHERE id = $1 AND claim_id = $2",
+            &[&id, &claim_id, &truncated],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_finished_crawl_jobs(&self, older_than: Duration) -> Result<u64, DatabaseError> {
+        let older_than = older_than.as_secs_f64();
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        transaction
+            .execute(
+                "DELETE FROM crawl_job_events
+                WHERE job_id IN (
+                    SELECT id FROM crawl_jobs
+                    WHERE status = 'done' AND updated_at < now() - make_interval(secs => $1)
+                )",
+                &[&older_than],
+            )
+            .await?;
+        let deleted = transaction
+            .execute(
+                "DELETE FROM crawl_jobs
+                WHERE status = 'done' AND updated_at < now() - make_interval(secs => $1)
+                AND id NOT IN (
+                    SELECT DISTINCT ON (path, max_depth, max_nodes) id FROM crawl_jobs
+                    WHERE status = 'done'
+                    ORDER BY path, max_depth, max_nodes, updated_at DESC
+                )",
+                &[&older_than],
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs the Postgres database told by the DATABASE_* variables"]
+    async fn test_reclaimed_job_drops_previous_worker_writes() {
+        let config = Config::from_env().unwrap();
+        let mut database = PostgresDatabase::from(&config).await.unwrap();
+        database.init(&config).await.unwrap();
+        let path = format!("{}/stale", Uuid::new_v4().simple());
+        let id = database.enqueue_crawl_job(&path, 1, 1).await.unwrap();
+        // Claimed as soon as queued, then at once considered stale
+        let claim = || async {
+            loop {
+                let job = database.claim_crawl_job(Duration::ZERO).await.unwrap().unwrap();
+                if job.id == id {
+                    return job;
+                }
+                // Left by a previous run
+                database
+                    .finish_crawl_job(job.id, job.claim_id, false)
+                    .await
+                    .unwrap();
+            }
+        };
+        let first = claim().await;
+        let second = claim().await;
+        assert_ne!(first.claim_id, second.claim_id);
+        let checkpoint = CrawlCheckpoint::default();
+        let messages = vec![ServerMessage::Progress {
+            discovered: 1,
+            processed: 0,
+        }];
+
+        let saved = database
+            .checkpoint_crawl_job(id, first.claim_id, &checkpoint, 0, &messages)
+            .await
+            .unwrap();
+        assert!(!saved);
+        assert!(database.crawl_job_events(id, 0).await.unwrap().is_empty());
+        database
+            .finish_crawl_job(id, first.claim_id, false)
+            .await
+            .unwrap();
+        assert!(!database.crawl_job_done(id).await.unwrap());
+
+        let saved = database
+            .checkpoint_crawl_job(id, second.claim_id, &checkpoint, 0, &messages)
+            .await
+            .unwrap();
+        assert!(saved);
+        assert_eq!(database.crawl_job_events(id, 0).await.unwrap(), messages);
+        database
+            .finish_crawl_job(id, second.claim_id, false)
+            .await
+            .unwrap();
+        assert!(database.crawl_job_done(id).await.unwrap());
+    }
 }
@@ -10,6 +10,8 @@ pub enum DatabaseError {
     InvalidColumn { expected: usize, got: usize },
     #[error("{0}")]
     AlreadyExists(String),
+    #[error("Database schema version {found} is newer than the supported {supported}")]
+    SchemaTooNew { found: i32, supported: i32 },
     #[error(transparent)]
     PostgresError(tokio_postgres::Error),
     #[error(transparent)]
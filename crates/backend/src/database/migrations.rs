@@ -0,0 +1,160 @@
+use super::errors::DatabaseError;
+use deadpool_postgres::Client;
+use tracing::{debug, info};
+
+/// A change of the schema, applied once in a transaction.
+pub(crate) struct Migration {
+    pub(crate) version: i32,
+    pub(crate) name: &'static str,
+    pub(crate) sql: &'static str,
+}
+
+/// All the migrations, in the order of their versions.
+///
+/// Applied migrations must not be modified, add a new one instead.
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initialization",
+        sql: include_str!("../../migrations/0001_initialization.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "crawl_jobs",
+        sql: include_str!("../../migrations/0002_crawl_jobs.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "repository_edges",
+        sql: include_str!("../../migrations/0003_repository_edges.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "contributors_history",
+        sql: include_str!("../../migrations/0004_contributors_history.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "contributor_identities",
+        sql: include_str!("../../migrations/0005_contributor_identities.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "bot_contributors",
+        sql: include_str!("../../migrations/0006_bot_contributors.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "bus_factor",
+        sql: include_str!("../../migrations/0007_bus_factor.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "repository_packages",
+        sql: include_str!("../../migrations/0008_repository_packages.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "unresolved_dependencies",
+        sql: include_str!("../../migrations/0009_unresolved_dependencies.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "crawl_truncation",
+        sql: include_str!("../../migrations/0010_crawl_truncation.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "package_forges",
+        sql: include_str!("../../migrations/0011_package_forges.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "path_lengths",
+        sql: include_str!("../../migrations/0012_path_lengths.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "crawl_job_claims",
+        sql: include_str!("../../migrations/0013_crawl_job_claims.sql"),
+    },
+];
+
+/// Key of the advisory lock preventing backends from migrating concurrently
+const MIGRATION_LOCK: i64 = 0x686d63;
+
+/// Version of the schema this binary understands.
+pub(crate) fn latest_version() -> i32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Applies the migrations not applied yet, refusing a schema newer than the
+/// ones known by this binary.
+pub(crate) async fn migrate(client: &mut Client) -> Result<(), DatabaseError> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version     INTEGER PRIMARY KEY,
+                name        VARCHAR(150) NOT NULL,
+                applied_at  TIMESTAMP WITH TIME ZONE    DEFAULT now()
+            )",
+        )
+        .await?;
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK])
+        .await?;
+    let migrated = apply_pending(client).await;
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK])
+        .await?;
+    migrated
+}
+
+async fn apply_pending(client: &mut Client) -> Result<(), DatabaseError> {
+    let current: i32 = client
+        .query_one(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            &[],
+        )
+        .await?
+        .get(0);
+    let latest = latest_version();
+    if current > latest {
+        return Err(DatabaseError::SchemaTooNew {
+            found: current,
+            supported: latest,
+        });
+    }
+    debug!("Database schema at version {current}");
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        info!(
+            "Applying database migration {} {}",
+            migration.version, migration.name
+        );
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(migration.sql).await?;
+        transaction
+            .execute(
+                "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)",
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+        transaction.commit().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_follow_each_other() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i32 + 1, "{}", migration.name);
+            assert!(!migration.sql.trim().is_empty(), "{}", migration.name);
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as i32);
+    }
+}
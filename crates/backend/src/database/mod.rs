@@ -1,5 +1,6 @@
 pub mod database;
 pub mod errors;
+mod migrations;
 pub mod models;
 
 pub use database::Database;
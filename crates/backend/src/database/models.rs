@@ -1,4 +1,6 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 pub struct RepositoryInfo {
     pub path: String,
@@ -7,4 +9,72 @@ pub struct RepositoryInfo {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub valid_until: DateTime<Utc>,
+    pub bus_factor: Option<i32>,
+    pub top_share: Option<f64>,
+}
+
+/// State of a crawl traversal, saved to resume it after a restart.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CrawlCheckpoint {
+    /// Repositories left to explore, with their depth, in their order
+    #[serde(alias = "stack")]
+    pub queue: Vec<(String, usize)>,
+    pub treated: Vec<String>,
+    pub processed: usize,
+    pub contributors: usize,
+    pub truncated: bool,
+}
+
+/// The last crawl of a repository with some limits.
+pub struct LastCrawlJob {
+    pub id: Uuid,
+    pub done: bool,
+    /// Whether it stopped on its limits, unknown until it is done
+    pub truncated: Option<bool>,
+}
+
+pub struct CrawlJob {
+    pub id: Uuid,
+    /// Claim of the worker running it, see [`Database::claim_crawl_job`](super::Database::claim_crawl_job)
+    pub claim_id: Uuid,
+    pub path: String,
+    pub max_depth: i32,
+    pub max_nodes: i32,
+    pub checkpoint: Option<CrawlCheckpoint>,
+}
+
+/// A repository of the known dependency graph of another one.
+#[derive(Serialize, Debug, Clone)]
+pub struct GraphNode {
+    pub path: String,
+    /// Lowest number of edges from the root of the graph
+    pub depth: i32,
+    pub contributors: Option<i32>,
+    /// Fewest people who authored more than half of the commits, when known
+    pub bus_factor: Option<i32>,
+    /// Share of the commits of the top contributor, when known
+    pub top_share: Option<f64>,
+    /// Whether the dependencies of the repository were ever fetched
+    pub crawled: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+/// The dependency graph of a repository, as far as it was crawled.
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    /// Parent and child of each dependency between the nodes
+    pub edges: Vec<(String, String)>,
+}
+
+/// Distinct people among the contributors of several repositories.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UniqueContributors {
+    /// People, the bots are not counted
+    pub contributors: usize,
+    /// Distinct bots, such as `dependabot[bot]`
+    pub bots: usize,
+    /// Number of repositories whose contributors are known, the other ones
+    /// are not counted
+    pub repositories: usize,
 }
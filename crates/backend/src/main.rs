@@ -2,6 +2,7 @@
 mod api;
 mod config;
 mod cache;
+mod crawler;
 mod errors;
 mod models;
 mod program;
@@ -15,6 +15,15 @@ pub fn init() {
         "
     );
 
+    info!("Initializing metrics: single_flight_joined");
+    metrics::describe_counter!(
+        "single_flight_joined",
+        metrics::Unit::Count,
+        "Count of fetches awaited from a concurrent crawl instead of being repeated. Labels:
+            - from: dependencies, contributors
+        "
+    );
+
     info!("Initializing metrics: ws_sent");
     metrics::describe_counter!(
         "ws_sent",
@@ -28,4 +37,52 @@ pub fn init() {
         metrics::Unit::Count,
         "Count of errors."
     );
+
+    info!("Initializing metrics: github_rate_limit_remaining");
+    metrics::describe_gauge!(
+        "github_rate_limit_remaining",
+        metrics::Unit::Count,
+        "Remaining GitHub requests quota, as reported by GitHub."
+    );
+
+    info!("Initializing metrics: github_rate_limit_tokens");
+    metrics::describe_gauge!(
+        "github_rate_limit_tokens",
+        metrics::Unit::Count,
+        "Requests that can be sent to GitHub right away."
+    );
+
+    info!("Initializing metrics: github_rate_limit_blocked_seconds");
+    metrics::describe_gauge!(
+        "github_rate_limit_blocked_seconds",
+        metrics::Unit::Seconds,
+        "Duration of the last pause of the requests to GitHub."
+    );
+
+    info!("Initializing metrics: github_rate_limited");
+    metrics::describe_counter!(
+        "github_rate_limited",
+        metrics::Unit::Count,
+        "Count of rate limited GitHub responses. Labels:
+            - kind: primary, secondary
+        "
+    );
+
+    info!("Initializing metrics: github_retries");
+    metrics::describe_counter!(
+        "github_retries",
+        metrics::Unit::Count,
+        "Count of retried GitHub requests. Labels:
+            - reason: rate_limit, server_error, network
+        "
+    );
+
+    info!("Initializing metrics: github_token_remaining");
+    metrics::describe_gauge!(
+        "github_token_remaining",
+        metrics::Unit::Count,
+        "Number of GitHub API requests left for each configured token. Labels:
+            - token: index of the token in the configuration
+        "
+    );
 }
@@ -1,10 +1,29 @@
+use std::collections::HashSet;
 use std::fmt;
 
+use crate::database::models::{GraphNode, UniqueContributors};
+use github_scrapper::{Dependency, RepositoryId};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct Link {
     pub link: String,
+    /// Version of the WebSocket protocol requested by the client, see `Protocol`
+    #[serde(default)]
+    pub protocol: Option<u32>,
+    /// Depth at which the crawl stops, within the server limit
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Number of repositories at which the crawl stops, within the server limit
+    #[serde(default)]
+    pub max_nodes: Option<usize>,
+    /// Session to resume after a reconnection, from its `hello` message
+    #[serde(default)]
+    pub session_id: Option<Uuid>,
+    /// Sequence number of the last message received before the reconnection
+    #[serde(default)]
+    pub last_seq: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -24,3 +43,524 @@ impl fmt::Display for ContributorsChunk {
         write!(f, "{}:{}", self.path, self.contributors)
     }
 }
+
+/// Reason for a dependencies session to fail.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum ErrorCode {
+    InvalidLink,
+    NotFound,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::InvalidLink => write!(f, "INVALID_LINK"),
+            ErrorCode::NotFound => write!(f, "NOT_FOUND"),
+        }
+    }
+}
+
+/// Message sent to the client of a dependencies session using the JSON protocol.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ServerMessage {
+    /// First message of the session, once the repository is known to exist.
+    /// `session_id` resumes the session after a reconnection.
+    Hello {
+        protocol_version: u32,
+        repository: String,
+        session_id: Uuid,
+    },
+    /// A repository of the dependency graph and its contributors, `depth` is
+    /// the number of edges from the searched repository when first reached
+    Repository {
+        path: String,
+        contributors: usize,
+        depth: usize,
+        /// People and bots among the contributors, when their identities are known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        humans: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bots: Option<usize>,
+        /// Fewest people who authored more than half of the commits, and the
+        /// share of the commits of the top one, when known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bus_factor: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        top_share: Option<f64>,
+    },
+    /// `child` is a dependency of `parent`
+    Edge { parent: String, child: String },
+    /// A package `parent` depends on, see [`Package`]
+    Package {
+        parent: String,
+        ecosystem: Option<String>,
+        name: String,
+        requirements: Option<String>,
+        manifest: Option<String>,
+        repository: Option<String>,
+        url: Option<String>,
+        unresolved: Option<String>,
+    },
+    /// Number of repositories found so far, and how many were crawled
+    Progress { discovered: usize, processed: usize },
+    /// A non fatal error, the crawl goes on
+    Warning { path: String, message: String },
+    /// The whole dependency graph was sent, or as much as the crawl limits
+    /// allowed when `truncated`
+    Done {
+        repositories: usize,
+        contributors: usize,
+        /// Distinct people among the contributors of the repositories whose
+        /// contributors are known, when any is
+        #[serde(default)]
+        unique_contributors: Option<usize>,
+        /// Distinct bots among them, which are not counted as people
+        #[serde(default)]
+        unique_bots: Option<usize>,
+        /// Distinct packages that do not resolve to a repository, whose
+        /// contributors are missing from the counts, when known
+        #[serde(default)]
+        unresolved: Option<usize>,
+        truncated: bool,
+    },
+    /// The session failed and is about to be closed
+    Error { code: ErrorCode, message: String },
+}
+
+/// Highest bus factor of a fragile repository, whose commits depend on one or
+/// two people.
+pub(crate) const LOW_BUS_FACTOR: i32 = 2;
+
+impl ServerMessage {
+    pub(crate) fn package(parent: &RepositoryId, dependency: &Dependency) -> Self {
+        let package = Package::from(dependency);
+        ServerMessage::Package {
+            parent: parent.path(),
+            ecosystem: package.ecosystem,
+            name: package.name,
+            requirements: package.requirements,
+            manifest: package.manifest,
+            repository: package.repository,
+            url: package.url,
+            unresolved: package.unresolved,
+        }
+    }
+}
+
+/// A package a repository depends on.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Package {
+    /// Lowercase package manager, such as `cargo` or `npm`, when known
+    pub ecosystem: Option<String>,
+    pub name: String,
+    /// Version requirement of the manifest, such as `^1.2`
+    pub requirements: Option<String>,
+    /// Path of the manifest declaring the dependency
+    pub manifest: Option<String>,
+    /// Path of the repository of the package, when it resolved to one: `owner/repo`
+    /// on GitHub, `host/owner/repo` on the other forges
+    pub repository: Option<String>,
+    /// Repository URL of the package as listed, whatever its host
+    pub url: Option<String>,
+    /// Why the package does not resolve to a repository, when it does not
+    pub unresolved: Option<String>,
+}
+
+impl From<&Dependency> for Package {
+    fn from(dependency: &Dependency) -> Self {
+        Self {
+            ecosystem: dependency.ecosystem.as_ref().map(ToString::to_string),
+            name: dependency.package_name.clone(),
+            requirements: dependency.requirements.clone(),
+            manifest: dependency.manifest.clone(),
+            repository: dependency.repository_id().as_ref().map(RepositoryId::path),
+            url: dependency.url.clone(),
+            unresolved: dependency.unresolved().as_ref().map(ToString::to_string),
+        }
+    }
+}
+
+/// A package of a repository of the graph that does not resolve to a repository
+/// of a supported forge, and whose contributors are therefore not counted.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnresolvedDependency {
+    /// The repository depending on the package
+    pub parent: String,
+    pub ecosystem: Option<String>,
+    pub name: String,
+    pub url: Option<String>,
+    pub reason: String,
+}
+
+impl UnresolvedDependency {
+    /// The dependency of `parent`, `None` when it resolves to a repository.
+    pub(crate) fn new(parent: String, dependency: &Dependency) -> Option<Self> {
+        let reason = dependency.unresolved()?;
+        Some(Self {
+            parent,
+            ecosystem: dependency.ecosystem.as_ref().map(ToString::to_string),
+            name: dependency.package_name.clone(),
+            url: dependency.url.clone(),
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Number of distinct packages among the dependencies, a package depended
+    /// on by several repositories or manifests being counted once.
+    pub(crate) fn distinct(dependencies: &[Self]) -> usize {
+        dependencies
+            .iter()
+            .map(|dependency| (&dependency.ecosystem, &dependency.name))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Query of the packages of a repository.
+#[derive(Deserialize, Debug)]
+pub(crate) struct PackagesQuery {
+    /// Only the packages of this ecosystem, such as `cargo`, when given
+    #[serde(default)]
+    pub ecosystem: Option<String>,
+}
+
+/// The known dependency graph of a repository, answered from the database.
+#[derive(Serialize, Debug)]
+pub(crate) struct DependencyReport {
+    pub repository: String,
+    pub repositories: usize,
+    /// Sum of the known contributors of the repositories
+    pub contributors: usize,
+    /// Distinct people among them, when the contributors of any repository are known
+    pub unique_contributors: Option<UniqueContributors>,
+    /// Repositories whose bus factor is at most [`LOW_BUS_FACTOR`], the most
+    /// fragile first
+    pub low_bus_factor: Vec<String>,
+    pub nodes: Vec<GraphNode>,
+    /// `[parent, child]` pairs
+    pub edges: Vec<(String, String)>,
+    /// Repositories whose dependencies were never fetched, within the depth
+    /// limit, such as the ones the last crawl stopped at
+    pub never_crawled: Vec<String>,
+    /// Packages that do not resolve to a repository, the contributors
+    /// being a lower bound when there are any
+    pub unresolved: Vec<UnresolvedDependency>,
+    /// Distinct packages among them
+    pub unresolved_packages: usize,
+    /// Whether the last crawl stopped on the server limits before the end of
+    /// the graph, unknown until one is done
+    pub truncated: Option<bool>,
+    /// The crawl running or queued to refresh the report, if any
+    pub job_id: Option<Uuid>,
+}
+
+impl DependencyReport {
+    /// Paths of the nodes whose bus factor is at most [`LOW_BUS_FACTOR`],
+    /// lowest bus factor then highest top contributor share first.
+    pub(crate) fn low_bus_factor(nodes: &[GraphNode]) -> Vec<String> {
+        let mut fragile: Vec<&GraphNode> = nodes
+            .iter()
+            .filter(|node| {
+                node.bus_factor
+                    .is_some_and(|bus_factor| bus_factor <= LOW_BUS_FACTOR)
+            })
+            .collect();
+        fragile.sort_by(|a, b| {
+            let top_share = |node: &GraphNode| node.top_share.unwrap_or_default();
+            a.bus_factor
+                .cmp(&b.bus_factor)
+                .then_with(|| top_share(b).total_cmp(&top_share(a)))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        fragile.into_iter().map(|node| node.path.clone()).collect()
+    }
+}
+
+/// Query of the dependency graph export.
+#[derive(Deserialize, Debug)]
+pub(crate) struct ExportQuery {
+    /// One of `dot`, `graphml`, `csv-nodes`, `csv-edges` or `json`, defaults to `json`
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// Query of a repository badge.
+#[derive(Deserialize, Debug)]
+pub(crate) struct BadgeQuery {
+    /// One of `flat`, `flat-square` or `for-the-badge`, defaults to `flat`
+    #[serde(default)]
+    pub style: Option<String>,
+    /// Text on the left of the badge
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// A report is not available until the crawl of the repository is done.
+#[derive(Serialize, Debug)]
+pub(crate) struct CrawlQueued {
+    pub job_id: Uuid,
+}
+
+/// An SBOM was imported, its report is not available until the crawl of its
+/// packages is done.
+#[derive(Serialize, Debug)]
+pub(crate) struct SbomImported {
+    /// `sbom/FORMAT/ID` path of the root of the graph of its packages
+    pub repository: String,
+    pub packages: usize,
+    pub job_id: Uuid,
+}
+
+/// Query of the comparison of two repositories, given as `owner/repo` paths.
+#[derive(Deserialize, Debug)]
+pub(crate) struct CompareQuery {
+    pub left: String,
+    pub right: String,
+}
+
+/// One of the compared repositories.
+#[derive(Serialize, Debug)]
+pub(crate) struct ComparedRepository {
+    pub repository: String,
+    /// Whether its dependencies are known, its sets are empty otherwise
+    pub crawled: bool,
+    /// The crawl queued because the repository was never crawled, if any
+    pub job_id: Option<Uuid>,
+}
+
+/// Dependencies of one or both of the compared repositories.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub(crate) struct DependencySet {
+    /// `[path, contributors]` pairs, most contributors first
+    pub repositories: Vec<(String, Option<i32>)>,
+    /// Sum of the known contributors of the repositories
+    pub contributors: usize,
+}
+
+impl DependencySet {
+    fn new<'a>(nodes: impl Iterator<Item = &'a GraphNode>) -> Self {
+        let mut repositories: Vec<(String, Option<i32>)> = nodes
+            .map(|node| (node.path.clone(), node.contributors))
+            .collect();
+        repositories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let contributors = repositories
+            .iter()
+            .filter_map(|(_, contributors)| *contributors)
+            .map(|contributors| contributors as usize)
+            .sum();
+        Self {
+            repositories,
+            contributors,
+        }
+    }
+}
+
+/// The transitive dependencies of two repositories, split between the shared
+/// ones and the ones unique to each side.
+#[derive(Serialize, Debug)]
+pub(crate) struct DependencyComparison {
+    pub left: ComparedRepository,
+    pub right: ComparedRepository,
+    pub shared: DependencySet,
+    pub left_only: DependencySet,
+    pub right_only: DependencySet,
+}
+
+impl DependencyComparison {
+    /// Compares the nodes of the dependency graphs of both repositories, the
+    /// roots not being dependencies of themselves.
+    pub(crate) fn new(
+        left: ComparedRepository,
+        left_nodes: &[GraphNode],
+        right: ComparedRepository,
+        right_nodes: &[GraphNode],
+    ) -> Self {
+        let dependencies = |nodes: &'_ [GraphNode], root: &str| -> HashSet<String> {
+            nodes
+                .iter()
+                .filter(|node| node.path != root)
+                .map(|node| node.path.clone())
+                .collect()
+        };
+        let left_paths = dependencies(left_nodes, &left.repository);
+        let right_paths = dependencies(right_nodes, &right.repository);
+
+        Self {
+            shared: DependencySet::new(
+                left_nodes
+                    .iter()
+                    .filter(|node| left_paths.contains(&node.path))
+                    .filter(|node| right_paths.contains(&node.path)),
+            ),
+            left_only: DependencySet::new(
+                left_nodes
+                    .iter()
+                    .filter(|node| left_paths.contains(&node.path))
+                    .filter(|node| !right_paths.contains(&node.path)),
+            ),
+            right_only: DependencySet::new(
+                right_nodes
+                    .iter()
+                    .filter(|node| right_paths.contains(&node.path))
+                    .filter(|node| !left_paths.contains(&node.path)),
+            ),
+            left,
+            right,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use github_scrapper::GitHubLink;
+
+    fn node(path: &str, contributors: Option<i32>) -> GraphNode {
+        GraphNode {
+            path: path.to_string(),
+            depth: 0,
+            contributors,
+            bus_factor: None,
+            top_share: None,
+            crawled: true,
+            updated_at: None,
+            valid_until: None,
+        }
+    }
+
+    fn compared(repository: &str) -> ComparedRepository {
+        ComparedRepository {
+            repository: repository.to_string(),
+            crawled: true,
+            job_id: None,
+        }
+    }
+
+    #[test]
+    fn test_comparison_splits_dependencies() {
+        let left = [
+            node("tokio-rs/tokio", Some(800)),
+            node("rust-lang/libc", Some(600)),
+            node("tokio-rs/mio", Some(200)),
+            node("smol-rs/smol", Some(50)),
+        ];
+        let right = [
+            node("smol-rs/smol", Some(50)),
+            node("rust-lang/libc", Some(600)),
+            node("smol-rs/polling", None),
+        ];
+
+        let comparison = DependencyComparison::new(
+            compared("tokio-rs/tokio"),
+            &left,
+            compared("smol-rs/smol"),
+            &right,
+        );
+
+        assert_eq!(
+            comparison.shared.repositories,
+            vec![("rust-lang/libc".to_string(), Some(600))]
+        );
+        // The right root is a dependency of the left one only
+        assert_eq!(
+            comparison.left_only.repositories,
+            vec![
+                ("tokio-rs/mio".to_string(), Some(200)),
+                ("smol-rs/smol".to_string(), Some(50)),
+            ]
+        );
+        assert_eq!(comparison.left_only.contributors, 250);
+        assert_eq!(
+            comparison.right_only,
+            DependencySet {
+                repositories: vec![("smol-rs/polling".to_string(), None)],
+                contributors: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_low_bus_factor_most_fragile_first() {
+        let concentrated = |path: &str, bus_factor: i32, top_share: f64| GraphNode {
+            bus_factor: Some(bus_factor),
+            top_share: Some(top_share),
+            ..node(path, Some(10))
+        };
+        let nodes = [
+            concentrated("tokio-rs/tokio", 12, 0.2),
+            concentrated("tokio-rs/bytes", 2, 0.4),
+            concentrated("dtolnay/itoa", 1, 0.9),
+            concentrated("rust-lang/cfg-if", 1, 0.6),
+            node("smol-rs/polling", None),
+        ];
+        assert_eq!(
+            DependencyReport::low_bus_factor(&nodes),
+            ["dtolnay/itoa", "rust-lang/cfg-if", "tokio-rs/bytes"]
+        );
+    }
+
+    #[test]
+    fn test_package_message() {
+        let parent = RepositoryId::from_path("tokio-rs/loom").unwrap();
+        let dependency = Dependency {
+            ecosystem: Some("Cargo".parse().unwrap()),
+            package_name: "private-macros".to_string(),
+            requirements: Some("0.2.1".to_string()),
+            manifest: Some("Cargo.lock".to_string()),
+            repository: None,
+            url: None,
+        };
+        assert_eq!(
+            serde_json::to_value(ServerMessage::package(&parent, &dependency)).unwrap(),
+            serde_json::json!({
+                "type": "package",
+                "parent": "tokio-rs/loom",
+                "ecosystem": "cargo",
+                "name": "private-macros",
+                "requirements": "0.2.1",
+                "manifest": "Cargo.lock",
+                "repository": null,
+                "url": null,
+                "unresolved": "no repository link",
+            })
+        );
+    }
+
+    #[test]
+    fn test_unresolved_dependencies_counted_once() {
+        let package = |name: &str, url: Option<&str>| Dependency {
+            ecosystem: Some("cargo".parse().unwrap()),
+            package_name: name.to_string(),
+            requirements: None,
+            manifest: Some("Cargo.toml".to_string()),
+            repository: None,
+            url: url.map(str::to_string),
+        };
+        let bitbucket = package("mercurial", Some("https://bitbucket.org/owner/mercurial"));
+        let unresolved: Vec<UnresolvedDependency> = [
+            ("tokio-rs/loom", &bitbucket),
+            ("tokio-rs/tokio", &bitbucket),
+            ("tokio-rs/tokio", &package("private-macros", None)),
+        ]
+        .into_iter()
+        .filter_map(|(parent, dependency)| {
+            UnresolvedDependency::new(parent.to_string(), dependency)
+        })
+        .collect();
+        assert_eq!(unresolved.len(), 3);
+        assert_eq!(UnresolvedDependency::distinct(&unresolved), 2);
+        assert_eq!(
+            unresolved[0].reason,
+            "https://bitbucket.org/owner/mercurial is not a repository of a supported forge"
+        );
+        let rug = package("rug", Some("https://gitlab.com/tspiteri/rug"));
+        assert_eq!(UnresolvedDependency::new("tokio-rs/loom".to_string(), &rug), None);
+
+        let resolved = Dependency::from(
+            GitHubLink::try_from("https://github.com/tokio-rs/tokio".to_string()).unwrap(),
+        );
+        assert_eq!(UnresolvedDependency::new("tokio-rs/loom".to_string(), &resolved), None);
+    }
+}
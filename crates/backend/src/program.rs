@@ -4,8 +4,10 @@ use crate::api::routes::{public_routes, try_metrics_routes};
 use crate::api::state::AppState;
 use crate::cache::RedisCache;
 use crate::config::Config;
+use crate::crawler::spawn_workers;
 use crate::database::database::{PostgresDatabase, Database};
 use anyhow::Error;
+use github_scrapper::{Forge, RateLimiter};
 use axum::Router;
 use axum_prometheus::PrometheusMetricLayerBuilder;
 use tokio::net::{TcpListener, ToSocketAddrs};
@@ -24,9 +26,18 @@ pub(crate) async fn run(config: &Config) -> Result<(), anyhow::Error> {
     let mut database = PostgresDatabase::from(config).await?;
     database.init(config).await?;
 
+    info!("Initializing GitHub rate limiter...");
+    RateLimiter::global().reconfigure(config.github.rate_limit.clone());
+
+    info!("Allowing the self-hosted forges...");
+    Forge::allow(config.forges.clone());
+
     info!("Initializing application state...");
     let app_state = AppState::try_new(config, cache, database)?;
 
+    info!("Initializing crawl workers...");
+    spawn_workers(&app_state);
+
     let mut servers = vec![];
 
     // PUBLIC ROUTES
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 300.0;
+const MARGIN: f64 = 40.0;
+
+/// Position of each observation in the chart, time on the x axis and
+/// contributors on the y axis.
+fn points(history: &[(DateTime<Utc>, usize)]) -> Vec<(f64, f64)> {
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return vec![];
+    };
+    let start = first.0.timestamp() as f64;
+    let duration = (last.0.timestamp() as f64 - start).max(1.0);
+    let max = history.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1) as f64;
+    history
+        .iter()
+        .map(|(date, contributors)| {
+            let x = if history.len() == 1 {
+                WIDTH / 2.0
+            } else {
+                MARGIN + (date.timestamp() as f64 - start) / duration * (WIDTH - 2.0 * MARGIN)
+            };
+            let y = HEIGHT - MARGIN - *contributors as f64 / max * (HEIGHT - 2.0 * MARGIN);
+            (x, y)
+        })
+        .collect()
+}
+
+/// Line chart of the contributors of a repository over time
+#[component]
+pub fn ContributorsChart(history: Signal<Vec<(DateTime<Utc>, usize)>>) -> Element {
+    let history = history.read();
+    let points = points(&history);
+    let polyline = points
+        .iter()
+        .map(|(x, y)| format!("{x:.1},{y:.1}"))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let max = history.iter().map(|(_, c)| *c).max().unwrap_or(0);
+    let first = history
+        .first()
+        .map(|(date, _)| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let last = history
+        .last()
+        .map(|(date, _)| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+
+    rsx! {
+        svg {
+            class: "mx-auto w-full max-w-screen-md text-pri-300 dark:text-pri-800",
+            view_box: "0 0 {WIDTH} {HEIGHT}",
+            line {
+                x1: "{MARGIN}",
+                y1: "{HEIGHT - MARGIN}",
+                x2: "{WIDTH - MARGIN}",
+                y2: "{HEIGHT - MARGIN}",
+                stroke: "gray",
+            }
+            line {
+                x1: "{MARGIN}",
+                y1: "{MARGIN}",
+                x2: "{MARGIN}",
+                y2: "{HEIGHT - MARGIN}",
+                stroke: "gray",
+            }
+            text { x: "{MARGIN - 4.0}", y: "{MARGIN}", text_anchor: "end", font_size: "12", fill: "gray", "{max}" }
+            text { x: "{MARGIN - 4.0}", y: "{HEIGHT - MARGIN}", text_anchor: "end", font_size: "12", fill: "gray", "0" }
+            text { x: "{MARGIN}", y: "{HEIGHT - MARGIN + 16.0}", font_size: "12", fill: "gray", "{first}" }
+            text { x: "{WIDTH - MARGIN}", y: "{HEIGHT - MARGIN + 16.0}", text_anchor: "end", font_size: "12", fill: "gray", "{last}" }
+            polyline {
+                points: "{polyline}",
+                fill: "none",
+                stroke: "currentColor",
+                stroke_width: "2",
+            }
+            for (idx , (x , y)) in points.iter().enumerate() {
+                circle {
+                    key: "{idx}",
+                    cx: "{x}",
+                    cy: "{y}",
+                    r: "3",
+                    fill: "currentColor",
+                    title { "{history[idx].0.format(\"%Y-%m-%d\")}: {history[idx].1}" }
+                }
+            }
+        }
+    }
+}
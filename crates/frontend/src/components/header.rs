@@ -27,6 +27,13 @@ pub fn Header() -> Element {
                                 "Leaderboard"
                             }
                         }
+                        li {
+                            Link {
+                                to: Routes::Compare {},
+                                class: "text-gray-700 transition-color hover:text-gray-700/75 dark:text-gray-300 dark:hover:text-gray-300/75",
+                                "Compare"
+                            }
+                        }
                         li {
                             Link {
                                 to: Routes::About {},
@@ -20,6 +20,7 @@ pub fn Leaderboard() -> Element {
                     while let Some(item) = leaderboard.pop() {
                         guard.push((item.0, item.1));
                     }
+                    #[allow(clippy::unnecessary_sort_by)]
                     guard.sort_by(|a, b| b.1.cmp(&a.1));
                 }
                 Err(e) => {
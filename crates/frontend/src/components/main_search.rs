@@ -1,10 +1,10 @@
 use std::borrow::BorrowMut;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ops::{Add, Deref, DerefMut};
 
 use crate::components::RepositoriesTable;
-use crate::models::ContributorsChunk;
-use crate::services::{ServiceWebsocket, get_dependencies};
+use crate::models::{ErrorCode, RepositoryDetails, ServerMessage};
+use crate::services::{ServiceWebsocket, export_url, get_dependencies};
 use crate::{assets::Logo, error::Error};
 
 use dioxus::prelude::*;
@@ -12,16 +12,43 @@ use gloo::timers::future::sleep;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 lazy_static! {
     static ref LINK_PATTERN: Regex =
         Regex::new(r#"^https?://github.com/([a-zA-Z0-9_\.-]{1,35})/([a-zA-Z0-9_\.-]{1,101})/?$"#)
             .unwrap();
+    /// GitLab, whose projects can be in nested groups, and Gitea or Forgejo
+    /// instances, the backend telling whether it serves their host
+    static ref FORGE_LINK_PATTERN: Regex = Regex::new(
+        r#"^https?://([a-z0-9-]+(?:\.[a-z0-9-]+)+)/((?:[a-zA-Z0-9_\.-]{1,101}/)+[a-zA-Z0-9_\.-]{1,101})/?$"#
+    )
+    .unwrap();
 }
 
+/// Formats of the dependency graph export, and their label
+const EXPORT_FORMATS: [(&str, &str); 5] = [
+    ("json", "JSON"),
+    ("dot", "Graphviz (DOT)"),
+    ("graphml", "GraphML"),
+    ("csv-nodes", "CSV (repositories)"),
+    ("csv-edges", "CSV (dependencies)"),
+];
+
 fn is_link_valid(link: &str) -> bool {
-    LINK_PATTERN.captures(link.trim()).is_some()
+    link_path(link).is_some()
+}
+
+/// Path of a valid link, `owner/repo` on GitHub and `host/owner/repo` on the
+/// other forges
+fn link_path(link: &str) -> Option<String> {
+    let link = link.trim();
+    if let Some(captures) = LINK_PATTERN.captures(link) {
+        return Some(format!("{}/{}", &captures[1], &captures[2]));
+    }
+    FORGE_LINK_PATTERN
+        .captures(link)
+        .map(|captures| format!("{}/{}", &captures[1], &captures[2]))
 }
 
 //TODO: Keyboard shortcuts
@@ -37,6 +64,20 @@ pub fn MainSearch(url: Option<String>) -> Element {
     let mut error_msg = use_signal(|| "");
     let mut total_contributors = use_signal(|| 0_usize);
     let mut repositories: Signal<Vec<(String, usize)>> = use_signal(Vec::new);
+    let mut parents: Signal<HashMap<String, String>> = use_signal(HashMap::new);
+    let mut truncated = use_signal(|| false);
+    let mut unique_contributors: Signal<Option<usize>> = use_signal(|| None);
+    let mut unique_bots: Signal<Option<usize>> = use_signal(|| None);
+    // People, bots and bus factor of the repositories whose contributions are known
+    let mut details: Signal<HashMap<String, RepositoryDetails>> = use_signal(HashMap::new);
+    // Package managers through which each repository is depended on
+    let mut ecosystems: Signal<HashMap<String, BTreeSet<String>>> = use_signal(HashMap::new);
+    // Packages that do not resolve to a GitHub repository, with the reason why
+    let mut unresolved: Signal<BTreeMap<String, String>> = use_signal(BTreeMap::new);
+    let mut unresolved_count: Signal<Option<usize>> = use_signal(|| None);
+    // Repository whose crawl is done, and whose graph can be exported
+    let mut exported: Signal<Option<String>> = use_signal(|| None);
+    let mut export_format = use_signal(|| EXPORT_FORMATS[0].0.to_string());
 
     let onclick = move |_| {
         debug!("Button pressed with: {}", url.read());
@@ -45,7 +86,7 @@ pub fn MainSearch(url: Option<String>) -> Element {
             return;
         };
         if !is_link_valid(url.read().as_str()) {
-            error_msg.set("Please provide a valid GitHub repository link.");
+            error_msg.set("Please provide a valid GitHub, GitLab or Codeberg repository link.");
             return;
         };
 
@@ -53,23 +94,116 @@ pub fn MainSearch(url: Option<String>) -> Element {
             error_msg.set("");
             total_contributors.set(0);
             repositories.write().deref_mut().clear();
+            parents.write().clear();
+            truncated.set(false);
+            unique_contributors.set(None);
+            unique_bots.set(None);
+            details.write().clear();
+            ecosystems.write().clear();
+            unresolved.write().clear();
+            unresolved_count.set(None);
+            exported.set(None);
             button_disabled.set(true);
             running.set(true);
 
             let u = url.read();
             let u = u.as_str();
-            let handle_chunk = move |chunk: ContributorsChunk| {
-                total_contributors += chunk.contributors;
-                repositories.write().push((chunk.path, chunk.contributors));
-                repositories.write().deref_mut().sort_by(|a, b| {
-                    if b.1 != a.1 {
-                        b.1.cmp(&a.1)
-                    } else {
-                        b.0.cmp(&a.0)
+            let path = link_path(u);
+            let handle_message = move |message: ServerMessage| match message {
+                ServerMessage::Hello { .. } => {
+                    // A new session, also after a reconnection that could not resume
+                    total_contributors.set(0);
+                    repositories.write().clear();
+                    parents.write().clear();
+                    details.write().clear();
+                    ecosystems.write().clear();
+                    unresolved.write().clear();
+                    truncated.set(false);
+                }
+                ServerMessage::Repository {
+                    path,
+                    contributors,
+                    humans,
+                    bots,
+                    bus_factor,
+                    top_share,
+                    ..
+                } => {
+                    let repository = RepositoryDetails {
+                        humans,
+                        bots,
+                        bus_factor,
+                        top_share,
+                    };
+                    if repository != RepositoryDetails::default() {
+                        details.write().insert(path.clone(), repository);
                     }
-                });
+                    total_contributors += contributors;
+                    repositories.write().push((path, contributors));
+                    repositories.write().deref_mut().sort_by(|a, b| {
+                        if b.1 != a.1 {
+                            b.1.cmp(&a.1)
+                        } else {
+                            b.0.cmp(&a.0)
+                        }
+                    });
+                }
+                ServerMessage::Edge { parent, child } => {
+                    // Keep the repository through which the dependency was first reached
+                    parents.write().entry(child).or_insert(parent);
+                }
+                ServerMessage::Package {
+                    ecosystem: Some(ecosystem),
+                    repository: Some(repository),
+                    ..
+                } => {
+                    ecosystems
+                        .write()
+                        .entry(repository)
+                        .or_default()
+                        .insert(ecosystem);
+                }
+                ServerMessage::Package {
+                    ecosystem,
+                    name,
+                    unresolved: Some(reason),
+                    ..
+                } => {
+                    let package = match ecosystem {
+                        Some(ecosystem) => format!("{name} ({ecosystem})"),
+                        None => name,
+                    };
+                    unresolved.write().insert(package, reason);
+                }
+                ServerMessage::Warning { path, message } => {
+                    warn!("Dependencies of {path} may be incomplete: {message}");
+                }
+                ServerMessage::Error { code, message } => {
+                    error!("Error Fetching dependencies: {message}");
+                    match code {
+                        ErrorCode::NotFound => error_msg.set("This repository does not exist."),
+                        ErrorCode::InvalidLink => {
+                            error_msg.set("Please provide a valid GitHub, GitLab or Codeberg repository link.")
+                        }
+                    }
+                }
+                ServerMessage::Done {
+                    truncated: t,
+                    unique_contributors: unique,
+                    unique_bots: bots,
+                    unresolved: count,
+                    ..
+                } => {
+                    truncated.set(t);
+                    unresolved_count.set(count);
+                    unique_contributors.set(unique);
+                    unique_bots.set(bots);
+                    // Only the graphs of GitHub repositories have an export route
+                    exported.set(path.clone().filter(|path| path.matches('/').count() == 1));
+                }
+                message => debug!("Received {message:?}"),
             };
-            match get_dependencies(u, handle_chunk) {
+            match get_dependencies(u, handle_message) {
                 Ok(mut ws) => {
                     let _ = ws;
                     debug!("Awaiting stop");
@@ -96,6 +230,19 @@ pub fn MainSearch(url: Option<String>) -> Element {
         });
     };
 
+    // Dependencies whose bus factor is low
+    let fragile = move || {
+        details
+            .read()
+            .values()
+            .filter(|details| details.low_bus_factor())
+            .count()
+    };
+
+    // Dependencies whose contributors are missing from the counts, as counted
+    // by the server once the crawl is done
+    let missing = move || unresolved_count.read().unwrap_or(unresolved.read().len());
+
     let onstop = move |_| {
         debug!("Cancel button pressed");
         should_stop.set(true);
@@ -144,6 +291,68 @@ pub fn MainSearch(url: Option<String>) -> Element {
                         strong { class: "text-7xl text-sec-300 dark:text-sec-800", "{repositories.read().len()}" }
                         " total dependencies !"
                     }
+                    if let Some(unique) = *unique_contributors.read() {
+                        p { class: "text-center text-2xl w-full mt-2",
+                            "That is "
+                            strong { class: "text-pri-300 dark:text-pri-800", "{unique}" }
+                            " distinct people, some contributing to several of them"
+                            if let Some(bots) = *unique_bots.read() {
+                                ", along with "
+                                strong { class: "text-sec-300 dark:text-sec-800", "{bots}" }
+                                " bots"
+                            }
+                            "."
+                        }
+                    }
+                    if fragile() > 0 {
+                        p { class: "text-center text-lg w-full mt-2",
+                            strong { class: "text-red-600", "{fragile()}" }
+                            " of them depend on one or two people for most of their commits."
+                        }
+                    }
+                    if missing() > 0 {
+                        p { class: "text-center text-lg w-full mt-2",
+                            "These counts are a lower bound: "
+                            strong { class: "text-sec-300 dark:text-sec-800", "{missing()}" }
+                            " dependencies do not resolve to a GitHub repository, and their contributors are missing."
+                        }
+                        details { class: "mx-auto mt-2 max-w-screen-md text-left text-sm",
+                            summary { class: "cursor-pointer text-center", "Unresolved dependencies" }
+                            ul { class: "list-disc pl-6",
+                                for (package , reason) in unresolved.read().clone() {
+                                    li { key: "{package}",
+                                        strong { "{package}" }
+                                        ": {reason}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if *truncated.read() {
+                        p { class: "text-center text-lg w-full mt-2",
+                            "The dependency graph is too large, only part of it was explored."
+                        }
+                    }
+                    if !*running.read() {
+                        if let Some(path) = exported.read().clone() {
+                            div { class: "flex justify-center items-center gap-2 mt-4",
+                                select {
+                                    class: "p-2 border border-slate-500 rounded-lg bg-white text-black",
+                                    value: "{export_format}",
+                                    onchange: move |event| export_format.set(event.value()),
+                                    for (format , label) in EXPORT_FORMATS {
+                                        option { value: format, "{label}" }
+                                    }
+                                }
+                                a {
+                                    class: "border border-slate-500 bg-slate-300 py-2 px-4 rounded-lg text-black hover:bg-slate-400",
+                                    href: export_url(&path, export_format.read().as_str()),
+                                    download: true,
+                                    "Download the graph"
+                                }
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -152,7 +361,7 @@ pub fn MainSearch(url: Option<String>) -> Element {
                 h2 { class: "text-center w-full mb-4 text-3xl font-extrabold leading-none tracking-tight text-gray-900 md:text-4xl lg:text-5xl dark:text-white",
                     "Dependencies Contributors"
                 }
-                RepositoriesTable { repositories }
+                RepositoriesTable { repositories, parents, details, ecosystems }
             }
         }
     }
@@ -8,6 +8,7 @@ mod theme_switcher;
 mod main_search;
 mod leaderboard;
 mod repositories_table;
+mod contributors_chart;
 
 pub use alert::{AlertBannerGreen, AlertBannerRed};
 pub use buttons::{ActionButton, GoBackButton, LinkButton};
@@ -18,3 +19,4 @@ pub use theme_switcher::ThemeSwitcher;
 pub use main_search::MainSearch;
 pub use leaderboard::Leaderboard;
 pub use repositories_table::RepositoriesTable;
+pub use contributors_chart::ContributorsChart;
@@ -1,29 +1,246 @@
+use crate::models::RepositoryDetails;
+use crate::routes::Routes;
+
 use dioxus::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+
+/// The chain of dependencies from the searched repository to this one,
+/// following the repository that first pulled in each of them.
+fn dependency_chain(parents: &HashMap<String, String>, repository: &str) -> String {
+    let mut chain = vec![repository];
+    while let Some(parent) = parents.get(*chain.last().unwrap()) {
+        // Cycles can not happen since only the first parent is kept, but stay safe
+        if chain.contains(&parent.as_str()) {
+            break;
+        }
+        chain.push(parent);
+    }
+    chain.reverse();
+    chain.join(" → ")
+}
+
+/// The `owner` and `repo` of a GitHub path, the paths of the other forges
+/// starting with their host.
+fn github_path(repository: &str) -> Option<(&str, &str)> {
+    repository.split_once('/').filter(|(_, repo)| !repo.contains('/'))
+}
+
+/// The web page of the repository of the path.
+fn repository_url(repository: &str) -> String {
+    match github_path(repository) {
+        Some(_) => format!("https://github.com/{repository}"),
+        None => format!("https://{repository}"),
+    }
+}
+
+/// Column the table is sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SortColumn {
+    Contributors,
+    Humans,
+    Bots,
+    BusFactor,
+    TopShare,
+}
+
+impl SortColumn {
+    /// Value of a repository in this column, `None` when unknown
+    fn value(self, contributors: usize, details: &RepositoryDetails) -> Option<f64> {
+        match self {
+            SortColumn::Contributors => Some(contributors as f64),
+            SortColumn::Humans => details.humans.map(|humans| humans as f64),
+            SortColumn::Bots => details.bots.map(|bots| bots as f64),
+            SortColumn::BusFactor => details.bus_factor.map(|bus_factor| bus_factor as f64),
+            SortColumn::TopShare => details.top_share,
+        }
+    }
+
+    /// Direction of the first sort by this column: the most fragile
+    /// repositories have the lowest bus factor, but the highest top share
+    fn descending_by_default(self) -> bool {
+        self != SortColumn::BusFactor
+    }
+}
+
+/// The repositories sorted by the column, the unknown values last whatever the
+/// direction, and the ties kept in their order.
+fn sorted_rows(
+    repositories: &[(String, usize)],
+    details: &HashMap<String, RepositoryDetails>,
+    column: SortColumn,
+    descending: bool,
+) -> Vec<(String, usize)> {
+    let mut rows = repositories.to_vec();
+    rows.sort_by(|(a, a_contributors), (b, b_contributors)| {
+        let a = column.value(
+            *a_contributors,
+            &details.get(a).copied().unwrap_or_default(),
+        );
+        let b = column.value(
+            *b_contributors,
+            &details.get(b).copied().unwrap_or_default(),
+        );
+        match (a, b) {
+            (Some(a), Some(b)) if descending => b.total_cmp(&a),
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    });
+    rows
+}
+
+fn known(value: Option<usize>) -> String {
+    value.map_or("?".to_string(), |value| value.to_string())
+}
 
 #[component]
-pub fn RepositoriesTable(repositories: Signal<Vec<(String, usize)>>) -> Element {
+fn SortHeader(
+    label: &'static str,
+    column: SortColumn,
+    sort: Signal<(SortColumn, bool)>,
+) -> Element {
+    let (current, descending) = *sort.read();
+    let arrow = match (current == column, descending) {
+        (false, _) => "",
+        (true, true) => " ▼",
+        (true, false) => " ▲",
+    };
     rsx! {
+        th {
+            scope: "col",
+            class: "text-center px-6 py-4 cursor-pointer select-none hover:text-sky-500",
+            title: "Sort by {label}",
+            onclick: move |_| {
+                if current == column {
+                    sort.set((column, !descending));
+                } else {
+                    sort.set((column, column.descending_by_default()));
+                }
+            },
+            "{label}{arrow}"
+        }
+    }
+}
+
+/// Repositories sorted by contributors, or by the column whose header is
+/// clicked. When `parents` is given, hovering a repository shows why it is
+/// part of the dependency graph. When `details` knows the people and bots, or
+/// the bus factor, of any repository, they are shown in their own columns and
+/// the repositories with a low bus factor are highlighted. When `ecosystems`
+/// knows the package managers through which the repositories are depended on,
+/// the table can be filtered to one of them.
+#[component]
+pub fn RepositoriesTable(
+    repositories: Signal<Vec<(String, usize)>>,
+    parents: Option<Signal<HashMap<String, String>>>,
+    details: Option<Signal<HashMap<String, RepositoryDetails>>>,
+    ecosystems: Option<Signal<HashMap<String, BTreeSet<String>>>>,
+) -> Element {
+    let sort = use_signal(|| (SortColumn::Contributors, true));
+    let mut ecosystem: Signal<Option<String>> = use_signal(|| None);
+    let chain = move |repository: &str| {
+        parents
+            .map(|parents| dependency_chain(&parents.read(), repository))
+            .unwrap_or_default()
+    };
+    let details = details
+        .map(|details| details.read().clone())
+        .unwrap_or_default();
+    let show_identified = details.values().any(|details| details.humans.is_some());
+    let show_concentration = details.values().any(|details| details.bus_factor.is_some());
+    let (column, descending) = *sort.read();
+    let ecosystems = ecosystems
+        .map(|ecosystems| ecosystems.read().clone())
+        .unwrap_or_default();
+    let known_ecosystems: BTreeSet<String> = ecosystems.values().flatten().cloned().collect();
+    let mut rows = sorted_rows(&repositories.read(), &details, column, descending);
+    if let Some(ecosystem) = ecosystem.read().as_ref() {
+        rows.retain(|(repository, _)| {
+            ecosystems
+                .get(repository)
+                .is_some_and(|ecosystems| ecosystems.contains(ecosystem))
+        });
+    }
+    let detail = move |repository: &str| details.get(repository).copied().unwrap_or_default();
+
+    rsx! {
+        if !known_ecosystems.is_empty() {
+            div { class: "flex justify-end mx-auto mb-2 max-w-[340px] sm:max-w-screen-sm md:max-w-screen-md lg:max-w-screen-lg",
+                select {
+                    class: "text-sm rounded-lg border border-neutral-300 bg-transparent px-2 py-1 dark:border-white/10",
+                    title: "Only the dependencies of one package manager",
+                    onchange: move |event| {
+                        let value = event.value();
+                        ecosystem.set((!value.is_empty()).then_some(value));
+                    },
+                    option { value: "", "All ecosystems" }
+                    for known in known_ecosystems {
+                        option { key: "{known}", value: "{known}", "{known}" }
+                    }
+                }
+            }
+        }
         table { class: "table-auto mx-auto text-[0.75em] sm:text-sm md:text-md font-light text-surface dark:text-white max-w-[340px] sm:max-w-screen-sm md:max-w-screen-md lg:max-w-screen-lg",
             thead { class: "border-b border-neutral-200 font-medium dark:border-white/10",
                 tr {
                     th { scope: "col", class: "text-center px-6 py-4", "Rank" }
                     th { scope: "col", class: "text-left px-6 py-4 break-all", "Repository" }
-                    th { scope: "col", class: "text-center px-6 py-4", "Contributors" }
+                    SortHeader { label: "Contributors", column: SortColumn::Contributors, sort }
+                    if show_identified {
+                        SortHeader { label: "Humans", column: SortColumn::Humans, sort }
+                        SortHeader { label: "Bots", column: SortColumn::Bots, sort }
+                    }
+                    if show_concentration {
+                        SortHeader { label: "Bus factor", column: SortColumn::BusFactor, sort }
+                        SortHeader { label: "Top share", column: SortColumn::TopShare, sort }
+                    }
                 }
             }
             tbody { class: "text-center text-0.5em sm:text-sm md:text-md",
-                for (idx , (repository , contributors)) in repositories.read().iter().enumerate() {
-                    tr { key: "{repository}", class: "border-b border-neutral-200 transition duration-300 ease-in-out hover:bg-neutral-200 dark:border-white/10 dark:hover:bg-neutral-600",
+                for (idx , (repository , contributors)) in rows.iter().enumerate() {
+                    tr {
+                        key: "{repository}",
+                        class: if detail(repository).low_bus_factor() { "border-b border-neutral-200 transition duration-300 ease-in-out bg-red-100 hover:bg-red-200 dark:border-white/10 dark:bg-red-950 dark:hover:bg-red-900" } else { "border-b border-neutral-200 transition duration-300 ease-in-out hover:bg-neutral-200 dark:border-white/10 dark:hover:bg-neutral-600" },
                         td { class: "text-center px-6 py-2", "#{idx+1}" }
                         td { class: "text-left px-6 py-2 break-all",
+                            title: "{chain(repository)}",
                             a {
-                                href: "https://github.com/{repository}",
+                                href: repository_url(repository),
                                 target: "_blank",
                                 class: "hover:text-sky-500",
                                 "{repository}"
                             }
                         }
-                        td { class: "text-center px-6 py-2", "{contributors}" }
+                        td { class: "text-center px-6 py-2",
+                            if let Some((owner, repo)) = github_path(repository) {
+                                Link {
+                                    to: Routes::History { owner: owner.to_string(), repo: repo.to_string() },
+                                    class: "hover:text-sky-500",
+                                    title: "Contributors over time",
+                                    "{contributors}"
+                                }
+                            } else {
+                                "{contributors}"
+                            }
+                        }
+                        if show_identified {
+                            td { class: "text-center px-6 py-2", "{known(detail(repository).humans)}" }
+                            td { class: "text-center px-6 py-2", "{known(detail(repository).bots)}" }
+                        }
+                        if show_concentration {
+                            td {
+                                class: "text-center px-6 py-2",
+                                title: "Fewest people who authored more than half of the commits",
+                                "{known(detail(repository).bus_factor)}"
+                            }
+                            td { class: "text-center px-6 py-2",
+                                if let Some(top_share) = detail(repository).top_share {
+                                    "{top_share * 100.0:.0}%"
+                                } else {
+                                    "?"
+                                }
+                            }
+                        }
                     }
                 }
             }
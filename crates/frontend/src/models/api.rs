@@ -1,5 +1,144 @@
-#[derive(Clone, Debug)]
-pub struct ContributorsChunk{
-    pub path: String,
+use serde::Deserialize;
+
+/// Reason for a dependencies session to fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidLink,
+    NotFound,
+}
+
+/// Message received from the dependencies WebSocket.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Hello {
+        protocol_version: u32,
+        repository: String,
+        session_id: String,
+    },
+    Repository {
+        path: String,
+        contributors: usize,
+        depth: usize,
+        /// People and bots among the contributors, when known
+        #[serde(default)]
+        humans: Option<usize>,
+        #[serde(default)]
+        bots: Option<usize>,
+        /// Fewest people who authored more than half of the commits, and the
+        /// share of the commits of the top one, when known
+        #[serde(default)]
+        bus_factor: Option<usize>,
+        #[serde(default)]
+        top_share: Option<f64>,
+    },
+    Edge {
+        parent: String,
+        child: String,
+    },
+    /// A package `parent` depends on, `repository` being the repository it
+    /// resolved to, if any
+    Package {
+        parent: String,
+        #[serde(default)]
+        ecosystem: Option<String>,
+        name: String,
+        #[serde(default)]
+        requirements: Option<String>,
+        #[serde(default)]
+        manifest: Option<String>,
+        #[serde(default)]
+        repository: Option<String>,
+        /// Repository URL of the package as listed, whatever its host
+        #[serde(default)]
+        url: Option<String>,
+        /// Why the package does not resolve to a GitHub repository, if it does not
+        #[serde(default)]
+        unresolved: Option<String>,
+    },
+    Progress {
+        discovered: usize,
+        processed: usize,
+    },
+    Warning {
+        path: String,
+        message: String,
+    },
+    Done {
+        repositories: usize,
+        contributors: usize,
+        /// Distinct people among the contributors, when known
+        #[serde(default)]
+        unique_contributors: Option<usize>,
+        /// Distinct bots among them, when known
+        #[serde(default)]
+        unique_bots: Option<usize>,
+        /// Distinct packages that do not resolve to a GitHub repository, when known
+        #[serde(default)]
+        unresolved: Option<usize>,
+        truncated: bool,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+/// A message along with its sequence number in the session, given back to
+/// resume the session after a reconnection.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct SequencedMessage {
+    pub seq: usize,
+    #[serde(flatten)]
+    pub message: ServerMessage,
+}
+
+/// Highest bus factor of a fragile repository, as in the backend report
+const LOW_BUS_FACTOR: usize = 2;
+
+/// What is known of the contributors of a repository besides their number.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RepositoryDetails {
+    pub humans: Option<usize>,
+    pub bots: Option<usize>,
+    pub bus_factor: Option<usize>,
+    pub top_share: Option<f64>,
+}
+
+impl RepositoryDetails {
+    /// Whether the commits of the repository depend on one or two people
+    pub fn low_bus_factor(&self) -> bool {
+        self.bus_factor
+            .is_some_and(|bus_factor| bus_factor <= LOW_BUS_FACTOR)
+    }
+}
+
+/// One of the compared repositories.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ComparedRepository {
+    pub repository: String,
+    /// Whether its dependencies are known, its sets are empty otherwise
+    pub crawled: bool,
+    /// The crawl queued because the repository was never crawled, if any
+    pub job_id: Option<String>,
+}
+
+/// Dependencies of one or both of the compared repositories.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct DependencySet {
+    /// Paths and contributors, most contributors first
+    pub repositories: Vec<(String, Option<usize>)>,
     pub contributors: usize,
 }
+
+/// The transitive dependencies of two repositories, split between the shared
+/// ones and the ones unique to each side.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct DependencyComparison {
+    pub left: ComparedRepository,
+    pub right: ComparedRepository,
+    pub shared: DependencySet,
+    pub left_only: DependencySet,
+    pub right_only: DependencySet,
+}
@@ -0,0 +1,173 @@
+use crate::components::{Footer, Header, RepositoriesTable};
+use crate::error::Error;
+use crate::models::{DependencyComparison, DependencySet};
+use crate::services::get_comparison;
+
+use dioxus::prelude::*;
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::{debug, error};
+
+lazy_static! {
+    static ref PATH_PATTERN: Regex = Regex::new(
+        r#"^(?:https?://github.com/)?([a-zA-Z0-9_\.-]{1,35}/[a-zA-Z0-9_\.-]{1,101})/?$"#
+    )
+    .unwrap();
+}
+
+/// `owner/repo` path of a GitHub link or path
+fn repository_path(input: &str) -> Option<String> {
+    PATH_PATTERN
+        .captures(input.trim())
+        .map(|captures| captures[1].to_string())
+}
+
+/// Repositories of the set, the unknown contributors counting as none
+fn table_rows(set: &DependencySet) -> Vec<(String, usize)> {
+    set.repositories
+        .iter()
+        .map(|(path, contributors)| (path.clone(), contributors.unwrap_or_default()))
+        .collect()
+}
+
+#[component]
+pub fn Compare() -> Element {
+    let mut left = use_signal(String::new);
+    let mut right = use_signal(String::new);
+    let mut button_disabled = use_signal(|| false);
+    let mut error_msg = use_signal(|| "");
+    let mut comparison: Signal<Option<DependencyComparison>> = use_signal(|| None);
+    let mut left_only: Signal<Vec<(String, usize)>> = use_signal(Vec::new);
+    let mut shared: Signal<Vec<(String, usize)>> = use_signal(Vec::new);
+    let mut right_only: Signal<Vec<(String, usize)>> = use_signal(Vec::new);
+
+    let onclick = move |_| {
+        let (Some(left_path), Some(right_path)) = (
+            repository_path(left.read().as_str()),
+            repository_path(right.read().as_str()),
+        ) else {
+            error_msg.set("Please provide two valid GitHub repository links.");
+            return;
+        };
+        debug!("Comparing {left_path} and {right_path}");
+
+        spawn(async move {
+            error_msg.set("");
+            button_disabled.set(true);
+            match get_comparison(&left_path, &right_path).await {
+                Ok(result) => {
+                    left_only.set(table_rows(&result.left_only));
+                    shared.set(table_rows(&result.shared));
+                    right_only.set(table_rows(&result.right_only));
+                    comparison.set(Some(result));
+                }
+                Err(e) => {
+                    error!("Error Fetching comparison: {:#?}", e);
+                    comparison.set(None);
+                    match e {
+                        Error::NotFound => {
+                            error_msg.set("One of these repositories does not exist.")
+                        }
+                        _ => error_msg.set("Whoops, something went wrong!"),
+                    }
+                }
+            };
+            button_disabled.set(false);
+        });
+    };
+
+    rsx! {
+        body { class: "flex flex-col min-h-screen justify-between",
+            body {
+                Header {}
+                section { class: "container py-8 px-4 mx-auto text-center lg:py-16 lg:px-12",
+                    h1 { class: "mb-4 text-4xl font-extrabold tracking-tight leading-none md:text-5xl lg:text-6xl",
+                        "Compare"
+                    }
+                    h2 { class: "mb-8 text-lg font-normal lg:text-xl sm:px-16 xl:px-48",
+                        "Which dependencies, and which contributors, do two repositories share?"
+                    }
+                    if !error_msg.read().is_empty() {
+                        p { class: "mb-4 mx-auto border-l-red-500 border-l-4 rounded-r-full bg-opacity-60 bg-slate-200 text-red-700 text-center text-lg py-2 w-full dark:bg-slate-900",
+                            "{error_msg}"
+                        }
+                    }
+                    div { class: "flex flex-col md:flex-row gap-2",
+                        input {
+                            "type": "search",
+                            class: "p-2 border border-slate-500 rounded-lg w-full bg-white text-black",
+                            placeholder: "https://github.com/owner/repository",
+                            value: "{left}",
+                            oninput: move |event| left.set(event.value()),
+                            maxlength: 300
+                        }
+                        input {
+                            "type": "search",
+                            class: "p-2 border border-slate-500 rounded-lg w-full bg-white text-black",
+                            placeholder: "https://github.com/other/repository",
+                            value: "{right}",
+                            oninput: move |event| right.set(event.value()),
+                            maxlength: 300
+                        }
+                    }
+                    button {
+                        class: "cursor-pointer border border-slate-500 bg-slate-300 py-2 px-4 rounded-lg text-black mt-4 hover:bg-slate-400 disabled:bg-gray-300 disabled:text-gray-600 disabled:cursor-not-allowed",
+                        "type": "submit",
+                        onclick,
+                        disabled: button_disabled,
+                        "Compare"
+                    }
+                }
+                if let Some(result) = comparison.read().as_ref() {
+                    section { class: "container px-4 mx-auto text-center",
+                        for side in [&result.left, &result.right] {
+                            if !side.crawled {
+                                p { class: "mb-4 text-lg",
+                                    "{side.repository} was never searched, its dependencies are being crawled. Come back in a few minutes!"
+                                }
+                            }
+                        }
+                        div { class: "grid grid-cols-1 md:grid-cols-3 gap-4 mb-8",
+                            for (title , set) in [
+                                (format!("Only in {}", result.left.repository), &result.left_only),
+                                ("Shared".to_string(), &result.shared),
+                                (format!("Only in {}", result.right.repository), &result.right_only),
+                            ]
+                            {
+                                div { class: "break-all",
+                                    h3 { class: "text-xl font-bold", "{title}" }
+                                    p {
+                                        strong { class: "text-3xl text-pri-300 dark:text-pri-800",
+                                            "{set.repositories.len()}"
+                                        }
+                                        " dependencies, "
+                                        strong { class: "text-3xl text-sec-300 dark:text-sec-800",
+                                            "{set.contributors}"
+                                        }
+                                        " contributors"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    for (title , repositories) in [
+                        (format!("Only in {}", result.left.repository), left_only),
+                        ("Shared dependencies".to_string(), shared),
+                        (format!("Only in {}", result.right.repository), right_only),
+                    ]
+                    {
+                        if !repositories.read().is_empty() {
+                            section { class: "container mb-8",
+                                h2 { class: "text-center w-full mb-4 text-2xl font-extrabold leading-none tracking-tight text-gray-900 md:text-3xl dark:text-white break-all",
+                                    "{title}"
+                                }
+                                RepositoriesTable { repositories }
+                            }
+                        }
+                    }
+                }
+            }
+            Footer {}
+        }
+    }
+}
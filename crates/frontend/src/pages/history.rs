@@ -0,0 +1,55 @@
+use crate::components::{ContributorsChart, Footer, Header};
+use crate::services::get_history;
+use chrono::{DateTime, Utc};
+use tracing::error;
+
+use dioxus::prelude::*;
+
+#[component]
+pub fn History(owner: String, repo: String) -> Element {
+    let path = format!("{owner}/{repo}");
+    let mut error_msg = use_signal(|| "");
+    let mut history: Signal<Vec<(DateTime<Utc>, usize)>> = use_signal(Vec::new);
+
+    let fetch_path = path.clone();
+    use_effect(move || {
+        let path = fetch_path.clone();
+        spawn(async move {
+            match get_history(&path).await {
+                Ok(observations) => history.set(observations),
+                Err(e) => {
+                    error!("Error Fetching history: {:#?}", e);
+                    error_msg.set("Whoops, something went wrong!");
+                }
+            };
+        });
+    });
+
+    rsx! {
+        body { class: "flex flex-col min-h-screen justify-between",
+            body {
+                Header {}
+                section { class: "container py-8 px-4 mx-auto text-center lg:py-16 lg:px-12",
+                    h1 { class: "mb-4 text-4xl font-extrabold tracking-tight leading-none md:text-5xl lg:text-6xl break-all",
+                        "{path}"
+                    }
+                    h2 { class: "mb-8 text-lg font-normal lg:text-xl sm:px-16 xl:px-48",
+                        "Contributors over time"
+                    }
+                    if !error_msg.read().is_empty() {
+                        p { class: "mb-4 mx-auto border-l-red-500 border-l-4 rounded-r-full bg-opacity-60 bg-slate-200 text-red-700 text-center text-lg py-2 w-full dark:bg-slate-900",
+                            "{error_msg}"
+                        }
+                    } else if history.read().is_empty() {
+                        p { class: "text-sm font-normal text-slate-400 dark:text-slate-600 lg:text-md",
+                            "No contributors were recorded for this repository yet, search it on the home page!"
+                        }
+                    } else {
+                        ContributorsChart { history }
+                    }
+                }
+            }
+            Footer {}
+        }
+    }
+}
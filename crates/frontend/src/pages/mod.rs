@@ -1,7 +1,11 @@
 mod home;
 mod about;
 mod leaderboard;
+mod history;
+mod compare;
 
 pub use home::Home;
 pub use about::About;
 pub use leaderboard::Leaderboard;
+pub use history::History;
+pub use compare::Compare;
@@ -1,4 +1,4 @@
-use crate::pages::{About, Home, Leaderboard};
+use crate::pages::{About, Compare, History, Home, Leaderboard};
 
 use dioxus::prelude::*;
 
@@ -9,6 +9,10 @@ pub enum Routes {
     Home {},
     #[route("/leaderboard")]
     Leaderboard {},
+    #[route("/compare")]
+    Compare {},
     #[route("/about")]
     About {},
+    #[route("/history/:owner/:repo")]
+    History { owner: String, repo: String },
 }
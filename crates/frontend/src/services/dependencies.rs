@@ -1,24 +1,60 @@
 use super::websockets::ServiceWebsocket;
 use crate::error::Error;
-use crate::models::ContributorsChunk;
+use crate::models::{SequencedMessage, ServerMessage};
 
-use tracing::debug;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::{debug, warn};
 use web_sys::MessageEvent;
 use web_sys::js_sys;
 use web_sys::wasm_bindgen::prelude::*;
 
-/// Get decks filtered by author
+/// Version of the dependencies WebSocket protocol understood by the frontend
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Get the dependencies of a repository, one message at a time
+///
+/// When the connection is lost, the session is resumed from the last received
+/// message. A `hello` message starts a new session, for example when the
+/// previous one could not be resumed anymore.
 pub fn get_dependencies<T>(link: &str, mut callback: T) -> Result<ServiceWebsocket, Error>
 where
-    T: FnMut(ContributorsChunk) + 'static,
+    T: FnMut(ServerMessage) + 'static,
 {
-    let mut ws = ServiceWebsocket::new(format!("/dependencies?link={link}").as_str())?;
+    // Session ID and sequence number of the last received message
+    let session: Rc<RefCell<Option<(String, usize)>>> = Rc::new(RefCell::new(None));
+
+    let mut ws = ServiceWebsocket::new(
+        format!("/dependencies?link={link}&protocol={PROTOCOL_VERSION}").as_str(),
+    )?;
+    let resume = session.clone();
+    ws.set_resume(move || match resume.borrow().as_ref() {
+        Some((id, seq)) => format!("&session_id={id}&last_seq={seq}"),
+        None => String::new(),
+    });
     ws.set_onmessage(move |e: MessageEvent| {
         if let Ok(message) = e.data().dyn_into::<js_sys::JsString>() {
-            debug!("Received dependency chunk: {}", message);
+            debug!("Received dependency message: {}", message);
             if let Some(msg) = message.as_string() {
-                if let Ok(chunk) = ContributorsChunk::try_from(msg.as_str()) {
-                    callback(chunk);
+                match SequencedMessage::try_from(msg.as_str()) {
+                    Ok(SequencedMessage { seq, message }) => {
+                        {
+                            let mut session = session.borrow_mut();
+                            match (&message, session.as_mut()) {
+                                (ServerMessage::Hello { session_id, .. }, _) => {
+                                    *session = Some((session_id.clone(), seq));
+                                }
+                                (_, Some((_, last_seq))) if seq <= *last_seq => {
+                                    debug!("Ignoring already received message {seq}");
+                                    return;
+                                }
+                                (_, Some((_, last_seq))) => *last_seq = seq,
+                                (_, None) => {}
+                            }
+                        }
+                        callback(message)
+                    }
+                    Err(e) => warn!("{e}"),
                 }
             }
         }
@@ -26,20 +62,10 @@ where
     Ok(ws)
 }
 
-impl TryFrom<&str> for ContributorsChunk {
+impl TryFrom<&str> for SequencedMessage {
     type Error = Error;
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let value = value.trim();
-        let Some(split) = value.split_once(':') else {
-            return Err(Error::InvalidChunkFormat(value.to_string()));
-        };
-
-        let contributors = split
-            .1
-            .parse::<usize>()
-            .map_err(|_| Error::InvalidChunkFormat(value.to_string()))?;
-        let path = split.0.to_string();
-        Ok(ContributorsChunk { path, contributors })
+    fn try_from(value: &str) -> Result<Self, Error> {
+        serde_json::from_str(value).map_err(|_| Error::InvalidChunkFormat(value.to_string()))
     }
 }
@@ -1,9 +1,11 @@
 mod dependencies;
 mod leaderboard;
+mod repositories;
 
 mod requests;
 mod websockets;
 pub use dependencies::get_dependencies;
 pub use leaderboard::get_leaderboard;
+pub use repositories::{export_url, get_comparison, get_history};
 pub use requests::panic_on_error;
 pub use websockets::ServiceWebsocket;
@@ -0,0 +1,21 @@
+use super::requests::{API_ROOT, request_get};
+use crate::error::Error;
+use crate::models::DependencyComparison;
+
+use chrono::{DateTime, Utc};
+
+/// Contributors counts observed for the repository over time, oldest first
+pub async fn get_history(path: &str) -> Result<Vec<(DateTime<Utc>, usize)>, Error> {
+    request_get::<Vec<(DateTime<Utc>, usize)>>(format!("/repositories/{path}/history")).await
+}
+
+/// Shared and unique dependencies of two `owner/repo` repositories
+pub async fn get_comparison(left: &str, right: &str) -> Result<DependencyComparison, Error> {
+    request_get::<DependencyComparison>(format!("/compare?left={left}&right={right}")).await
+}
+
+/// Link downloading the known dependency graph of the repository, in one of
+/// the formats supported by the backend
+pub fn export_url(path: &str, format: &str) -> String {
+    format!("{API_ROOT}/repositories/{path}/export?format={format}")
+}
@@ -1,13 +1,138 @@
 use crate::error::Error;
-use tracing::debug;
+use gloo::timers::callback::Timeout;
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use tracing::{debug, warn};
 use web_sys::wasm_bindgen::prelude::*;
 use web_sys::wasm_bindgen::JsCast;
 use web_sys::{CloseEvent, ErrorEvent, MessageEvent, WebSocket};
 
 use super::requests::API_ROOT;
 
+/// Number of reconnections attempted in a row before giving up.
+const MAX_RECONNECTIONS: u32 = 5;
+/// Delay before the first reconnection, doubled on each failed attempt.
+const RECONNECTION_DELAY_MS: u32 = 500;
+
+type Callback<E> = RefCell<Option<Box<dyn FnMut(E)>>>;
+
+/// A websocket and its callbacks, moved to a new websocket when the
+/// connection is lost.
+struct Connection {
+    path: String,
+    ws: RefCell<WebSocket>,
+    onmessage: Callback<MessageEvent>,
+    onerror: Callback<ErrorEvent>,
+    onclose: Callback<CloseEvent>,
+    /// Query parameters added to the path when reconnecting
+    resume: RefCell<Option<Box<dyn Fn() -> String>>>,
+    attempts: Cell<u32>,
+    /// Whether a reconnection is scheduled
+    reconnecting: Cell<bool>,
+    /// Whether the websocket was closed on purpose
+    closed: Cell<bool>,
+}
+
+impl Connection {
+    fn call<E>(callback: &Callback<E>, event: E) {
+        if let Some(callback) = callback.borrow_mut().as_mut() {
+            callback(event);
+        }
+    }
+
+    /// Forwards the events of the websocket to the callbacks.
+    fn attach(self: &Rc<Self>, ws: &WebSocket) {
+        let connection = Rc::downgrade(self);
+        let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+            if let Some(connection) = connection.upgrade() {
+                Self::call(&connection.onmessage, e);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        ws.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        onmessage.forget();
+
+        let connection = Rc::downgrade(self);
+        let onerror = Closure::wrap(Box::new(move |e: ErrorEvent| {
+            if let Some(connection) = connection.upgrade() {
+                Self::call(&connection.onerror, e);
+            }
+        }) as Box<dyn FnMut(ErrorEvent)>);
+        ws.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+        onerror.forget();
+
+        let connection = Rc::downgrade(self);
+        let onopen = Closure::wrap(Box::new(move || {
+            if let Some(connection) = connection.upgrade() {
+                connection.attempts.set(0);
+            }
+        }) as Box<dyn FnMut()>);
+        ws.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+        onopen.forget();
+
+        let connection = Rc::downgrade(self);
+        let onclose = Closure::wrap(Box::new(move |e: CloseEvent| {
+            let Some(connection) = connection.upgrade() else {
+                return;
+            };
+            // A clean close comes from the server ending the session
+            if e.was_clean()
+                || connection.closed.get()
+                || connection.attempts.get() >= MAX_RECONNECTIONS
+            {
+                Self::call(&connection.onclose, e);
+            } else {
+                connection.reconnect();
+            }
+        }) as Box<dyn FnMut(CloseEvent)>);
+        ws.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+        onclose.forget();
+    }
+
+    /// Opens a new websocket after a delay, with the resume parameters.
+    fn reconnect(self: &Rc<Self>) {
+        let attempt = self.attempts.get();
+        self.attempts.set(attempt + 1);
+        self.reconnecting.set(true);
+        let delay = RECONNECTION_DELAY_MS << attempt;
+        warn!("WS connection lost, reconnecting in {delay}ms");
+
+        let connection: Weak<Self> = Rc::downgrade(self);
+        Timeout::new(delay, move || {
+            let Some(connection) = connection.upgrade() else {
+                return;
+            };
+            connection.reconnecting.set(false);
+            if connection.closed.get() {
+                return;
+            }
+            let resume = connection
+                .resume
+                .borrow()
+                .as_ref()
+                .map(|resume| resume())
+                .unwrap_or_default();
+            match open(&format!("{}{}", connection.path, resume)) {
+                Ok(ws) => {
+                    connection.attach(&ws);
+                    connection.ws.replace(ws);
+                }
+                Err(e) => warn!("Could not reconnect: {e}"),
+            }
+        })
+        .forget();
+    }
+}
+
+fn open(path: &str) -> Result<WebSocket, Error> {
+    let url = format!("{}{}", API_ROOT.replacen("http", "ws", 1).as_str(), path);
+    debug!("WS {}", url);
+    WebSocket::new(url.as_str()).map_err(|_| Error::WebSocket)
+}
+
+/// A websocket reconnecting by itself when the connection is lost, until it is
+/// closed by either side.
 pub struct ServiceWebsocket {
-    ws: WebSocket,
+    connection: Rc<Connection>,
 }
 
 impl ServiceWebsocket {
@@ -54,12 +179,30 @@ impl ServiceWebsocket {
             API_ROOT.starts_with("http"),
             "API_ROOT must start with 'http'"
         );
-        let url = format!("{}{}", API_ROOT.replacen("http", "ws", 1).as_str(), path);
-        debug!("WS {}", url);
-        match WebSocket::new(url.as_str()) {
-            Ok(ws) => Ok(ServiceWebsocket { ws }),
-            Err(_) => Err(Error::WebSocket),
-        }
+        let ws = open(path)?;
+        let connection = Rc::new(Connection {
+            path: path.to_string(),
+            ws: RefCell::new(ws.clone()),
+            onmessage: RefCell::new(None),
+            onerror: RefCell::new(None),
+            onclose: RefCell::new(None),
+            resume: RefCell::new(None),
+            attempts: Cell::new(0),
+            reconnecting: Cell::new(false),
+            closed: Cell::new(false),
+        });
+        connection.attach(&ws);
+        Ok(ServiceWebsocket { connection })
+    }
+
+    /// Sets the query parameters added to the path when reconnecting, for
+    /// example to resume a session.
+    pub fn set_resume<T>(&mut self, resume: T) -> &mut Self
+    where
+        T: Fn() -> String + 'static,
+    {
+        self.connection.resume.replace(Some(Box::new(resume)));
+        self
     }
 
     /// Adds a callback to the websocket that will be called when a message is received.
@@ -79,10 +222,7 @@ impl ServiceWebsocket {
     where
         T: FnMut(MessageEvent) + 'static,
     {
-        let callback = Closure::wrap(Box::new(callback) as Box<dyn FnMut(MessageEvent)>);
-        self.ws
-            .set_onmessage(Some(callback.as_ref().unchecked_ref()));
-        callback.forget();
+        self.connection.onmessage.replace(Some(Box::new(callback)));
         self
     }
 
@@ -100,13 +240,12 @@ impl ServiceWebsocket {
     where
         T: FnMut(ErrorEvent) + 'static,
     {
-        let callback = Closure::wrap(Box::new(callback) as Box<dyn FnMut(ErrorEvent)>);
-        self.ws.set_onerror(Some(callback.as_ref().unchecked_ref()));
-        callback.forget();
+        self.connection.onerror.replace(Some(Box::new(callback)));
         self
     }
 
-    /// Adds a callback to the websocket that will be called when the connection is closed.
+    /// Adds a callback to the websocket that will be called when the connection is closed,
+    /// and will not be reconnected.
     /// ## Example
     ///
     /// ```rs
@@ -120,33 +259,40 @@ impl ServiceWebsocket {
     where
         T: FnMut(CloseEvent) + 'static,
     {
-        let callback = Closure::wrap(Box::new(callback) as Box<dyn FnMut(CloseEvent)>);
-        self.ws.set_onclose(Some(callback.as_ref().unchecked_ref()));
-        callback.forget();
+        self.connection.onclose.replace(Some(Box::new(callback)));
         self
     }
 
-    /// Closes the websocket. No error checking is done.
+    /// Whether the websocket is open, connecting, or about to reconnect.
     pub fn is_open(&self) -> bool {
-        self.ws.ready_state() != web_sys::WebSocket::OPEN
-            || self.ws.ready_state() != web_sys::WebSocket::CONNECTING
+        if self.connection.closed.get() {
+            return false;
+        }
+        self.connection.reconnecting.get()
+            || matches!(
+                self.connection.ws.borrow().ready_state(),
+                web_sys::WebSocket::OPEN | web_sys::WebSocket::CONNECTING
+            )
     }
 
     /// Closes the websocket. No error checking is done.
     pub fn close(&mut self) {
-        match self.ws.ready_state() {
+        self.connection.closed.set(true);
+        let ws = self.connection.ws.borrow();
+        match ws.ready_state() {
             web_sys::WebSocket::CLOSING | web_sys::WebSocket::CLOSED => (),
             _ => {
-                let _ = self.ws.close();
+                let _ = ws.close();
             }
         }
     }
 
-    pub fn inner(&self) -> &WebSocket {
-        &self.ws
+    /// The current websocket, replaced on each reconnection.
+    pub fn inner(&self) -> WebSocket {
+        self.connection.ws.borrow().clone()
     }
 
     pub fn into_inner(self) -> WebSocket {
-        self.ws
+        self.inner()
     }
 }
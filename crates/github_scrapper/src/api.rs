@@ -0,0 +1,613 @@
+//! Retrieves the repositories data through the official GitHub REST and GraphQL APIs.
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::{ACCEPT, HeaderMap, LINK};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use serde_json::json;
+use tracing::debug;
+
+use crate::utils::check_status;
+use crate::{
+    Contribution, Contributor, Dependency, FetchRequest, GitHubClient, GitHubError, GitHubFetcher,
+    GitHubLink, RepositoryId,
+};
+
+lazy_static! {
+    static ref LAST_PAGE_PATTERN: Regex =
+        Regex::new(r#"[?&]page=(\d+)[^>]*>;\s*rel="last""#).unwrap();
+}
+
+/// Largest page size of the REST API
+const PER_PAGE: usize = 100;
+
+// The dependency graph is only exposed through the GraphQL API behind this preview.
+const DEPENDENCY_GRAPH_PREVIEW: &str = "application/vnd.github.hawkgirl-preview+json";
+
+const MANIFESTS_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $cursor: String) {
+  repository(owner: $owner, name: $repo) {
+    dependencyGraphManifests(first: 10, after: $cursor) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        id
+        filename
+        blobPath
+        dependencies(first: 100) {
+          pageInfo { hasNextPage endCursor }
+          nodes { packageName packageManager requirements repository { url } }
+        }
+      }
+    }
+  }
+}"#;
+
+const MANIFEST_DEPENDENCIES_QUERY: &str = r#"
+query($id: ID!, $cursor: String) {
+  node(id: $id) {
+    ... on DependencyGraphManifest {
+      dependencies(first: 100, after: $cursor) {
+        pageInfo { hasNextPage endCursor }
+        nodes { packageName packageManager requirements repository { url } }
+      }
+    }
+  }
+}"#;
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQlError>>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Connection<T> {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct ManifestsData {
+    repository: Option<RepositoryNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryNode {
+    dependency_graph_manifests: Connection<ManifestNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestNode {
+    id: String,
+    filename: Option<String>,
+    /// `/OWNER/REPO/blob/REF/PATH`
+    blob_path: Option<String>,
+    dependencies: Connection<DependencyNode>,
+}
+
+impl ManifestNode {
+    /// Path of the manifest in the repository, or else its file name
+    fn path(&self) -> Option<String> {
+        self.blob_path
+            .as_deref()
+            .and_then(|blob_path| blob_path.splitn(6, '/').nth(5))
+            .map(str::to_string)
+            .or_else(|| self.filename.clone())
+    }
+}
+
+#[derive(Deserialize)]
+struct ManifestDependenciesData {
+    node: Option<ManifestDependencies>,
+}
+
+#[derive(Deserialize)]
+struct ManifestDependencies {
+    dependencies: Connection<DependencyNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DependencyNode {
+    package_name: String,
+    package_manager: Option<String>,
+    requirements: Option<String>,
+    repository: Option<RepositoryUrl>,
+}
+
+#[derive(Deserialize)]
+struct RepositoryUrl {
+    url: String,
+}
+
+/// An entry of the REST contributors list, anonymous ones having no login
+#[derive(Deserialize)]
+struct ContributorEntry {
+    login: Option<String>,
+    email: Option<String>,
+    name: Option<String>,
+    #[serde(default)]
+    contributions: u64,
+}
+
+impl From<ContributorEntry> for Contribution {
+    fn from(entry: ContributorEntry) -> Self {
+        let contributor = match entry.login {
+            Some(login) => Contributor::User { login },
+            None => Contributor::Anonymous {
+                email: entry.email,
+                name: entry.name,
+            },
+        };
+        Contribution {
+            contributor,
+            commits: entry.contributions,
+        }
+    }
+}
+
+/// The number of contributors, anonymous ones included, from the REST API.
+pub(crate) async fn fetch_contributors<F: GitHubFetcher>(
+    client: &GitHubClient<F>,
+    link: &GitHubLink,
+) -> Result<usize, GitHubError> {
+    // With a single contributor per page, the number of the last page is the
+    // number of contributors, which spares paginating through all of them.
+    let url = format!(
+        "{}/repos/{}/{}/contributors?anon=1&per_page=1",
+        client.api_url(),
+        link.owner(),
+        link.repo()
+    );
+    let response = client
+        .fetcher()
+        .fetch(&FetchRequest::get(&url).authenticated())
+        .await?;
+    let response = check_status(response, &url)?;
+
+    // Empty repositories have no contributors
+    if response.status == reqwest::StatusCode::NO_CONTENT {
+        return Ok(0);
+    }
+    if let Some(last_page) = last_page(&response.headers) {
+        return Ok(last_page);
+    }
+    let contributors: Vec<IgnoredAny> = serde_json::from_str(&response.body)?;
+    Ok(contributors.len())
+}
+
+/// Every contributor of the repository, anonymous ones included, with their
+/// number of commits, from the REST API.
+///
+/// GitHub only links the first 500 emails of a repository to their accounts,
+/// the authors of the other ones are listed as anonymous.
+pub(crate) async fn fetch_contributions<F: GitHubFetcher>(
+    client: &GitHubClient<F>,
+    link: &GitHubLink,
+) -> Result<Vec<Contribution>, GitHubError> {
+    let mut contributors = vec![];
+    for page in 1.. {
+        let url = format!(
+            "{}/repos/{}/{}/contributors?anon=1&per_page={PER_PAGE}&page={page}",
+            client.api_url(),
+            link.owner(),
+            link.repo()
+        );
+        let response = client
+            .fetcher()
+            .fetch(&FetchRequest::get(&url).authenticated())
+            .await?;
+        let response = check_status(response, &url)?;
+        // Empty repositories have no contributors
+        if response.status == reqwest::StatusCode::NO_CONTENT {
+            break;
+        }
+
+        let entries: Vec<ContributorEntry> = serde_json::from_str(&response.body)?;
+        let full_page = entries.len() == PER_PAGE;
+        contributors.extend(entries.into_iter().map(Contribution::from));
+        if !full_page || !has_next_page(&response.headers) {
+            break;
+        }
+    }
+    Ok(contributors)
+}
+
+/// The dependencies listed in the dependency graph of the repository, from the GraphQL API.
+///
+/// A package is listed once for each manifest depending on it, with no
+/// repository when it does not have one.
+pub(crate) async fn fetch_dependencies<F: GitHubFetcher>(
+    client: &GitHubClient<F>,
+    link: &GitHubLink,
+) -> Result<Vec<Result<Dependency, GitHubError>>, GitHubError> {
+    let mut seen = HashSet::new();
+    let mut dependencies = vec![];
+    let mut push_nodes = |manifest: &Option<String>, nodes: Vec<DependencyNode>| {
+        for node in nodes {
+            let url = node.repository.map(|repository| repository.url);
+            let repository = match &url {
+                Some(url) => RepositoryId::try_from(url.clone())
+                    .inspect_err(|_| debug!("{} links to {url}", node.package_name))
+                    .ok(),
+                None => {
+                    debug!("{} has no repository", node.package_name);
+                    None
+                }
+            };
+            let dependency = Dependency {
+                ecosystem: node
+                    .package_manager
+                    .and_then(|package_manager| package_manager.parse().ok()),
+                package_name: node.package_name,
+                requirements: node
+                    .requirements
+                    .filter(|requirements| !requirements.trim().is_empty()),
+                manifest: manifest.clone(),
+                repository,
+                url,
+            };
+            if seen.insert(dependency.clone()) {
+                dependencies.push(Ok(dependency));
+            }
+        }
+    };
+
+    let mut cursor: Option<String> = None;
+    loop {
+        let variables = json!({ "owner": link.owner(), "repo": link.repo(), "cursor": cursor });
+        let data: ManifestsData = graphql(client, MANIFESTS_QUERY, variables, link).await?;
+        let Some(repository) = data.repository else {
+            return Err(GitHubError::NotFound(link.link().to_string()));
+        };
+        let manifests = repository.dependency_graph_manifests;
+
+        for manifest in manifests.nodes {
+            let path = manifest.path();
+            let mut page_info = manifest.dependencies.page_info;
+            push_nodes(&path, manifest.dependencies.nodes);
+            while page_info.has_next_page {
+                let variables = json!({ "id": manifest.id, "cursor": page_info.end_cursor });
+                let data: ManifestDependenciesData =
+                    graphql(client, MANIFEST_DEPENDENCIES_QUERY, variables, link).await?;
+                let Some(node) = data.node else {
+                    break;
+                };
+                page_info = node.dependencies.page_info;
+                push_nodes(&path, node.dependencies.nodes);
+            }
+        }
+
+        if !manifests.page_info.has_next_page {
+            break;
+        }
+        cursor = manifests.page_info.end_cursor;
+    }
+
+    Ok(dependencies)
+}
+
+async fn graphql<T: for<'de> Deserialize<'de>, F: GitHubFetcher>(
+    client: &GitHubClient<F>,
+    query: &str,
+    variables: serde_json::Value,
+    link: &GitHubLink,
+) -> Result<T, GitHubError> {
+    let url = format!("{}/graphql", client.api_url());
+    let body = json!({ "query": query, "variables": variables }).to_string();
+    let request = FetchRequest::post(&url, body)
+        .header(ACCEPT, DEPENDENCY_GRAPH_PREVIEW)
+        .authenticated();
+    let response = client.fetcher().fetch(&request).await?;
+    let response = check_status(response, &url)?;
+    let response: GraphQlResponse<T> = serde_json::from_str(&response.body)?;
+
+    if let Some(error) = response.errors.as_ref().and_then(|errors| errors.first()) {
+        if error.kind.as_deref() == Some("NOT_FOUND") {
+            return Err(GitHubError::NotFound(link.link().to_string()));
+        }
+        return Err(GitHubError::UnexpectedApiResponse(error.message.clone()));
+    }
+    response
+        .data
+        .ok_or_else(|| GitHubError::UnexpectedApiResponse(format!("No data for {link}")))
+}
+
+/// Whether the `Link` pagination header points to a next page.
+fn has_next_page(headers: &HeaderMap) -> bool {
+    headers
+        .get(LINK)
+        .and_then(|link| link.to_str().ok())
+        .is_some_and(|link| link.contains(r#"rel="next""#))
+}
+
+/// The number of the last page from the `Link` pagination header.
+fn last_page(headers: &HeaderMap) -> Option<usize> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    LAST_PAGE_PATTERN
+        .captures(link)
+        .and_then(|captures| captures[1].parse::<usize>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ecosystem, FetchMode, HttpFetcher, Unresolved};
+    use wiremock::matchers::{body_string_contains, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn link() -> GitHubLink {
+        GitHubLink::try_from("https://github.com/OWNER/REPO".to_string()).unwrap()
+    }
+
+    fn client(server: &MockServer) -> GitHubClient<HttpFetcher> {
+        GitHubClient::with_fetcher(HttpFetcher::default(), FetchMode::Api)
+            .with_api_url(&server.uri())
+    }
+
+    fn dependency(name: &str, url: Option<&str>) -> serde_json::Value {
+        json!({
+            "packageName": name,
+            "packageManager": "RUST",
+            "requirements": "= 1.0.0",
+            "repository": url.map(|url| json!({ "url": url })),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_contributors_from_last_page() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .and(query_param("anon", "1"))
+            .and(query_param("per_page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{}])).insert_header(
+                "Link",
+                r#"<https://api.github.com/repositories/1/contributors?anon=1&per_page=1&page=2>; rel="next", <https://api.github.com/repositories/1/contributors?anon=1&per_page=1&page=421>; rel="last""#,
+            ))
+            .mount(&server)
+            .await;
+
+        let contributors = fetch_contributors(&client(&server), &link()).await;
+        assert_eq!(contributors.unwrap(), 421);
+    }
+
+    #[tokio::test]
+    async fn test_contributors_single_page() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{}])))
+            .mount(&server)
+            .await;
+
+        let contributors = fetch_contributors(&client(&server), &link()).await;
+        assert_eq!(contributors.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_contributors_empty_repository() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let contributors = fetch_contributors(&client(&server), &link()).await;
+        assert_eq!(contributors.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_contributors_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let contributors = fetch_contributors(&client(&server), &link()).await;
+        assert!(matches!(contributors, Err(GitHubError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_contributions_pagination() {
+        let server = MockServer::start().await;
+        let users: Vec<serde_json::Value> = (0..PER_PAGE)
+            .map(|i| json!({ "login": format!("user-{i}"), "type": "User", "contributions": 1 }))
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(users).insert_header(
+                "Link",
+                r#"<https://api.github.com/repositories/1/contributors?anon=1&per_page=100&page=2>; rel="next""#,
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "login": "dependabot[bot]", "type": "Bot", "contributions": 1 },
+                { "email": "alice@example.com", "name": "Alice", "type": "Anonymous", "contributions": 12 },
+            ])))
+            .mount(&server)
+            .await;
+
+        let contributions = fetch_contributions(&client(&server), &link())
+            .await
+            .unwrap();
+        assert_eq!(contributions.len(), PER_PAGE + 2);
+        assert_eq!(
+            contributions[0].contributor,
+            Contributor::User {
+                login: "user-0".to_string()
+            }
+        );
+        assert_eq!(
+            contributions[PER_PAGE + 1],
+            Contribution {
+                contributor: Contributor::Anonymous {
+                    email: Some("alice@example.com".to_string()),
+                    name: Some("Alice".to_string()),
+                },
+                commits: 12,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dependencies_pagination() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(body_string_contains("\"cursor\":null"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": {
+                "repository": { "dependencyGraphManifests": {
+                    "pageInfo": { "hasNextPage": true, "endCursor": "MANIFESTS_2" },
+                    "nodes": [{
+                        "id": "MANIFEST_1",
+                        "filename": "Cargo.toml",
+                        "blobPath": "/OWNER/REPO/blob/main/Cargo.toml",
+                        "dependencies": {
+                            "pageInfo": { "hasNextPage": true, "endCursor": "DEPENDENCIES_2" },
+                            "nodes": [
+                                dependency("tokio", Some("https://github.com/tokio-rs/tokio")),
+                                dependency("private", None),
+                            ]
+                        }
+                    }]
+                }}
+            }})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(body_string_contains("DEPENDENCIES_2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": {
+                "node": { "dependencies": {
+                    "pageInfo": { "hasNextPage": false, "endCursor": null },
+                    "nodes": [
+                        dependency("serde", Some("https://github.com/serde-rs/serde")),
+                        dependency("rug", Some("https://gitlab.com/tspiteri/rug")),
+                    ]
+                }}
+            }})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(body_string_contains("MANIFESTS_2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": {
+                "repository": { "dependencyGraphManifests": {
+                    "pageInfo": { "hasNextPage": false, "endCursor": null },
+                    "nodes": [{
+                        "id": "MANIFEST_2",
+                        "filename": "Cargo.toml",
+                        "blobPath": "/OWNER/REPO/blob/main/benches/Cargo.toml",
+                        "dependencies": {
+                            "pageInfo": { "hasNextPage": false, "endCursor": null },
+                            "nodes": [
+                                dependency("tokio", Some("https://github.com/tokio-rs/tokio")),
+                                dependency("regex", Some("https://github.com/rust-lang/regex")),
+                            ]
+                        }
+                    }]
+                }}
+            }})))
+            .mount(&server)
+            .await;
+
+        let dependencies: Vec<Dependency> = fetch_dependencies(&client(&server), &link())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let listed: Vec<(&str, Option<String>, Option<&str>)> = dependencies
+            .iter()
+            .map(|dependency| {
+                (
+                    dependency.package_name.as_str(),
+                    dependency.repository.as_ref().map(RepositoryId::path),
+                    dependency.manifest.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (
+                    "tokio",
+                    Some("tokio-rs/tokio".to_string()),
+                    Some("Cargo.toml")
+                ),
+                ("private", None, Some("Cargo.toml")),
+                (
+                    "serde",
+                    Some("serde-rs/serde".to_string()),
+                    Some("Cargo.toml")
+                ),
+                (
+                    "rug",
+                    Some("gitlab.com/tspiteri/rug".to_string()),
+                    Some("Cargo.toml")
+                ),
+                (
+                    "tokio",
+                    Some("tokio-rs/tokio".to_string()),
+                    Some("benches/Cargo.toml")
+                ),
+                (
+                    "regex",
+                    Some("rust-lang/regex".to_string()),
+                    Some("benches/Cargo.toml")
+                ),
+            ]
+        );
+        assert_eq!(dependencies[0].ecosystem, Some(Ecosystem::Cargo));
+        assert_eq!(dependencies[0].requirements.as_deref(), Some("= 1.0.0"));
+        assert_eq!(dependencies[1].unresolved(), Some(Unresolved::NoLink));
+        assert_eq!(
+            dependencies[3].repository_id().unwrap().path(),
+            "gitlab.com/tspiteri/rug"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dependencies_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "repository": null },
+                "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a Repository" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let dependencies = fetch_dependencies(&client(&server), &link()).await;
+        assert!(matches!(dependencies, Err(GitHubError::NotFound(_))));
+    }
+}
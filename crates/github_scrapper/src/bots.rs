@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use crate::Contributor;
+
+/// Suffix of the GitHub App accounts, such as `dependabot[bot]`
+const BOT_SUFFIXES: &[&str] = &["[bot]"];
+/// Bots running on regular accounts
+const BOT_LOGINS: &[&str] = &[
+    "dependabot",
+    "dependabot-preview",
+    "renovate-bot",
+    "greenkeeperio-bot",
+    "pyup-bot",
+    "snyk-bot",
+    "codecov-io",
+    "imgbot",
+    "allcontributors",
+    "bors",
+    "homu",
+    "k8s-ci-robot",
+];
+
+/// Tells the bots apart from the human contributors.
+///
+/// A contributor is a bot when its login, or the name or email of an anonymous
+/// contributor, ends with one of the suffixes or is one of the denied logins,
+/// case insensitive.
+///
+/// ```rust
+/// use github_scrapper::{BotClassifier, Contributor};
+///
+/// let bots = BotClassifier::default().with_logins(["my-release-bot"]);
+/// assert!(bots.is_bot(&Contributor::User { login: "renovate[bot]".to_string() }));
+/// assert!(bots.is_bot(&Contributor::User { login: "My-Release-Bot".to_string() }));
+/// assert!(!bots.is_bot(&Contributor::User { login: "Darksonn".to_string() }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotClassifier {
+    suffixes: Vec<String>,
+    logins: HashSet<String>,
+}
+
+impl Default for BotClassifier {
+    fn default() -> Self {
+        Self {
+            suffixes: vec![],
+            logins: HashSet::new(),
+        }
+        .with_suffixes(BOT_SUFFIXES.iter().copied())
+        .with_logins(BOT_LOGINS.iter().copied())
+    }
+}
+
+impl BotClassifier {
+    /// Also considers as bots the contributors ending with these suffixes.
+    pub fn with_suffixes<S: AsRef<str>>(mut self, suffixes: impl IntoIterator<Item = S>) -> Self {
+        self.suffixes.extend(
+            suffixes
+                .into_iter()
+                .map(|suffix| suffix.as_ref().trim().to_lowercase())
+                .filter(|suffix| !suffix.is_empty()),
+        );
+        self
+    }
+
+    /// Also considers as bots the contributors with these logins.
+    pub fn with_logins<S: AsRef<str>>(mut self, logins: impl IntoIterator<Item = S>) -> Self {
+        self.logins.extend(
+            logins
+                .into_iter()
+                .map(|login| login.as_ref().trim().to_lowercase())
+                .filter(|login| !login.is_empty()),
+        );
+        self
+    }
+
+    pub fn is_bot(&self, contributor: &Contributor) -> bool {
+        let names: Vec<&str> = match contributor {
+            Contributor::User { login } => vec![login],
+            Contributor::Anonymous { email, name } => {
+                // `49699333+dependabot[bot]@users.noreply.github.com`
+                let email = email
+                    .as_deref()
+                    .and_then(|email| email.split_once('@'))
+                    .map(|(local, _)| local.split_once('+').map_or(local, |(_, login)| login));
+                name.as_deref().into_iter().chain(email).collect()
+            }
+        };
+        names.into_iter().any(|name| self.matches(name))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        self.logins.contains(&name) || self.suffixes.iter().any(|suffix| name.ends_with(suffix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(login: &str) -> Contributor {
+        Contributor::User {
+            login: login.to_string(),
+        }
+    }
+
+    fn anonymous(email: Option<&str>, name: Option<&str>) -> Contributor {
+        Contributor::Anonymous {
+            email: email.map(str::to_string),
+            name: name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let bots = BotClassifier::default();
+        assert!(bots.is_bot(&user("dependabot[bot]")));
+        assert!(bots.is_bot(&user("GitHub-Actions[bot]")));
+        assert!(bots.is_bot(&user("bors")));
+        assert!(!bots.is_bot(&user("botanist")));
+        assert!(!bots.is_bot(&user("carllerche")));
+    }
+
+    #[test]
+    fn test_anonymous_contributors() {
+        let bots = BotClassifier::default();
+        assert!(bots.is_bot(&anonymous(
+            Some("49699333+dependabot[bot]@users.noreply.github.com"),
+            Some("dependabot"),
+        )));
+        assert!(bots.is_bot(&anonymous(
+            Some("41898282+github-actions[bot]@users.noreply.github.com"),
+            None,
+        )));
+        assert!(!bots.is_bot(&anonymous(Some("alice@example.com"), Some("Alice"))));
+        assert!(!bots.is_bot(&anonymous(None, None)));
+    }
+
+    #[test]
+    fn test_configured_rules() {
+        let bots = BotClassifier::default()
+            .with_suffixes(["-ci", " "])
+            .with_logins(["Release-Robot"]);
+        assert!(bots.is_bot(&user("tokio-ci")));
+        assert!(bots.is_bot(&user("release-robot")));
+        // Empty rules match nothing
+        assert!(!bots.is_bot(&user("alice")));
+    }
+}
@@ -0,0 +1,270 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use metrics::counter;
+use scraper::Html;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::utils::{check_status, fetch_page};
+use crate::{
+    AuthenticatedFetcher, Contribution, DefaultFetcher, FetchRequest, GitHubError, GitHubFetcher, GitHubLink,
+    GitHubLinkDependencies, GitHubToken, RateLimitedFetcher, TokenPool, api,
+};
+
+/// Root of the GitHub website, used to scrape the HTML pages.
+pub const GITHUB_WEB_URL: &str = "https://github.com";
+/// Root of the official GitHub REST and GraphQL APIs.
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+/// Root of the raw files of the repositories, used to read their manifests.
+pub const GITHUB_RAW_URL: &str = "https://raw.githubusercontent.com";
+
+lazy_static! {
+    /// Client used by the [`GitHubLink`] shortcuts.
+    pub(crate) static ref DEFAULT_CLIENT: Arc<GitHubClient> = Arc::new(GitHubClient::default());
+}
+
+/// How the repositories data is retrieved from GitHub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchMode {
+    /// Scrape the public HTML pages.
+    #[default]
+    Html,
+    /// Use the official REST and GraphQL APIs.
+    Api,
+    /// Use the official APIs, and scrape the HTML pages when they fail.
+    Auto,
+}
+
+impl fmt::Display for FetchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchMode::Html => write!(f, "html"),
+            FetchMode::Api => write!(f, "api"),
+            FetchMode::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl FromStr for FetchMode {
+    type Err = GitHubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "html" => Ok(FetchMode::Html),
+            "api" => Ok(FetchMode::Api),
+            "auto" => Ok(FetchMode::Auto),
+            _ => Err(GitHubError::InvalidFetchMode(s.to_string())),
+        }
+    }
+}
+
+/// Fetches the contributors and dependencies of GitHub repositories.
+///
+/// The data is either scraped from the HTML pages or retrieved through the
+/// official APIs depending on its [`FetchMode`]. The requests themselves are
+/// sent by a [`GitHubFetcher`], over the network and paced by the process-wide
+/// [`RateLimiter`](crate::RateLimiter) by default.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use github_scrapper::{FetchMode, GitHubClient, GitHubLink};
+///
+/// async fn example() {
+///     let client = Arc::new(GitHubClient::new(FetchMode::Auto));
+///     let link = GitHubLink::try_from("https://github.com/tokio-rs/tokio".to_string()).unwrap();
+///     let contributors = client.fetch_contributors(&link).await;
+///     let mut dep_iterator = client.dependencies(&link);
+///     while let Some(dep) = dep_iterator.next().await {
+///         // Do something with dep
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GitHubClient<F = DefaultFetcher> {
+    fetcher: F,
+    mode: FetchMode,
+    web_url: String,
+    api_url: String,
+    raw_url: String,
+}
+
+impl Default for GitHubClient {
+    fn default() -> Self {
+        Self::new(FetchMode::default())
+    }
+}
+
+impl GitHubClient {
+    pub fn new(mode: FetchMode) -> Self {
+        Self::with_fetcher(DefaultFetcher::default(), mode)
+    }
+
+    /// A client authenticating its API requests with the given tokens.
+    pub fn with_tokens(mode: FetchMode, tokens: Vec<GitHubToken>) -> Self {
+        let fetcher =
+            AuthenticatedFetcher::new(RateLimitedFetcher::default(), TokenPool::new(tokens));
+        Self::with_fetcher(fetcher, mode)
+    }
+}
+
+impl<F: GitHubFetcher> GitHubClient<F> {
+    /// A client sending its requests through the given fetcher.
+    pub fn with_fetcher(fetcher: F, mode: FetchMode) -> Self {
+        Self {
+            fetcher,
+            mode,
+            web_url: GITHUB_WEB_URL.to_string(),
+            api_url: GITHUB_API_URL.to_string(),
+            raw_url: GITHUB_RAW_URL.to_string(),
+        }
+    }
+
+    /// Overrides the root of the GitHub website, for example to target a mock server.
+    pub fn with_web_url(mut self, url: &str) -> Self {
+        self.web_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Overrides the root of the GitHub APIs, for example to target a mock server.
+    pub fn with_api_url(mut self, url: &str) -> Self {
+        self.api_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Overrides the root of the raw files, for example to target a mock server.
+    pub fn with_raw_url(mut self, url: &str) -> Self {
+        self.raw_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn mode(&self) -> FetchMode {
+        self.mode
+    }
+
+    pub(crate) fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    pub(crate) fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// The number of contributors of the repository.
+    pub async fn fetch_contributors(&self, link: &GitHubLink) -> Result<usize, GitHubError> {
+        counter!("fetch", "type" => "contributor").increment(1);
+        match self.mode {
+            FetchMode::Html => self.html_contributors(link).await,
+            FetchMode::Api => api::fetch_contributors(self, link).await,
+            FetchMode::Auto => match api::fetch_contributors(self, link).await {
+                Err(e) if should_fallback(&e) => {
+                    warn!("API contributors fetching failed for {link}, scraping HTML: {e}");
+                    self.html_contributors(link).await
+                }
+                result => result,
+            },
+        }
+    }
+
+    /// Every contributor of the repository with their number of commits, to
+    /// tell apart the people contributing to several repositories and to
+    /// measure how much the repository depends on few of them.
+    ///
+    /// They are always fetched through the REST API whatever the [`FetchMode`],
+    /// since the website does not list them.
+    pub async fn fetch_contributions(
+        &self,
+        link: &GitHubLink,
+    ) -> Result<Vec<Contribution>, GitHubError> {
+        counter!("fetch", "type" => "contributions").increment(1);
+        api::fetch_contributions(self, link).await
+    }
+
+    /// The dependencies of the repository, as listed in its dependency graph.
+    pub fn dependencies(self: &Arc<Self>, link: &GitHubLink) -> GitHubLinkDependencies<F> {
+        GitHubLinkDependencies::with_client(link.clone(), self.clone())
+    }
+
+    /// Fetches a page of the GitHub website from its path, such as `/OWNER/REPO`.
+    pub(crate) async fn fetch_html(&self, path: &str) -> Result<Html, GitHubError> {
+        let request = FetchRequest::get(&format!("{}{}", self.web_url, path));
+        fetch_page(&self.fetcher, &request).await
+    }
+
+    /// A file of the default branch of the repository, `None` when it has none
+    /// at this path.
+    pub(crate) async fn fetch_file(
+        &self,
+        link: &GitHubLink,
+        path: &str,
+    ) -> Result<Option<String>, GitHubError> {
+        let url = format!("{}/{}/HEAD/{path}", self.raw_url, link.path());
+        match check_status(self.fetcher.fetch(&FetchRequest::get(&url)).await?, &url) {
+            Ok(response) => Ok(Some(response.body)),
+            Err(GitHubError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn html_contributors(&self, link: &GitHubLink) -> Result<usize, GitHubError> {
+        let html = self.fetch_html(&format!("/{}", link.path())).await?;
+        link.get_contributors_from_html(&html)
+    }
+}
+
+/// Whether an API failure should be retried by scraping the HTML pages.
+///
+/// A repository that does not exist will not exist on the website either.
+pub(crate) fn should_fallback(error: &GitHubError) -> bool {
+    !matches!(error, GitHubError::NotFound(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpFetcher;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_fetch_mode_parsing() {
+        assert_eq!("html".parse::<FetchMode>().unwrap(), FetchMode::Html);
+        assert_eq!("API".parse::<FetchMode>().unwrap(), FetchMode::Api);
+        assert_eq!(" auto ".parse::<FetchMode>().unwrap(), FetchMode::Auto);
+        assert!("graphql".parse::<FetchMode>().is_err());
+        for mode in [FetchMode::Html, FetchMode::Api, FetchMode::Auto] {
+            assert_eq!(mode.to_string().parse::<FetchMode>().unwrap(), mode);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_falls_back_to_html() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/OWNER/REPO/contributors"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/OWNER/REPO"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<a href="/OWNER/REPO/graphs/contributors">Contributors <span>1,234</span></a>"#,
+            ))
+            .mount(&server)
+            .await;
+        let link = GitHubLink::try_from("https://github.com/OWNER/REPO".to_string()).unwrap();
+
+        let api = GitHubClient::with_fetcher(HttpFetcher::default(), FetchMode::Api)
+            .with_api_url(&server.uri())
+            .with_web_url(&server.uri());
+        assert!(api.fetch_contributors(&link).await.is_err());
+
+        let auto = GitHubClient::with_fetcher(HttpFetcher::default(), FetchMode::Auto)
+            .with_api_url(&server.uri())
+            .with_web_url(&server.uri());
+        assert_eq!(auto.fetch_contributors(&link).await.unwrap(), 1234);
+    }
+}
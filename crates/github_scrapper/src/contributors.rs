@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+/// Someone who contributed to a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Contributor {
+    /// A GitHub account
+    User { login: String },
+    /// An author of commits whose email is not linked to a GitHub account
+    Anonymous {
+        email: Option<String>,
+        name: Option<String>,
+    },
+}
+
+impl Contributor {
+    /// Key telling whether two contributors of different repositories are the
+    /// same person: the login of an account, or the email of an anonymous
+    /// contributor, case insensitive.
+    ///
+    /// A login can not contain a `:`, so an account and an anonymous
+    /// contributor never share an identity.
+    pub fn identity(&self) -> String {
+        match self {
+            Contributor::User { login } => login.to_lowercase(),
+            Contributor::Anonymous { email, name } => format!(
+                "anonymous:{}",
+                email
+                    .as_deref()
+                    .or(name.as_deref())
+                    .unwrap_or_default()
+                    .to_lowercase()
+            ),
+        }
+    }
+}
+
+/// A contributor of a repository, with the number of commits they authored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub contributor: Contributor,
+    pub commits: u64,
+}
+
+/// How much the commits of a repository depend on few people.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Concentration {
+    /// Fewest contributors who together authored more than half of the commits
+    pub bus_factor: usize,
+    /// Share of the commits authored by the top contributor, between 0 and 1
+    pub top_share: f64,
+}
+
+impl Concentration {
+    /// Concentration of the commits of each contributor, `None` without any commit.
+    ///
+    /// ```rust
+    /// use github_scrapper::Concentration;
+    ///
+    /// let concentration = Concentration::from_commits([95, 3, 2]).unwrap();
+    /// assert_eq!(concentration.bus_factor, 1);
+    /// assert_eq!(concentration.top_share, 0.95);
+    /// ```
+    pub fn from_commits(commits: impl IntoIterator<Item = u64>) -> Option<Self> {
+        let mut commits: Vec<u64> = commits.into_iter().filter(|&c| c > 0).collect();
+        let total: u64 = commits.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        commits.sort_unstable_by(|a, b| b.cmp(a));
+        let mut covered = 0;
+        let bus_factor = commits
+            .iter()
+            .take_while(|&&c| {
+                let below_half = covered * 2 <= total;
+                covered += c;
+                below_half
+            })
+            .count();
+        Some(Self {
+            bus_factor,
+            top_share: commits[0] as f64 / total as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concentration() {
+        assert_eq!(Concentration::from_commits([]), None);
+        assert_eq!(Concentration::from_commits([0, 0]), None);
+        let even = Concentration::from_commits([10, 10, 10, 10]).unwrap();
+        assert_eq!(even.bus_factor, 3);
+        assert_eq!(even.top_share, 0.25);
+        // Exactly half is not more than half
+        let halves = Concentration::from_commits([5, 5]).unwrap();
+        assert_eq!(halves.bus_factor, 2);
+        let skewed = Concentration::from_commits([1, 2, 60, 40]).unwrap();
+        assert_eq!(skewed.bus_factor, 1);
+        assert!((skewed.top_share - 60.0 / 103.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_identity() {
+        let user = |login: &str| Contributor::User {
+            login: login.to_string(),
+        };
+        assert_eq!(user("Darksonn").identity(), user("darksonn").identity());
+        assert_eq!(
+            Contributor::Anonymous {
+                email: Some("Alice@Example.com".to_string()),
+                name: Some("Alice".to_string()),
+            }
+            .identity(),
+            "anonymous:alice@example.com"
+        );
+        assert_eq!(
+            Contributor::Anonymous {
+                email: None,
+                name: Some("Bob".to_string()),
+            }
+            .identity(),
+            "anonymous:bob"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{GitHubLink, RepositoryId};
+
+/// Package manager of a dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Pip,
+    Go,
+    Maven,
+    NuGet,
+    RubyGems,
+    Composer,
+    Actions,
+    /// Any other package manager, lowercase
+    Other(String),
+}
+
+impl FromStr for Ecosystem {
+    type Err = Infallible;
+
+    /// Parses the names shown on the dependency graph page and the package
+    /// managers of the GraphQL API, such as `Cargo` or `RUST`, case insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ecosystem = s.trim().to_lowercase();
+        Ok(match ecosystem.as_str() {
+            "cargo" | "rust" | "crates.io" => Ecosystem::Cargo,
+            "npm" | "yarn" | "pnpm" => Ecosystem::Npm,
+            "pip" | "pypi" | "python" | "poetry" => Ecosystem::Pip,
+            "go" | "golang" | "gomod" => Ecosystem::Go,
+            "maven" | "gradle" => Ecosystem::Maven,
+            "nuget" => Ecosystem::NuGet,
+            "rubygems" | "bundler" => Ecosystem::RubyGems,
+            "composer" => Ecosystem::Composer,
+            "actions" | "github actions" => Ecosystem::Actions,
+            _ => Ecosystem::Other(ecosystem),
+        })
+    }
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Pip => "pip",
+            Ecosystem::Go => "go",
+            Ecosystem::Maven => "maven",
+            Ecosystem::NuGet => "nuget",
+            Ecosystem::RubyGems => "rubygems",
+            Ecosystem::Composer => "composer",
+            Ecosystem::Actions => "actions",
+            Ecosystem::Other(name) => name,
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A package a repository depends on, as listed in its dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    /// Package manager of the package, when known
+    pub ecosystem: Option<Ecosystem>,
+    pub package_name: String,
+    /// Version requirement of the manifest, such as `^1.2` or `= 0.3.1`
+    pub requirements: Option<String>,
+    /// Path of the manifest declaring the dependency, such as `Cargo.lock`
+    pub manifest: Option<String>,
+    /// The repository of the package, `None` when it does not resolve to a
+    /// repository of a supported [`Forge`](crate::Forge)
+    pub repository: Option<RepositoryId>,
+    /// Repository URL of the package as listed, whatever its host
+    pub url: Option<String>,
+}
+
+/// Why a dependency does not resolve to a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Unresolved {
+    /// The dependency graph links the package to no repository
+    NoLink,
+    /// The package links to something else than a repository of a supported
+    /// [`Forge`](crate::Forge), such as a Bitbucket one
+    Unsupported(String),
+}
+
+impl fmt::Display for Unresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unresolved::NoLink => write!(f, "no repository link"),
+            Unresolved::Unsupported(url) => {
+                write!(f, "{url} is not a repository of a supported forge")
+            }
+        }
+    }
+}
+
+impl Dependency {
+    /// The repository of the package on any supported forge: the one it
+    /// resolved to, or else the one its URL links to.
+    pub fn repository_id(&self) -> Option<RepositoryId> {
+        match &self.repository {
+            Some(id) => Some(id.clone()),
+            None => RepositoryId::try_from(self.url.clone()?).ok(),
+        }
+    }
+
+    /// Why the dependency does not resolve to a repository, `None` when it does.
+    pub fn unresolved(&self) -> Option<Unresolved> {
+        if self.repository_id().is_some() {
+            return None;
+        }
+        Some(match &self.url {
+            Some(url) => Unresolved::Unsupported(url.clone()),
+            None => Unresolved::NoLink,
+        })
+    }
+}
+
+impl From<GitHubLink> for Dependency {
+    /// A dependency only known by its repository, named after it.
+    fn from(link: GitHubLink) -> Self {
+        Self {
+            ecosystem: None,
+            package_name: link.repo().to_string(),
+            requirements: None,
+            manifest: None,
+            url: Some(link.link().to_string()),
+            repository: Some(RepositoryId::from(link)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ecosystem_names() {
+        for (name, ecosystem) in [
+            ("Cargo", Ecosystem::Cargo),
+            ("RUST", Ecosystem::Cargo),
+            ("npm", Ecosystem::Npm),
+            ("PIP", Ecosystem::Pip),
+            ("Go", Ecosystem::Go),
+            ("GitHub Actions", Ecosystem::Actions),
+            ("Hex", Ecosystem::Other("hex".to_string())),
+        ] {
+            assert_eq!(name.parse::<Ecosystem>().unwrap(), ecosystem, "{name}");
+        }
+        assert_eq!(Ecosystem::RubyGems.to_string(), "rubygems");
+        assert_eq!(Ecosystem::Other("hex".to_string()).to_string(), "hex");
+        // Displayed names parse back to the same ecosystem
+        assert_eq!(
+            Ecosystem::NuGet.to_string().parse::<Ecosystem>().unwrap(),
+            Ecosystem::NuGet
+        );
+    }
+
+    #[test]
+    fn test_unresolved_reasons() {
+        let link = GitHubLink::try_from("https://github.com/tokio-rs/tokio".to_string()).unwrap();
+        let resolved = Dependency::from(link);
+        assert_eq!(resolved.unresolved(), None);
+
+        let unlinked = Dependency {
+            repository: None,
+            url: None,
+            ..resolved.clone()
+        };
+        assert_eq!(unlinked.unresolved(), Some(Unresolved::NoLink));
+
+        let gitlab = Dependency {
+            repository: None,
+            url: Some("https://gitlab.com/tokio/tokio".to_string()),
+            ..resolved.clone()
+        };
+        assert_eq!(gitlab.unresolved(), None);
+        assert_eq!(
+            gitlab.repository_id().unwrap().path(),
+            "gitlab.com/tokio/tokio"
+        );
+
+        let bitbucket = Dependency {
+            repository: None,
+            url: Some("https://bitbucket.org/tokio/tokio".to_string()),
+            ..resolved
+        };
+        assert_eq!(
+            bitbucket.unresolved().unwrap().to_string(),
+            "https://bitbucket.org/tokio/tokio is not a repository of a supported forge"
+        );
+    }
+}
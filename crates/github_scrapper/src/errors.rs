@@ -11,9 +11,42 @@ pub enum GitHubError {
     #[error("Request Error")]
     Request(#[from] reqwest::Error),
     /// The request response was not successful
-    #[error("Request Error")]
-    RequestResponse(reqwest::Response),
+    #[error("Request Error: {url} answered {status}")]
+    RequestResponse { url: String, status: u16 },
     /// No contributors were found on the project
     #[error("No contributors component found: {0}")]
     NoContributorsComponent(String),
+    /// The GitHub API answered with an unexpected payload
+    #[error("Unexpected API response: {0}")]
+    UnexpectedApiResponse(String),
+    /// GitHub kept rate limiting the request after several retries
+    #[error("Rate Limited: {url}, retry after {retry_after:?}")]
+    RateLimited {
+        url: String,
+        retry_after: std::time::Duration,
+    },
+    /// The response could not be deserialized
+    #[error("Deserialization Error")]
+    Deserialize(#[from] serde_json::Error),
+    /// No response was recorded for this request
+    #[error("Missing Fixture: {0}")]
+    MissingFixture(String),
+    /// A recorded response could not be saved
+    #[error("Recording Error")]
+    Io(#[from] std::io::Error),
+    /// The provided fetch mode is unknown
+    #[error("Invalid fetch mode: {0}")]
+    InvalidFetchMode(String),
+    /// The provided GitHub token is empty
+    #[error("Invalid GitHub token")]
+    InvalidToken,
+    /// A manifest of the repository could not be parsed
+    #[error("Invalid manifest {path}: {reason}")]
+    InvalidManifest { path: String, reason: String },
+    /// The document is neither an SPDX nor a CycloneDX JSON SBOM
+    #[error("Invalid SBOM: {0}")]
+    InvalidSbom(String),
+    /// The provided graph export format is unknown
+    #[error("Invalid graph format: {0}")]
+    InvalidGraphFormat(String),
 }
@@ -0,0 +1,315 @@
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, StatusCode, Url};
+use tracing::{debug, info};
+
+use crate::GitHubError;
+
+const USER_AGENT: &str = concat!("howmanycontributors/", env!("CARGO_PKG_VERSION"));
+
+/// A request sent to GitHub, either to its website or to its APIs.
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+    /// Whether the request may carry a token, only the GitHub APIs accept them.
+    pub authenticated: bool,
+}
+
+impl FetchRequest {
+    pub fn get(url: &str) -> Self {
+        Self {
+            method: Method::GET,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: None,
+            authenticated: false,
+        }
+    }
+
+    pub fn post(url: &str, body: String) -> Self {
+        Self {
+            method: Method::POST,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: Some(body),
+            authenticated: false,
+        }
+    }
+
+    pub fn header(mut self, name: HeaderName, value: &'static str) -> Self {
+        self.headers.insert(name, HeaderValue::from_static(value));
+        self
+    }
+
+    /// Marks the request as sent to the GitHub APIs, with a token when available.
+    pub fn authenticated(mut self) -> Self {
+        self.authenticated = true;
+        self
+    }
+}
+
+/// The response to a [`FetchRequest`], whatever its status.
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// Sends the requests of a [`GitHubClient`](crate::GitHubClient).
+///
+/// This is the extension point used to drive the scrapper without reaching
+/// github.com, for example with a [`ReplayFetcher`] in tests.
+pub trait GitHubFetcher: Send + Sync {
+    fn fetch(
+        &self,
+        request: &FetchRequest,
+    ) -> impl Future<Output = Result<FetchResponse, GitHubError>> + Send;
+}
+
+/// Sends the requests over the network.
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    http: reqwest::Client,
+}
+
+impl Default for HttpFetcher {
+    fn default() -> Self {
+        let http = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+        Self { http }
+    }
+}
+
+impl GitHubFetcher for HttpFetcher {
+    async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+        info!("Fetching: {}", request.url);
+        let mut builder = self
+            .http
+            .request(request.method.clone(), &request.url)
+            .headers(request.headers.clone());
+        if let Some(body) = &request.body {
+            builder = builder
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+        }
+        let response = builder.send().await?;
+        Ok(FetchResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.text().await?,
+        })
+    }
+}
+
+/// Serves the responses previously saved by a [`RecordingFetcher`].
+///
+/// Each response is stored in its own file, named after the request URL, in an
+/// HTTP-like format: the status line, the headers, an empty line and the body.
+/// A request without a saved response fails with [`GitHubError::MissingFixture`].
+#[derive(Debug, Clone)]
+pub struct ReplayFetcher {
+    directory: PathBuf,
+}
+
+impl ReplayFetcher {
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl GitHubFetcher for ReplayFetcher {
+    async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+        let path = fixture_path(&self.directory, request)?;
+        debug!("Replaying: {} from {}", request.url, path.display());
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Err(GitHubError::MissingFixture(path.display().to_string()));
+        };
+        parse_fixture(&content)
+            .ok_or_else(|| GitHubError::MissingFixture(format!("Invalid {}", path.display())))
+    }
+}
+
+/// Forwards the requests to another fetcher and saves its responses so they can
+/// be served by a [`ReplayFetcher`].
+#[derive(Debug, Clone)]
+pub struct RecordingFetcher<F> {
+    inner: F,
+    directory: PathBuf,
+}
+
+impl<F: GitHubFetcher> RecordingFetcher<F> {
+    pub fn new<P: AsRef<Path>>(inner: F, directory: P) -> Self {
+        Self {
+            inner,
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl<F: GitHubFetcher> GitHubFetcher for RecordingFetcher<F> {
+    async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+        let response = self.inner.fetch(request).await?;
+        let path = fixture_path(&self.directory, request)?;
+        info!("Recording: {} into {}", request.url, path.display());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, format_fixture(&response))?;
+        Ok(response)
+    }
+}
+
+/// The fixture file of a request: `<directory>/<host>/<path>[@<query>][#<body hash>].http`.
+fn fixture_path(directory: &Path, request: &FetchRequest) -> Result<PathBuf, GitHubError> {
+    let url =
+        Url::parse(&request.url).map_err(|_| GitHubError::InvalidLink(request.url.clone()))?;
+    let mut name = url.path().trim_matches('/').to_string();
+    if name.is_empty() {
+        name.push_str("index");
+    }
+    if let Some(query) = url.query() {
+        name.push('@');
+        name.push_str(query);
+    }
+    if let Some(body) = &request.body {
+        name.push_str(&format!("#{:016x}", fnv1a(body.as_bytes())));
+    }
+    name.push_str(".http");
+    Ok(directory
+        .join(url.host_str().unwrap_or("localhost"))
+        .join(name))
+}
+
+/// A hash that does not change across Rust versions, unlike the standard library's.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn format_fixture(response: &FetchResponse) -> String {
+    let mut content = format!(
+        "HTTP/1.1 {} {}\n",
+        response.status.as_u16(),
+        response.status.canonical_reason().unwrap_or("")
+    );
+    for (name, value) in response.headers.iter() {
+        if let Ok(value) = value.to_str() {
+            content.push_str(&format!("{name}: {value}\n"));
+        }
+    }
+    content.push('\n');
+    content.push_str(&response.body);
+    content
+}
+
+fn parse_fixture(content: &str) -> Option<FetchResponse> {
+    let (head, body) = content.split_once("\n\n").unwrap_or((content, ""));
+    let mut lines = head.lines();
+    let status = lines
+        .next()?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<u16>()
+        .ok()?;
+    let mut headers = HeaderMap::new();
+    for line in lines {
+        let (name, value) = line.split_once(':')?;
+        headers.append(
+            HeaderName::from_bytes(name.trim().as_bytes()).ok()?,
+            HeaderValue::from_str(value.trim()).ok()?,
+        );
+    }
+    Some(FetchResponse {
+        status: StatusCode::from_u16(status).ok()?,
+        headers,
+        body: body.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_path() {
+        let directory = Path::new("fixtures");
+        let path = |request: &FetchRequest| fixture_path(directory, request).unwrap();
+
+        assert_eq!(
+            path(&FetchRequest::get("https://github.com/OWNER/REPO")),
+            directory.join("github.com/OWNER/REPO.http")
+        );
+        assert_eq!(
+            path(&FetchRequest::get(
+                "https://github.com/OWNER/REPO/network/dependencies?page=2"
+            )),
+            directory.join("github.com/OWNER/REPO/network/dependencies@page=2.http")
+        );
+        assert_ne!(
+            path(&FetchRequest::post(
+                "https://api.github.com/graphql",
+                "{}".to_string()
+            )),
+            path(&FetchRequest::post(
+                "https://api.github.com/graphql",
+                "[]".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_fixture_round_trip() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("60"));
+        let response = FetchResponse {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            headers,
+            body: "<html>\n\n<body></body>\n</html>".to_string(),
+        };
+
+        let parsed = parse_fixture(&format_fixture(&response)).unwrap();
+        assert_eq!(parsed.status, response.status);
+        assert_eq!(parsed.headers, response.headers);
+        assert_eq!(parsed.body, response.body);
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        struct StaticFetcher;
+        impl GitHubFetcher for StaticFetcher {
+            async fn fetch(&self, _: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+                Ok(FetchResponse {
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: "recorded".to_string(),
+                })
+            }
+        }
+        let directory = std::env::temp_dir().join(format!("hmc-fixtures-{}", std::process::id()));
+        let request = FetchRequest::get("https://github.com/OWNER/REPO");
+
+        let replay = ReplayFetcher::new(&directory);
+        assert!(matches!(
+            replay.fetch(&request).await,
+            Err(GitHubError::MissingFixture(_))
+        ));
+
+        let recorder = RecordingFetcher::new(StaticFetcher, &directory);
+        recorder.fetch(&request).await.unwrap();
+        assert_eq!(replay.fetch(&request).await.unwrap().body, "recorded");
+
+        let _ = fs::remove_dir_all(directory);
+    }
+}
@@ -0,0 +1,453 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Url;
+use tracing::warn;
+
+use crate::{
+    GitHubClient, GitHubError, GitHubFetcher, GitHubLink, GitLabClient, GiteaClient, SbomFormat,
+};
+
+lazy_static! {
+    static ref SEGMENT_PATTERN: Regex = Regex::new(r"^[a-zA-Z0-9_\.-]{1,101}$").unwrap();
+    /// Self-hosted instances allowed by the operator, see [`Forge::allow`]
+    static ref ALLOWED_FORGES: RwLock<Vec<Forge>> = RwLock::new(vec![]);
+}
+
+/// Public GitLab instances, known without being allowed
+const GITLAB_HOSTS: &[&str] = &[
+    "gitlab.com",
+    "salsa.debian.org",
+    "invent.kde.org",
+    "framagit.org",
+];
+/// Public Gitea and Forgejo instances, known without being allowed
+const GITEA_HOSTS: &[&str] = &["codeberg.org", "gitea.com"];
+/// Host of the imported SBOMs, which can not be the one of a real forge
+const SBOM_HOST: &str = "sbom";
+
+/// A code forge hosting repositories.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub enum Forge {
+    GitHub,
+    /// gitlab.com, or a self-hosted GitLab allowed with [`Forge::allow`]
+    GitLab(String),
+    /// A Gitea or Forgejo instance, such as codeberg.org
+    Gitea(String),
+    /// Not a forge: the imported SBOMs, each the root of the graph of its
+    /// packages, see [`RepositoryId::sbom`]
+    Sbom,
+}
+
+impl Forge {
+    /// The forge hosted at `host`, `None` when it is neither a well known
+    /// instance nor one allowed with [`Forge::allow`].
+    ///
+    /// The hosts are never guessed from their name: their API is requested for
+    /// every repository linking to them, whoever wrote the link.
+    pub fn from_host(host: &str) -> Option<Self> {
+        let host = normalized_host(host);
+        if host == "github.com" {
+            Some(Forge::GitHub)
+        } else if GITLAB_HOSTS.contains(&host.as_str()) {
+            Some(Forge::GitLab(host))
+        } else if GITEA_HOSTS.contains(&host.as_str()) {
+            Some(Forge::Gitea(host))
+        } else {
+            ALLOWED_FORGES
+                .read()
+                .unwrap()
+                .iter()
+                .find(|forge| forge.host() == host)
+                .cloned()
+        }
+    }
+
+    /// Allows the repositories of self-hosted GitLab or Gitea instances, such
+    /// as `Forge::GitLab("gitlab.gnome.org".to_string())`, for the whole
+    /// process. Only the operator should tell them.
+    ///
+    /// GitHub and the SBOMs are not hosts to allow, and are left aside.
+    pub fn allow(forges: impl IntoIterator<Item = Forge>) {
+        let mut allowed = ALLOWED_FORGES.write().unwrap();
+        for forge in forges {
+            let forge = match forge {
+                Forge::GitLab(host) => Forge::GitLab(normalized_host(&host)),
+                Forge::Gitea(host) => Forge::Gitea(normalized_host(&host)),
+                Forge::GitHub | Forge::Sbom => {
+                    warn!("Not allowing {forge:?}, only self-hosted instances are");
+                    continue;
+                }
+            };
+            if forge.host().is_empty() || forge.host() == SBOM_HOST {
+                warn!("Not allowing {forge:?}, its host is reserved");
+            } else if !allowed.contains(&forge) {
+                allowed.push(forge);
+            }
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        match self {
+            Forge::GitHub => "github.com",
+            Forge::GitLab(host) | Forge::Gitea(host) => host,
+            Forge::Sbom => SBOM_HOST,
+        }
+    }
+}
+
+fn normalized_host(host: &str) -> String {
+    host.trim().trim_start_matches("www.").to_lowercase()
+}
+
+/// A repository hosted on any supported [`Forge`].
+///
+/// Its path is `OWNER/REPO` on GitHub, to stay compatible with [`GitHubLink`],
+/// and `HOST/OWNER/REPO` elsewhere. GitLab owners can be nested groups, such as
+/// `gitlab.com/group/subgroup/project`.
+///
+/// ```rust
+/// use github_scrapper::{Forge, RepositoryId};
+///
+/// let id = RepositoryId::try_from("https://codeberg.org/forgejo/forgejo".to_string()).unwrap();
+/// assert_eq!(id.forge(), &Forge::Gitea("codeberg.org".to_string()));
+/// assert_eq!(id.path(), "codeberg.org/forgejo/forgejo");
+/// assert_eq!(RepositoryId::from_path(&id.path()).unwrap(), id);
+///
+/// let id = RepositoryId::from_path("tokio-rs/tokio").unwrap();
+/// assert_eq!(id.url(), "https://github.com/tokio-rs/tokio");
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct RepositoryId {
+    forge: Forge,
+    owner: String,
+    repo: String,
+}
+
+impl fmt::Display for RepositoryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path())
+    }
+}
+
+impl From<GitHubLink> for RepositoryId {
+    fn from(link: GitHubLink) -> Self {
+        Self {
+            forge: Forge::GitHub,
+            owner: link.owner().to_string(),
+            repo: link.repo().to_string(),
+        }
+    }
+}
+
+impl From<&GitHubLink> for RepositoryId {
+    fn from(link: &GitHubLink) -> Self {
+        Self::from(link.clone())
+    }
+}
+
+impl TryFrom<String> for RepositoryId {
+    type Error = GitHubError;
+
+    /// Parses the URL of a repository, or of any page of it such as
+    /// `https://gitlab.com/OWNER/REPO/-/tree/main`. `git+` prefixes and `.git`
+    /// suffixes, as found in package manifests, are ignored.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || GitHubError::InvalidLink(value.trim().to_string());
+        let url = Url::parse(value.trim().trim_start_matches("git+")).map_err(|_| invalid())?;
+        if url.scheme() != "https" && url.scheme() != "http" {
+            return Err(invalid());
+        }
+        let forge = url
+            .host_str()
+            .and_then(Forge::from_host)
+            .ok_or_else(invalid)?;
+        let mut segments: Vec<&str> = url
+            .path_segments()
+            .ok_or_else(invalid)?
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        match forge {
+            // Pages of a GitLab project follow a `-` segment
+            Forge::GitLab(_) => {
+                if let Some(end) = segments.iter().position(|segment| *segment == "-") {
+                    segments.truncate(end);
+                }
+            }
+            Forge::GitHub | Forge::Gitea(_) | Forge::Sbom => segments.truncate(2),
+        }
+        let Some((repo, owner)) = segments.split_last() else {
+            return Err(invalid());
+        };
+        let repo = repo.strip_suffix(".git").unwrap_or(repo);
+        if owner.is_empty()
+            || !owner
+                .iter()
+                .chain([&repo])
+                .all(|s| SEGMENT_PATTERN.is_match(s))
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            forge,
+            owner: owner.join("/"),
+            repo: repo.to_string(),
+        })
+    }
+}
+
+impl RepositoryId {
+    /// Parses a [`path`](RepositoryId::path), `OWNER/REPO` being a GitHub repository.
+    ///
+    /// Unlike the URLs, the paths of the roots of the imported SBOMs are parsed,
+    /// as saved along with their crawls.
+    pub fn from_path(path: &str) -> Result<Self, GitHubError> {
+        let path = path.trim().trim_matches('/');
+        if path.matches('/').count() == 1 {
+            return GitHubLink::try_from(format!("https://github.com/{path}")).map(Self::from);
+        }
+        if let Some(sbom) = path.strip_prefix(&format!("{SBOM_HOST}/")) {
+            let invalid = || GitHubError::InvalidLink(path.to_string());
+            let (format, id) = sbom.split_once('/').ok_or_else(invalid)?;
+            return Self::sbom(format.parse().map_err(|_| invalid())?, id);
+        }
+        Self::try_from(format!("https://{path}"))
+    }
+
+    /// The root of the graph of the packages of an imported SBOM, whose path
+    /// is `sbom/FORMAT/ID`. The only way to get a repository of [`Forge::Sbom`].
+    pub fn sbom(format: SbomFormat, id: &str) -> Result<Self, GitHubError> {
+        if !SEGMENT_PATTERN.is_match(id) {
+            return Err(GitHubError::InvalidLink(format!(
+                "{SBOM_HOST}/{format}/{id}"
+            )));
+        }
+        Ok(Self {
+            forge: Forge::Sbom,
+            owner: format.to_string(),
+            repo: id.to_string(),
+        })
+    }
+
+    pub fn forge(&self) -> &Forge {
+        &self.forge
+    }
+
+    /// The owner of the repository, the groups of a GitLab project joined by `/`
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub fn repo(&self) -> &str {
+        &self.repo
+    }
+
+    /// `OWNER/REPO` on GitHub, `HOST/OWNER/REPO` on the other forges
+    pub fn path(&self) -> String {
+        match self.forge {
+            Forge::GitHub => format!("{}/{}", self.owner, self.repo),
+            _ => format!("{}/{}/{}", self.forge.host(), self.owner, self.repo),
+        }
+    }
+
+    /// The web page of the repository
+    pub fn url(&self) -> String {
+        format!("https://{}/{}/{}", self.forge.host(), self.owner, self.repo)
+    }
+
+    /// The repository as a [`GitHubLink`], `None` when it is not on GitHub
+    pub fn github(&self) -> Option<GitHubLink> {
+        match self.forge {
+            Forge::GitHub => GitHubLink::try_from(self.url()).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Counts the contributors of the repositories of some forges.
+///
+/// This is the extension point to support a new forge, see [`Forges`] for the
+/// providers used by default.
+pub trait ForgeProvider: Send + Sync {
+    /// Whether the provider knows the repositories of the forge.
+    fn serves(&self, forge: &Forge) -> bool;
+
+    /// The number of contributors of the repository.
+    fn fetch_contributors(
+        &self,
+        repository: &RepositoryId,
+    ) -> impl Future<Output = Result<usize, GitHubError>> + Send;
+}
+
+impl<F: GitHubFetcher> ForgeProvider for GitHubClient<F> {
+    fn serves(&self, forge: &Forge) -> bool {
+        *forge == Forge::GitHub
+    }
+
+    async fn fetch_contributors(&self, repository: &RepositoryId) -> Result<usize, GitHubError> {
+        match repository.github() {
+            Some(link) => GitHubClient::fetch_contributors(self, &link).await,
+            None => Err(GitHubError::InvalidLink(repository.url())),
+        }
+    }
+}
+
+/// The providers of every supported forge, counting the contributors of a
+/// repository with the one serving its forge.
+#[derive(Debug, Clone)]
+pub struct Forges<F = crate::DefaultFetcher, G = crate::RateLimitedFetcher> {
+    github: Arc<GitHubClient<F>>,
+    gitlab: GitLabClient<G>,
+    gitea: GiteaClient<G>,
+}
+
+impl<F: GitHubFetcher> Forges<F> {
+    /// The GitHub client along with the default GitLab and Gitea ones.
+    pub fn new(github: Arc<GitHubClient<F>>) -> Self {
+        Self::with_providers(github, GitLabClient::default(), GiteaClient::default())
+    }
+}
+
+impl<F: GitHubFetcher, G: GitHubFetcher> Forges<F, G> {
+    pub fn with_providers(
+        github: Arc<GitHubClient<F>>,
+        gitlab: GitLabClient<G>,
+        gitea: GiteaClient<G>,
+    ) -> Self {
+        Self {
+            github,
+            gitlab,
+            gitea,
+        }
+    }
+
+    pub fn github(&self) -> &Arc<GitHubClient<F>> {
+        &self.github
+    }
+
+    /// The number of contributors of the repository, whatever its forge.
+    pub async fn fetch_contributors(
+        &self,
+        repository: &RepositoryId,
+    ) -> Result<usize, GitHubError> {
+        let forge = repository.forge();
+        if *forge == Forge::Sbom {
+            // The packages of an SBOM have contributors, not the SBOM itself
+            return Ok(0);
+        }
+        if self.github.serves(forge) {
+            ForgeProvider::fetch_contributors(self.github.as_ref(), repository).await
+        } else if self.gitlab.serves(forge) {
+            self.gitlab.fetch_contributors(repository).await
+        } else if self.gitea.serves(forge) {
+            self.gitea.fetch_contributors(repository).await
+        } else {
+            Err(GitHubError::InvalidLink(repository.url()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(url: &str) -> Result<RepositoryId, GitHubError> {
+        RepositoryId::try_from(url.to_string())
+    }
+
+    #[test]
+    fn test_forge_hosts() {
+        assert_eq!(Forge::from_host("github.com"), Some(Forge::GitHub));
+        assert_eq!(
+            Forge::from_host("salsa.debian.org"),
+            Some(Forge::GitLab("salsa.debian.org".to_string()))
+        );
+        assert_eq!(
+            Forge::from_host("Codeberg.org"),
+            Some(Forge::Gitea("codeberg.org".to_string()))
+        );
+        assert_eq!(Forge::from_host("bitbucket.org"), None);
+        // Not guessed from their name, such as an internal service
+        assert_eq!(Forge::from_host("gitlab.internal"), None);
+        assert_eq!(Forge::from_host("forgejo.localhost"), None);
+        assert_eq!(Forge::from_host("sbom"), None);
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        assert_eq!(Forge::from_host("gitlab.example.org"), None);
+        Forge::allow([
+            Forge::GitLab("GitLab.Example.org".to_string()),
+            Forge::Gitea("git.example.org".to_string()),
+            Forge::Gitea(SBOM_HOST.to_string()),
+            Forge::Sbom,
+        ]);
+        assert_eq!(
+            Forge::from_host("gitlab.example.org"),
+            Some(Forge::GitLab("gitlab.example.org".to_string()))
+        );
+        assert_eq!(
+            id("https://git.example.org/owner/repo").unwrap().path(),
+            "git.example.org/owner/repo"
+        );
+        assert_eq!(Forge::from_host(SBOM_HOST), None);
+    }
+
+    #[test]
+    fn test_repository_urls() {
+        let tokio = id("https://github.com/tokio-rs/tokio/tree/master").unwrap();
+        assert_eq!(tokio.path(), "tokio-rs/tokio");
+        assert_eq!(tokio.github().unwrap().path(), "tokio-rs/tokio");
+
+        let nested = id("git+https://gitlab.com/group/subgroup/project.git").unwrap();
+        assert_eq!(nested.owner(), "group/subgroup");
+        assert_eq!(nested.repo(), "project");
+        assert_eq!(nested.path(), "gitlab.com/group/subgroup/project");
+        assert_eq!(nested.github(), None);
+        assert_eq!(
+            id("https://gitlab.com/tspiteri/rug/-/blob/master/Cargo.toml").unwrap(),
+            id("https://gitlab.com/tspiteri/rug").unwrap()
+        );
+        assert_eq!(
+            id("https://codeberg.org/forgejo/forgejo/src/branch/forgejo")
+                .unwrap()
+                .path(),
+            "codeberg.org/forgejo/forgejo"
+        );
+
+        for invalid in [
+            "https://bitbucket.org/owner/repo",
+            "https://gitlab.com/project",
+            "https://github.com/tokio-rs",
+            "ftp://codeberg.org/owner/repo",
+            "codeberg.org/owner/repo",
+            "https://gitlab.internal/owner/repo",
+            "https://sbom/cyclonedx/0123abcd",
+        ] {
+            assert!(id(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_repository_paths() {
+        for path in [
+            "tokio-rs/tokio",
+            "gitlab.com/group/subgroup/project",
+            "codeberg.org/forgejo/forgejo",
+        ] {
+            assert_eq!(RepositoryId::from_path(path).unwrap().path(), path);
+        }
+        assert!(RepositoryId::from_path("tokio-rs").is_err());
+        assert!(RepositoryId::from_path("bitbucket.org/owner/repo").is_err());
+
+        let sbom = RepositoryId::sbom(SbomFormat::CycloneDx, "0123abcd").unwrap();
+        assert_eq!(sbom.path(), "sbom/cyclonedx/0123abcd");
+        assert_eq!(RepositoryId::from_path(&sbom.path()).unwrap(), sbom);
+        assert_eq!(sbom.forge(), &Forge::Sbom);
+        assert!(RepositoryId::from_path("sbom/unknown/0123abcd").is_err());
+        assert!(RepositoryId::sbom(SbomFormat::Spdx, "../0123abcd").is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use metrics::counter;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::utils::check_status;
+use crate::{
+    Contributor, FetchRequest, Forge, ForgeProvider, GitHubError, GitHubFetcher, HttpFetcher,
+    RateLimitedFetcher, RateLimiter, RateLimiterConfig, RepositoryId,
+};
+
+/// Largest page of the Gitea API with its default settings
+const PER_PAGE: usize = 50;
+/// Pages of commits read at most, beyond which the authors of the older
+/// commits are not counted
+const MAX_PAGES: usize = 100;
+/// Whether there are more pages after this one
+const HAS_MORE_HEADER: &str = "x-hasmore";
+/// Leaves out of the commits what is not needed to tell their authors
+const COMMITS_QUERY: &str = "stat=false&verification=false&files=false";
+
+#[derive(Deserialize)]
+struct CommitEntry {
+    /// The account of the author, when their email is linked to one
+    author: Option<AccountEntry>,
+    commit: CommitDetails,
+}
+
+#[derive(Deserialize)]
+struct AccountEntry {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct CommitDetails {
+    author: Option<SignatureEntry>,
+}
+
+#[derive(Deserialize)]
+struct SignatureEntry {
+    name: Option<String>,
+    email: Option<String>,
+}
+
+impl From<CommitEntry> for Contributor {
+    fn from(entry: CommitEntry) -> Self {
+        match entry.author.filter(|account| !account.login.is_empty()) {
+            Some(account) => Contributor::User {
+                login: account.login,
+            },
+            None => {
+                let signature = entry.commit.author;
+                Contributor::Anonymous {
+                    email: signature.as_ref().and_then(|s| s.email.clone()),
+                    name: signature.and_then(|s| s.name),
+                }
+            }
+        }
+    }
+}
+
+/// Counts the contributors of the repositories of Gitea and Forgejo instances,
+/// such as codeberg.org, through their REST API at `https://HOST/api/v1`.
+///
+/// Their API does not list the contributors, so the authors of the commits of
+/// the default branch are counted, an account or else an email being a
+/// contributor. This costs one request per 50 commits, so only the authors of
+/// the latest 5000 commits are counted, a lower bound for the larger
+/// repositories.
+#[derive(Debug, Clone)]
+pub struct GiteaClient<F = RateLimitedFetcher> {
+    fetcher: F,
+    api_url: Option<String>,
+}
+
+impl Default for GiteaClient {
+    /// Requests paced by their own [`RateLimiter`], apart from the GitHub ones.
+    fn default() -> Self {
+        let limiter = Arc::new(RateLimiter::new(RateLimiterConfig::default()));
+        Self::with_fetcher(RateLimitedFetcher::new(HttpFetcher::default(), limiter))
+    }
+}
+
+impl<F: GitHubFetcher> GiteaClient<F> {
+    pub fn with_fetcher(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            api_url: None,
+        }
+    }
+
+    /// Overrides the root of the API of every instance, for example to target a mock server.
+    pub fn with_api_url(mut self, url: &str) -> Self {
+        self.api_url = Some(url.trim_end_matches('/').to_string());
+        self
+    }
+
+    fn api_url(&self, repository: &RepositoryId) -> String {
+        match &self.api_url {
+            Some(url) => url.clone(),
+            None => format!("https://{}/api/v1", repository.forge().host()),
+        }
+    }
+}
+
+impl<F: GitHubFetcher> ForgeProvider for GiteaClient<F> {
+    fn serves(&self, forge: &Forge) -> bool {
+        matches!(forge, Forge::Gitea(_))
+    }
+
+    async fn fetch_contributors(&self, repository: &RepositoryId) -> Result<usize, GitHubError> {
+        counter!("fetch", "type" => "contributor", "forge" => "gitea").increment(1);
+        let mut identities = HashSet::new();
+        for page in 1..=MAX_PAGES {
+            let url = format!(
+                "{}/repos/{}/{}/commits?{COMMITS_QUERY}&limit={PER_PAGE}&page={page}",
+                self.api_url(repository),
+                repository.owner(),
+                repository.repo()
+            );
+            let response = self.fetcher.fetch(&FetchRequest::get(&url)).await?;
+            // Empty repositories have no commits
+            if response.status == StatusCode::CONFLICT {
+                return Ok(0);
+            }
+            let response = check_status(response, &url)?;
+            let has_more = response
+                .headers
+                .get(HAS_MORE_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().eq_ignore_ascii_case("true"));
+
+            let entries: Vec<CommitEntry> = serde_json::from_str(&response.body)?;
+            let full_page = entries.len() == PER_PAGE;
+            identities.extend(
+                entries
+                    .into_iter()
+                    .map(|entry| Contributor::from(entry).identity()),
+            );
+            if !has_more.unwrap_or(full_page) {
+                return Ok(identities.len());
+            }
+        }
+        warn!(
+            "Only counted the authors of the latest {} commits of {repository}",
+            MAX_PAGES * PER_PAGE
+        );
+        counter!("fetch_truncated", "type" => "contributor", "forge" => "gitea").increment(1);
+        Ok(identities.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const COMMITS_PATH: &str = "/repos/forgejo/forgejo/commits";
+
+    fn client(server: &MockServer) -> GiteaClient<HttpFetcher> {
+        GiteaClient::with_fetcher(HttpFetcher::default()).with_api_url(&server.uri())
+    }
+
+    fn repository() -> RepositoryId {
+        RepositoryId::try_from("https://codeberg.org/forgejo/forgejo".to_string()).unwrap()
+    }
+
+    fn commit(login: Option<&str>, email: &str) -> serde_json::Value {
+        json!({
+            "sha": "0000000000000000000000000000000000000000",
+            "author": login.map(|login| json!({ "login": login })),
+            "commit": { "author": { "name": "Someone", "email": email } }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_distinct_authors() {
+        let server = MockServer::start().await;
+        let first_page: Vec<serde_json::Value> = (0..PER_PAGE)
+            .map(|i| {
+                commit(
+                    Some(if i % 2 == 0 { "alice" } else { "Bob" }),
+                    "x@example.com",
+                )
+            })
+            .collect();
+        Mock::given(method("GET"))
+            .and(path(COMMITS_PATH))
+            .and(query_param("page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-hasmore", "true")
+                    .set_body_json(first_page),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(COMMITS_PATH))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-hasmore", "false")
+                    .set_body_json(json!([
+                        commit(Some("bob"), "bob@example.com"),
+                        commit(None, "carol@example.com"),
+                        commit(None, "Carol@example.com"),
+                    ])),
+            )
+            .mount(&server)
+            .await;
+
+        let count = client(&server).fetch_contributors(&repository()).await;
+        // alice, bob and the anonymous carol
+        assert_eq!(count.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pages_are_capped() {
+        let server = MockServer::start().await;
+        let page: Vec<serde_json::Value> = (0..PER_PAGE)
+            .map(|i| commit(None, &format!("{i}@example.com")))
+            .collect();
+        Mock::given(method("GET"))
+            .and(path(COMMITS_PATH))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-hasmore", "true")
+                    .set_body_json(page),
+            )
+            .expect(MAX_PAGES as u64)
+            .mount(&server)
+            .await;
+
+        let count = client(&server).fetch_contributors(&repository()).await;
+        // The same authors on every page
+        assert_eq!(count.unwrap(), PER_PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_empty_repository() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(COMMITS_PATH))
+            .respond_with(
+                ResponseTemplate::new(409)
+                    .set_body_json(json!({ "message": "Git Repository is empty." })),
+            )
+            .mount(&server)
+            .await;
+
+        let count = client(&server).fetch_contributors(&repository()).await;
+        assert_eq!(count.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let count = client(&server).fetch_contributors(&repository()).await;
+        assert!(matches!(count, Err(GitHubError::NotFound(_))));
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use metrics::counter;
+use serde::de::IgnoredAny;
+
+use crate::rate_limit::header_u64;
+use crate::utils::check_status;
+use crate::{
+    FetchRequest, Forge, ForgeProvider, GitHubError, GitHubFetcher, HttpFetcher,
+    RateLimitedFetcher, RateLimiter, RateLimiterConfig, RepositoryId,
+};
+
+/// Largest page of the GitLab API
+const PER_PAGE: usize = 100;
+/// Number of items of every page, when the listing is not too large to count
+const TOTAL_HEADER: &str = "x-total";
+
+/// Counts the contributors of the projects of GitLab instances through their
+/// REST API, at `https://HOST/api/v4`.
+///
+/// GitLab tells the contributors apart by their name and email, and only counts
+/// the commits of the default branch.
+#[derive(Debug, Clone)]
+pub struct GitLabClient<F = RateLimitedFetcher> {
+    fetcher: F,
+    api_url: Option<String>,
+}
+
+impl Default for GitLabClient {
+    /// Requests paced by their own [`RateLimiter`], apart from the GitHub ones.
+    fn default() -> Self {
+        let limiter = Arc::new(RateLimiter::new(RateLimiterConfig::default()));
+        Self::with_fetcher(RateLimitedFetcher::new(HttpFetcher::default(), limiter))
+    }
+}
+
+impl<F: GitHubFetcher> GitLabClient<F> {
+    pub fn with_fetcher(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            api_url: None,
+        }
+    }
+
+    /// Overrides the root of the API of every instance, for example to target a mock server.
+    pub fn with_api_url(mut self, url: &str) -> Self {
+        self.api_url = Some(url.trim_end_matches('/').to_string());
+        self
+    }
+
+    fn api_url(&self, repository: &RepositoryId) -> String {
+        match &self.api_url {
+            Some(url) => url.clone(),
+            None => format!("https://{}/api/v4", repository.forge().host()),
+        }
+    }
+}
+
+impl<F: GitHubFetcher> ForgeProvider for GitLabClient<F> {
+    fn serves(&self, forge: &Forge) -> bool {
+        matches!(forge, Forge::GitLab(_))
+    }
+
+    async fn fetch_contributors(&self, repository: &RepositoryId) -> Result<usize, GitHubError> {
+        counter!("fetch", "type" => "contributor", "forge" => "gitlab").increment(1);
+        // Projects are identified by their URL encoded path
+        let project = format!("{}/{}", repository.owner(), repository.repo()).replace('/', "%2F");
+        let mut contributors = 0;
+        for page in 1.. {
+            let url = format!(
+                "{}/projects/{project}/repository/contributors?per_page={PER_PAGE}&page={page}",
+                self.api_url(repository)
+            );
+            let response = self.fetcher.fetch(&FetchRequest::get(&url)).await?;
+            let response = check_status(response, &url)?;
+            if let Some(total) = header_u64(&response.headers, TOTAL_HEADER) {
+                return Ok(total as usize);
+            }
+            let entries: Vec<IgnoredAny> = serde_json::from_str(&response.body)?;
+            contributors += entries.len();
+            if entries.len() < PER_PAGE {
+                break;
+            }
+        }
+        Ok(contributors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client(server: &MockServer) -> GitLabClient<HttpFetcher> {
+        GitLabClient::with_fetcher(HttpFetcher::default()).with_api_url(&server.uri())
+    }
+
+    fn project(url: &str) -> RepositoryId {
+        RepositoryId::try_from(url.to_string()).unwrap()
+    }
+
+    fn contributors(count: usize) -> serde_json::Value {
+        (0..count)
+            .map(|i| json!({ "name": format!("Contributor {i}"), "email": format!("{i}@example.com"), "commits": 1 }))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_total_header() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/projects/tspiteri%2Frug/repository/contributors"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-total", "42")
+                    .set_body_json(contributors(PER_PAGE)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = client(&server)
+            .fetch_contributors(&project("https://gitlab.com/tspiteri/rug"))
+            .await;
+        assert_eq!(count.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_nested_groups_pagination() {
+        let server = MockServer::start().await;
+        let contributors_path = "/projects/group%2Fsubgroup%2Fproject/repository/contributors";
+        Mock::given(method("GET"))
+            .and(path(contributors_path))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(contributors(PER_PAGE)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(contributors_path))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(contributors(3)))
+            .mount(&server)
+            .await;
+
+        let count = client(&server)
+            .fetch_contributors(&project("https://gitlab.com/group/subgroup/project"))
+            .await;
+        assert_eq!(count.unwrap(), PER_PAGE + 3);
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let count = client(&server)
+            .fetch_contributors(&project("https://gitlab.com/owner/missing"))
+            .await;
+        assert!(matches!(count, Err(GitHubError::NotFound(_))));
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::GitHubError;
+
+/// A repository of a dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphNode {
+    /// `owner/repo` path of the repository
+    pub path: String,
+    /// Contributors of the repository, when known
+    pub contributors: Option<usize>,
+    /// Number of edges from the root when first reached
+    pub depth: usize,
+}
+
+/// The dependency graph of a repository, as collected by a crawl.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DependencyGraph {
+    /// `owner/repo` path of the crawled repository
+    pub root: String,
+    pub nodes: Vec<GraphNode>,
+    /// `parent` and `child` paths of each dependency, `child` being a
+    /// dependency of `parent`
+    pub edges: Vec<(String, String)>,
+}
+
+/// Formats a [`DependencyGraph`] can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphFormat {
+    /// Graphviz
+    Dot,
+    /// XML format read by Gephi, yEd or NetworkX
+    GraphMl,
+    /// One `path,contributors,depth` line per repository
+    CsvNodes,
+    /// One `parent,child` line per dependency
+    CsvEdges,
+    #[default]
+    Json,
+}
+
+impl GraphFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "text/vnd.graphviz",
+            GraphFormat::GraphMl => "application/graphml+xml",
+            GraphFormat::CsvNodes | GraphFormat::CsvEdges => "text/csv",
+            GraphFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::CsvNodes | GraphFormat::CsvEdges => "csv",
+            GraphFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphFormat::Dot => write!(f, "dot"),
+            GraphFormat::GraphMl => write!(f, "graphml"),
+            GraphFormat::CsvNodes => write!(f, "csv-nodes"),
+            GraphFormat::CsvEdges => write!(f, "csv-edges"),
+            GraphFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = GitHubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "csv-nodes" => Ok(GraphFormat::CsvNodes),
+            "csv-edges" => Ok(GraphFormat::CsvEdges),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(GitHubError::InvalidGraphFormat(s.to_string())),
+        }
+    }
+}
+
+impl DependencyGraph {
+    /// Serializes the graph in the given format.
+    pub fn export(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::GraphMl => self.to_graphml(),
+            GraphFormat::CsvNodes => self.to_csv_nodes(),
+            GraphFormat::CsvEdges => self.to_csv_edges(),
+            // Only strings and integers, which can not fail
+            GraphFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n", dot_id(&self.root));
+        for node in &self.nodes {
+            let label = match node.contributors {
+                Some(contributors) => format!("{}\\n{contributors} contributors", node.path),
+                None => node.path.clone(),
+            };
+            let _ = writeln!(
+                dot,
+                "    {} [label={}, depth={}];",
+                dot_id(&node.path),
+                dot_id(&label),
+                node.depth
+            );
+        }
+        for (parent, child) in &self.edges {
+            let _ = writeln!(dot, "    {} -> {};", dot_id(parent), dot_id(child));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"contributors\" for=\"node\" attr.name=\"contributors\" attr.type=\"int\"/>\n",
+            "  <key id=\"depth\" for=\"node\" attr.name=\"depth\" attr.type=\"int\"/>\n",
+        ));
+        let _ = writeln!(
+            xml,
+            "  <graph id=\"{}\" edgedefault=\"directed\">",
+            xml_escape(&self.root)
+        );
+        for node in &self.nodes {
+            let _ = writeln!(xml, "    <node id=\"{}\">", xml_escape(&node.path));
+            if let Some(contributors) = node.contributors {
+                let _ = writeln!(
+                    xml,
+                    "      <data key=\"contributors\">{contributors}</data>"
+                );
+            }
+            let _ = writeln!(xml, "      <data key=\"depth\">{}</data>", node.depth);
+            xml.push_str("    </node>\n");
+        }
+        for (parent, child) in &self.edges {
+            let _ = writeln!(
+                xml,
+                "    <edge source=\"{}\" target=\"{}\"/>",
+                xml_escape(parent),
+                xml_escape(child)
+            );
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    fn to_csv_nodes(&self) -> String {
+        let mut csv = String::from("path,contributors,depth\n");
+        for node in &self.nodes {
+            let contributors = node
+                .contributors
+                .map(|contributors| contributors.to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                csv,
+                "{},{contributors},{}",
+                csv_field(&node.path),
+                node.depth
+            );
+        }
+        csv
+    }
+
+    fn to_csv_edges(&self) -> String {
+        let mut csv = String::from("parent,child\n");
+        for (parent, child) in &self.edges {
+            let _ = writeln!(csv, "{},{}", csv_field(parent), csv_field(child));
+        }
+        csv
+    }
+}
+
+/// Quoted DOT identifier.
+fn dot_id(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quotes the field when it contains a separator or a quote.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> DependencyGraph {
+        DependencyGraph {
+            root: "tokio-rs/tokio".to_string(),
+            nodes: vec![
+                GraphNode {
+                    path: "tokio-rs/tokio".to_string(),
+                    contributors: Some(842),
+                    depth: 0,
+                },
+                GraphNode {
+                    path: "rust-lang/libc".to_string(),
+                    contributors: None,
+                    depth: 1,
+                },
+            ],
+            edges: vec![("tokio-rs/tokio".to_string(), "rust-lang/libc".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("DOT".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+        assert_eq!(
+            "csv-edges".parse::<GraphFormat>().unwrap(),
+            GraphFormat::CsvEdges
+        );
+        assert!("svg".parse::<GraphFormat>().is_err());
+        for format in [
+            GraphFormat::Dot,
+            GraphFormat::GraphMl,
+            GraphFormat::CsvNodes,
+            GraphFormat::CsvEdges,
+            GraphFormat::Json,
+        ] {
+            assert_eq!(format.to_string().parse::<GraphFormat>().unwrap(), format);
+        }
+    }
+
+    #[test]
+    fn test_export_dot() {
+        assert_eq!(
+            graph().export(GraphFormat::Dot),
+            concat!(
+                "digraph \"tokio-rs/tokio\" {\n",
+                "    \"tokio-rs/tokio\" [label=\"tokio-rs/tokio\\n842 contributors\", depth=0];\n",
+                "    \"rust-lang/libc\" [label=\"rust-lang/libc\", depth=1];\n",
+                "    \"tokio-rs/tokio\" -> \"rust-lang/libc\";\n",
+                "}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_csv() {
+        assert_eq!(
+            graph().export(GraphFormat::CsvNodes),
+            "path,contributors,depth\ntokio-rs/tokio,842,0\nrust-lang/libc,,1\n"
+        );
+        assert_eq!(
+            graph().export(GraphFormat::CsvEdges),
+            "parent,child\ntokio-rs/tokio,rust-lang/libc\n"
+        );
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_export_graphml() {
+        let xml = graph().export(GraphFormat::GraphMl);
+        assert!(xml.contains("<node id=\"tokio-rs/tokio\">"));
+        assert!(xml.contains("<data key=\"contributors\">842</data>"));
+        assert!(xml.contains("<edge source=\"tokio-rs/tokio\" target=\"rust-lang/libc\"/>"));
+        assert_eq!(xml_escape("a&<\""), "a&amp;&lt;&quot;");
+    }
+
+    #[test]
+    fn test_export_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&graph().export(GraphFormat::Json)).unwrap();
+        assert_eq!(json["root"], "tokio-rs/tokio");
+        assert_eq!(json["nodes"][1]["contributors"], serde_json::Value::Null);
+        assert_eq!(json["edges"][0][1], "rust-lang/libc");
+    }
+}
@@ -1,8 +1,45 @@
+mod api;
+mod bots;
+mod client;
+mod contributors;
+mod dependency;
 mod errors;
+mod fetcher;
+mod forge;
+mod gitea;
+mod gitlab;
+mod graph;
 mod links;
 mod link_deps;
+mod manifests;
+mod rate_limit;
+mod registries;
+mod resolver;
+mod sbom;
+mod tokens;
 pub(crate) mod utils;
 
+pub use bots::BotClassifier;
+pub use client::{FetchMode, GITHUB_API_URL, GITHUB_RAW_URL, GITHUB_WEB_URL, GitHubClient};
+pub use contributors::{Concentration, Contribution, Contributor};
+pub use dependency::{Dependency, Ecosystem, Unresolved};
 pub use errors::GitHubError;
+pub use fetcher::{
+    FetchRequest, FetchResponse, GitHubFetcher, HttpFetcher, RecordingFetcher, ReplayFetcher,
+};
+pub use forge::{Forge, ForgeProvider, Forges, RepositoryId};
+pub use gitea::GiteaClient;
+pub use gitlab::GitLabClient;
+pub use graph::{DependencyGraph, GraphFormat, GraphNode};
 pub use links::GitHubLink;
+pub use manifests::Manifest;
+pub use rate_limit::{RateLimitedFetcher, RateLimiter, RateLimiterConfig};
+pub use registries::{PackageRegistry, Registries};
+pub use resolver::ManifestResolver;
+pub use sbom::{Sbom, SbomFormat};
 pub use link_deps::GitHubLinkDependencies;
+pub use tokens::{AuthenticatedFetcher, GitHubToken, TokenPool};
+
+/// The fetcher used by default: requests sent over the network, paced by the
+/// process-wide [`RateLimiter`] and authenticated with the tokens of a [`TokenPool`].
+pub type DefaultFetcher = AuthenticatedFetcher<RateLimitedFetcher<HttpFetcher>>;
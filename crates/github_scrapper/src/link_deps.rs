@@ -1,68 +1,89 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
 use lazy_static::lazy_static;
 use metrics::counter;
-use scraper::{Html, Selector};
-use tracing::error;
+use scraper::{ElementRef, Html, Selector};
+use tracing::{error, warn};
 
-use crate::utils::fetch_page;
-use crate::{GitHubError, GitHubLink};
+use crate::client::{DEFAULT_CLIENT, should_fallback};
+use crate::{
+    DefaultFetcher, Dependency, FetchMode, GitHubClient, GitHubError, GitHubFetcher, GitHubLink,
+    RepositoryId, api,
+};
 
 lazy_static! {
+    static ref ROW_SELECTOR: Selector =
+        Selector::parse(r#"[data-test-selector="dg-repo-pkg-dependency"]"#).unwrap();
     // "dependendency" is a typo on GitHub's end, its actually written like this in the HTML.
     static ref DEPENDENCY_SELECTOR: Selector =
         Selector::parse(r#"a[data-hovercard-type="dependendency_graph_package"]"#).unwrap();
+    // Packages hosted elsewhere link to their repository without a hovercard
+    static ref LINK_SELECTOR: Selector = Selector::parse("a[href]").unwrap();
+    static ref PACKAGE_NAME_SELECTOR: Selector =
+        Selector::parse(r#"[data-test-selector="dg-package-name"]"#).unwrap();
+    static ref PACKAGE_MANAGER_SELECTOR: Selector =
+        Selector::parse(r#"[data-test-selector="dg-package-manager"]"#).unwrap();
+    static ref REQUIREMENTS_SELECTOR: Selector =
+        Selector::parse(r#"[data-test-selector="dg-package-requirements"]"#).unwrap();
+    static ref MANIFEST_SELECTOR: Selector =
+        Selector::parse(r#"[data-test-selector="dg-manifest-path"]"#).unwrap();
     static ref PAGINATION_SELECTOR: Selector = Selector::parse(r#"em[class="current"]"#).unwrap();
 }
 
 const MAX_ERRORS: u8 = 5;
 
 /// An iterator over a [`GitHubLink`]'s dependencies.
-pub struct GitHubLinkDependencies {
+pub struct GitHubLinkDependencies<F = DefaultFetcher> {
     link: Option<GitHubLink>,
-    index: usize,
+    client: Arc<GitHubClient<F>>,
+    mode: FetchMode,
     page: usize,
-    current_html: Option<Html>,
     number_of_pages: Option<usize>,
-    number_of_items: Option<usize>,
-    precomputed: Option<Vec<GitHubLink>>,
-    precomputed_idx: usize,
+    pending: VecDeque<Result<Dependency, GitHubError>>,
+    finished: bool,
+    precomputed: bool,
     errors: u8,
 }
 
-// Don't waste your time with Arc and RwLock, it does not fix the Websocket requirements
-unsafe impl Send for GitHubLinkDependencies {}
-unsafe impl Sync for GitHubLinkDependencies {}
-
 impl GitHubLinkDependencies {
     pub fn new(link: GitHubLink) -> Self {
+        Self::with_client(link, DEFAULT_CLIENT.clone())
+    }
+
+    pub fn from_precomputed(dependencies: Vec<Dependency>) -> Self {
         Self {
-            link: Some(link),
-            index: 0,
+            link: None,
+            client: DEFAULT_CLIENT.clone(),
+            mode: FetchMode::default(),
             page: 0,
             number_of_pages: None,
-            current_html: None,
-            number_of_items: None,
-            precomputed: None,
-            precomputed_idx: 0,
+            pending: dependencies.into_iter().map(Ok).collect(),
+            finished: true,
+            precomputed: true,
             errors: 0,
         }
     }
+}
 
-    pub fn from_precomputed(links: Vec<GitHubLink>) -> Self {
+impl<F: GitHubFetcher> GitHubLinkDependencies<F> {
+    /// Iterates over the dependencies of the link, fetched by the given client.
+    pub fn with_client(link: GitHubLink, client: Arc<GitHubClient<F>>) -> Self {
         Self {
-            link: None,
-            index: 0,
+            link: Some(link),
+            mode: client.mode(),
+            client,
             page: 0,
             number_of_pages: None,
-            current_html: None,
-            number_of_items: None,
-            precomputed: Some(links),
-            precomputed_idx: 0,
+            pending: VecDeque::new(),
+            finished: false,
+            precomputed: false,
             errors: 0,
         }
     }
 
     pub fn is_precomputed(&self) -> bool {
-        self.precomputed.is_some()
+        self.precomputed
     }
 
     /// Iterates over the next dependency. Can be used as follows:
@@ -74,101 +95,165 @@ impl GitHubLinkDependencies {
     ///    let link = GitHubLink::try_from("https://github.com/tokio-rs/tokio".to_string()).unwrap();
     ///    let mut dep_iterator = link.dependencies();
     ///    while let Some(dep) = dep_iterator.next().await {
-    ///        if let Ok(l) = dep {
-    ///            // Do something with l
+    ///        if let Ok(dependency) = dep {
+    ///            // Do something with dependency.repository
     ///        } else {
     ///            eprintln!("Dependency fetching error: {}", dep.unwrap_err());
     ///        }
     ///    }
     ///}
     ///```
-    pub async fn next(&mut self) -> Option<Result<GitHubLink, GitHubError>> {
-        if let Some(precomputed) = &self.precomputed {
-            if self.precomputed_idx >= precomputed.len() {
-                return None;
+    pub async fn next(&mut self) -> Option<Result<Dependency, GitHubError>> {
+        loop {
+            if let Some(output) = self.pending.pop_front() {
+                return Some(output);
             }
-            let out = precomputed.get(self.precomputed_idx).unwrap();
-            self.precomputed_idx += 1;
-            return Some(Ok(out.clone()));
-        }
-        if self.errors > MAX_ERRORS {
-            error!(
-                "Got {} errors in a row, stopping fetch for {}",
-                &self.errors,
-                &self
-                    .link
-                    .as_ref()
-                    .map(|l| l.path())
-                    .unwrap_or("<Precomputed>".to_string())
-            );
-            return None;
-        }
-
-        if self.current_html.is_none() {
-            if self.number_of_pages.is_some() && self.page >= self.number_of_pages.unwrap() {
+            if self.finished {
                 return None;
             }
-            self.page += 1;
-            let fetched_html = self.fetch_page(self.page).await;
-            if let Ok(current_page) = fetched_html {
-                self.current_html = Some(current_page);
-            } else {
-                self.errors += 1;
-                counter!("errors").increment(1);
-                return Some(Err(fetched_html.unwrap_err()));
+            if self.errors > MAX_ERRORS {
+                error!(
+                    "Got {} errors in a row, stopping fetch for {}",
+                    &self.errors,
+                    &self
+                        .link
+                        .as_ref()
+                        .map(|l| l.path())
+                        .unwrap_or("<Precomputed>".to_string())
+                );
+                return None;
             }
-        }
 
-        let current_page = self.current_html.as_ref().unwrap();
-
-        if self.number_of_pages.is_none() {
-            if let Some(pagination_component) = current_page.select(&PAGINATION_SELECTOR).next() {
-                self.number_of_pages = pagination_component
-                    .attr("data-total-pages")
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .or(Some(1));
-            } else {
-                self.number_of_pages = Some(1);
+            let fetched = match self.mode {
+                FetchMode::Html => self.fetch_next_page().await,
+                FetchMode::Api | FetchMode::Auto => self.fetch_from_api().await,
+            };
+            match fetched {
+                Ok(()) => self.errors = 0,
+                // The rate limiter waits for the limit to be lifted before the next try
+                Err(GitHubError::RateLimited { url, retry_after }) => {
+                    self.errors += 1;
+                    counter!("errors").increment(1);
+                    if self.errors > MAX_ERRORS {
+                        // Such as an exhausted token pool, which would be waited on forever
+                        self.finished = true;
+                        return Some(Err(GitHubError::RateLimited { url, retry_after }));
+                    }
+                    warn!("Still rate limited on {url}, retrying in {retry_after:?}");
+                }
+                Err(e) => {
+                    if matches!(e, GitHubError::NotFound(_)) {
+                        self.finished = true;
+                    }
+                    self.errors += 1;
+                    counter!("errors").increment(1);
+                    return Some(Err(e));
+                }
             }
         }
+    }
 
-        let mut elements = current_page.select(&DEPENDENCY_SELECTOR);
+    /// Fills the pending dependencies from the next dependency graph HTML page.
+    async fn fetch_next_page(&mut self) -> Result<(), GitHubError> {
+        counter!("fetch", "type" => "dependencies").increment(1);
+        let page = self.page + 1;
+        let path = format!(
+            "/{}/network/dependencies?page={}",
+            self.link.as_ref().unwrap().path(),
+            page
+        );
+        let html = self.client.fetch_html(&path).await?;
+        self.page = page;
 
-        if self.number_of_items.is_none() {
-            self.number_of_items = Some(elements.clone().count());
+        if self.number_of_pages.is_none() {
+            self.number_of_pages = Some(number_of_pages(&html));
         }
+        self.pending.extend(dependencies_from_html(&html));
 
-        let current_element = elements.nth(self.index)?;
-        let repo_path = current_element.attr("href").unwrap();
-        let repo_path = format!("https://github.com{}", repo_path.trim());
-        let output = GitHubLink::try_from(repo_path);
-
-        self.index += 1;
-
-        if self.number_of_items.unwrap() <= self.index {
-            self.number_of_items = None;
-            self.current_html = None;
-            self.index = 0;
+        if self.page >= self.number_of_pages.unwrap() {
+            self.finished = true;
         }
-
-        Some(output)
+        Ok(())
     }
 
-    async fn fetch_page(&self, page: usize) -> Result<Html, GitHubError> {
+    /// Fills the pending dependencies from the dependency graph API, falling back
+    /// to the HTML pages when allowed.
+    async fn fetch_from_api(&mut self) -> Result<(), GitHubError> {
         counter!("fetch", "type" => "dependencies").increment(1);
-        let link = format!(
-            "{}/network/dependencies?page={}",
-            self.link.clone().unwrap().link(),
-            page
-        );
-        fetch_page(&link).await
+        let link = self.link.as_ref().unwrap();
+        match api::fetch_dependencies(&self.client, link).await {
+            Ok(dependencies) => {
+                self.pending.extend(dependencies);
+                self.finished = true;
+                Ok(())
+            }
+            Err(e) if self.mode == FetchMode::Auto && should_fallback(&e) => {
+                warn!("API dependencies fetching failed for {link}, scraping HTML: {e}");
+                self.mode = FetchMode::Html;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
     }
 }
 
+fn number_of_pages(html: &Html) -> usize {
+    html.select(&PAGINATION_SELECTOR)
+        .next()
+        .and_then(|pagination_component| pagination_component.attr("data-total-pages"))
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(1)
+}
+
+#[allow(clippy::result_large_err)]
+fn dependencies_from_html(html: &Html) -> Vec<Result<Dependency, GitHubError>> {
+    html.select(&ROW_SELECTOR)
+        .filter_map(dependency_from_row)
+        .collect()
+}
+
+/// The dependency of a row of the page, linked to its repository when it has
+/// one. Rows with neither a link nor a package name are skipped.
+#[allow(clippy::result_large_err)]
+fn dependency_from_row(row: ElementRef) -> Option<Result<Dependency, GitHubError>> {
+    let text = |selector: &Selector| {
+        row.select(selector)
+            .next()
+            .map(|element| element.text().collect::<String>().trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+    let url = match row
+        .select(&DEPENDENCY_SELECTOR)
+        .next()
+        .and_then(|element| element.attr("href"))
+    {
+        Some(repo_path) => Some(format!("https://github.com{}", repo_path.trim())),
+        None => row
+            .select(&LINK_SELECTOR)
+            .filter_map(|element| element.attr("href"))
+            .map(str::trim)
+            .find(|href| href.starts_with("https://") || href.starts_with("http://"))
+            .map(str::to_string),
+    };
+    let repository = url
+        .as_ref()
+        .and_then(|url| RepositoryId::try_from(url.clone()).ok());
+    let package_name = text(&PACKAGE_NAME_SELECTOR)
+        .or_else(|| repository.as_ref().map(|id| id.repo().to_string()))?;
+    Some(Ok(Dependency {
+        ecosystem: text(&PACKAGE_MANAGER_SELECTOR).and_then(|name| name.parse().ok()),
+        package_name,
+        requirements: text(&REQUIREMENTS_SELECTOR),
+        manifest: text(&MANIFEST_SELECTOR),
+        repository,
+        url,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::GitHubLink;
+    use crate::{Ecosystem, GitHubLink, Unresolved};
 
     #[tokio::test]
     async fn test_precomputed() {
@@ -181,13 +266,136 @@ mod tests {
             GitHubLink::try_from("https://github.com/rust-lang/rustfmt".to_string()).unwrap(),
         ];
 
-        let mut iterator = GitHubLinkDependencies::from_precomputed(links.clone());
+        let dependencies: Vec<Dependency> = links.into_iter().map(Dependency::from).collect();
+        let mut iterator = GitHubLinkDependencies::from_precomputed(dependencies.clone());
 
         let mut output = vec![];
-        while let Some(link) = iterator.next().await {
-            output.push(link.unwrap())
+        while let Some(dependency) = iterator.next().await {
+            output.push(dependency.unwrap())
         }
 
-        assert_eq!(links, output);
+        assert_eq!(dependencies, output);
+    }
+
+    async fn fixtures_dependencies(url: &str) -> Vec<Result<Dependency, GitHubError>> {
+        let link = GitHubLink::try_from(url.to_string()).unwrap();
+        let mut iterator = crate::utils::fixtures_client().dependencies(&link);
+        let mut output = vec![];
+        while let Some(dep) = iterator.next().await {
+            output.push(dep);
+        }
+        output
+    }
+
+    #[tokio::test]
+    async fn test_fixtures_single_page() {
+        let dependencies: Vec<Dependency> =
+            fixtures_dependencies("https://github.com/tokio-rs/loom")
+                .await
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+        let paths: Vec<Option<String>> = dependencies
+            .iter()
+            .map(|dependency| dependency.repository.as_ref().map(RepositoryId::path))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                Some("rust-lang/cfg-if".to_string()),
+                Some("tokio-rs/tracing".to_string()),
+                Some("rust-random/rand".to_string()),
+                None,
+                Some("gitlab.com/tspiteri/rug".to_string()),
+            ]
+        );
+        assert_eq!(
+            dependencies[0],
+            Dependency {
+                ecosystem: Some(Ecosystem::Cargo),
+                package_name: "cfg-if".to_string(),
+                requirements: Some("1.0.0".to_string()),
+                manifest: Some("Cargo.lock".to_string()),
+                repository: Some(RepositoryId::from_path("rust-lang/cfg-if").unwrap()),
+                url: Some("https://github.com/rust-lang/cfg-if".to_string()),
+            }
+        );
+        assert_eq!(dependencies[3].package_name, "private-macros");
+        assert_eq!(dependencies[3].unresolved(), Some(Unresolved::NoLink));
+        // Hosted on GitLab, the package resolves all the same
+        assert_eq!(dependencies[4].package_name, "rug");
+        assert_eq!(dependencies[4].unresolved(), None);
+        assert_eq!(
+            dependencies[4].repository_id().unwrap().path(),
+            "gitlab.com/tspiteri/rug"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixtures_paginated() {
+        let paths: Vec<String> = fixtures_dependencies("https://github.com/tokio-rs/tokio")
+            .await
+            .into_iter()
+            .map(|dep| dep.unwrap().repository.unwrap().path())
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "tokio-rs/bytes",
+                "tokio-rs/mio",
+                "rust-lang/libc",
+                "taiki-e/pin-project-lite",
+                "tokio-rs/tracing",
+                "rust-lang/socket2",
+                "dtolnay/syn",
+            ]
+        );
+    }
+
+    /// Rate limited whatever the request, counting the requests.
+    #[derive(Default)]
+    struct ExhaustedFetcher(std::sync::atomic::AtomicUsize);
+
+    impl GitHubFetcher for ExhaustedFetcher {
+        async fn fetch(
+            &self,
+            request: &crate::FetchRequest,
+        ) -> Result<crate::FetchResponse, GitHubError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(GitHubError::RateLimited {
+                url: request.url.clone(),
+                retry_after: std::time::Duration::from_secs(60),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_when_still_rate_limited() {
+        let client = Arc::new(GitHubClient::with_fetcher(
+            ExhaustedFetcher::default(),
+            FetchMode::Html,
+        ));
+        let link = GitHubLink::try_from("https://github.com/tokio-rs/tokio".to_string()).unwrap();
+        let mut iterator = client.dependencies(&link);
+
+        assert!(matches!(
+            iterator.next().await,
+            Some(Err(GitHubError::RateLimited { .. }))
+        ));
+        assert!(iterator.next().await.is_none());
+        assert_eq!(
+            client.fetcher().0.load(std::sync::atomic::Ordering::SeqCst),
+            MAX_ERRORS as usize + 1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixtures_not_found() {
+        let output = fixtures_dependencies("https://github.com/OWNER/MISSING").await;
+
+        assert_eq!(output.len(), 1);
+        assert!(matches!(output[0], Err(GitHubError::NotFound(_))));
     }
 }
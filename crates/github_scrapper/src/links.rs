@@ -1,10 +1,9 @@
 use lazy_static::lazy_static;
-use metrics::counter;
 use regex::Regex;
 use scraper::{Html, Selector};
 use tracing::debug;
 
-use crate::utils::fetch_page;
+use crate::client::DEFAULT_CLIENT;
 use crate::{GitHubLinkDependencies, errors::GitHubError};
 
 lazy_static! {
@@ -74,24 +73,22 @@ impl GitHubLink {
 
     /// The number of contributors displayed on the right side
     /// of the main page.
+    ///
+    /// Uses the default [`GitHubClient`](crate::GitHubClient), which scrapes the HTML pages.
     pub async fn fetch_contributors(&self) -> Result<usize, GitHubError> {
-        counter!("fetch", "type" => "contributor").increment(1);
-        let html = self.fetch_main_page().await?;
-        self.get_contributors_from_html(&html)
+        DEFAULT_CLIENT.fetch_contributors(self).await
     }
 
     /// The dependencies of the repo, found in the
     /// "Insight -> Dependency Graph" page.
+    ///
+    /// Uses the default [`GitHubClient`](crate::GitHubClient), which scrapes the HTML pages.
     pub fn dependencies(&self) -> GitHubLinkDependencies {
-        GitHubLinkDependencies::new(self.clone())
-    }
-
-    async fn fetch_main_page(&self) -> Result<Html, GitHubError> {
-        fetch_page(self.link()).await
+        DEFAULT_CLIENT.dependencies(self)
     }
 
     #[allow(clippy::result_large_err)]
-    fn get_contributors_from_html(&self, html: &Html) -> Result<usize, GitHubError> {
+    pub(crate) fn get_contributors_from_html(&self, html: &Html) -> Result<usize, GitHubError> {
         let a_selector =
             Selector::parse(format!(r#"a[href="/{}/graphs/contributors"]"#, self.path()).as_str())
                 .unwrap();
@@ -179,4 +176,37 @@ mod tests {
         let link = GitHubLink::try_from("https://github.com/OWNER/REPO".to_string()).unwrap();
         assert_eq!(format!("{link}"), "OWNER/REPO");
     }
+
+    #[tokio::test]
+    async fn test_fixtures_contributors() {
+        let client = crate::utils::fixtures_client();
+        let contributors = |url: &str| {
+            let link = GitHubLink::try_from(url.to_string()).unwrap();
+            let client = client.clone();
+            async move { client.fetch_contributors(&link).await }
+        };
+
+        assert_eq!(
+            contributors("https://github.com/tokio-rs/loom")
+                .await
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            contributors("https://github.com/rust-lang/rust")
+                .await
+                .unwrap(),
+            5000
+        );
+        assert_eq!(
+            contributors("https://github.com/DefinitelyTyped/DefinitelyTyped")
+                .await
+                .unwrap(),
+            20154
+        );
+        assert!(matches!(
+            contributors("https://github.com/OWNER/MISSING").await,
+            Err(GitHubError::NotFound(_))
+        ));
+    }
 }
@@ -1,63 +1,425 @@
-use async_recursion::async_recursion;
-use github_scrapper::GitHubLink;
+use github_scrapper::{
+    AuthenticatedFetcher, BotClassifier, Concentration, Dependency, DependencyGraph, FetchMode,
+    Forge, Forges, GitHubClient, GitHubFetcher, GitHubLink, GitHubToken, GraphFormat, GraphNode,
+    ManifestResolver, RateLimitedFetcher, RecordingFetcher, RepositoryId, Sbom, TokenPool,
+};
+use std::collections::{HashMap, VecDeque};
+use std::process::exit;
 use std::sync::Arc;
-use std::{collections::HashMap, process::exit};
 use tokio::sync::RwLock;
-use tokio::time::{Duration, sleep};
 use tracing::{Level, error, info};
 
+/// Limits of the crawl, unlimited when not set.
+#[derive(Debug, Clone, Copy, Default)]
+struct Limits {
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Crawl {
+    dependencies: HashMap<String, usize>,
+    /// Repositories in the order they were reached, and every dependency
+    /// between them
+    graph: DependencyGraph,
+    /// Identities of the contributors of all the repositories, and whether
+    /// they are bots, when asked for
+    identities: Option<HashMap<String, bool>>,
+    /// Whether the crawl stopped on its limits before the end of the graph
+    truncated: bool,
+}
+
+/// What the crawl starts from
+enum Target {
+    Repository(GitHubLink),
+    /// Path of an SPDX or CycloneDX JSON file, whose packages are crawled
+    Sbom(String),
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {program} <url|sbom <file>> [html|api|auto] [--max-depth <depth>] \
+         [--max-nodes <count>] [--output-format <dot|graphml|csv-nodes|csv-edges|json>] \
+         [--identities] [--manifests]"
+    );
+    exit(1);
+}
+
 #[tokio::main]
 async fn main() {
+    // The logs are kept apart from the exported graph
     tracing_subscriber::fmt::fmt()
         .with_max_level(Level::INFO)
+        .with_writer(std::io::stderr)
         .init();
-    let args: Vec<_> = std::env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: {} <url>", args[0]);
-        exit(1);
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_default();
+    let mut positional = vec![];
+    let mut limits = Limits::default();
+    let mut format = None;
+    let mut identities = false;
+    let mut manifests = false;
+    while let Some(arg) = args.next() {
+        let limit = match arg.as_str() {
+            "--identities" => {
+                identities = true;
+                continue;
+            }
+            "--manifests" => {
+                manifests = true;
+                continue;
+            }
+            "--output-format" => {
+                match args
+                    .next()
+                    .and_then(|value| value.parse::<GraphFormat>().ok())
+                {
+                    Some(value) => format = Some(value),
+                    None => usage(&program),
+                }
+                continue;
+            }
+            "--max-depth" => &mut limits.max_depth,
+            "--max-nodes" => &mut limits.max_nodes,
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        };
+        match args.next().and_then(|value| value.parse().ok()) {
+            Some(value) => *limit = Some(value),
+            None => usage(&program),
+        }
+    }
+    let target = match positional.first().map(String::as_str) {
+        Some("sbom") if positional.len() > 1 => Target::Sbom(positional.remove(1)),
+        Some(_) => Target::Repository(GitHubLink::try_from(positional[0].clone()).unwrap()),
+        None => usage(&program),
+    };
+    if positional.len() > 2 {
+        usage(&program);
     }
 
-    let link = GitHubLink::try_from(args[1].clone()).unwrap();
-    info!("Contributors: {:?}", &link.fetch_contributors().await);
-    let dependencies = Arc::new(RwLock::new(HashMap::new()));
-    let dependencies = recursive_dependencies(link, dependencies).await;
+    let mode = positional
+        .get(1)
+        .map(|mode| mode.parse::<FetchMode>().unwrap())
+        .unwrap_or_default();
+    // Reads the dependencies from the manifests instead of the dependency graph
+    let resolver = manifests.then(|| Arc::new(ManifestResolver::default()));
+    // Comma separated tokens authenticating the API requests
+    let tokens: Vec<GitHubToken> = std::env::var("GITHUB_TOKENS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|token| token.parse().ok())
+        .collect();
+    // Comma separated self-hosted instances whose repositories are counted
+    let hosts = |variable: &str| -> Vec<String> {
+        std::env::var(variable)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    Forge::allow(
+        hosts("GITLAB_HOSTS")
+            .into_iter()
+            .map(Forge::GitLab)
+            .chain(hosts("GITEA_HOSTS").into_iter().map(Forge::Gitea)),
+    );
+
+    // Saves every response so it can be replayed in the tests
+    if let Ok(directory) = std::env::var("RECORD_FIXTURES") {
+        let recorder = RecordingFetcher::new(RateLimitedFetcher::default(), directory);
+        let fetcher = AuthenticatedFetcher::new(recorder, TokenPool::new(tokens));
+        let client = Arc::new(GitHubClient::with_fetcher(fetcher, mode));
+        run(client, target, limits, format, identities, resolver).await;
+    } else {
+        run(
+            Arc::new(GitHubClient::with_tokens(mode, tokens)),
+            target,
+            limits,
+            format,
+            identities,
+            resolver,
+        )
+        .await;
+    }
+}
+
+async fn run<F: GitHubFetcher>(
+    client: Arc<GitHubClient<F>>,
+    target: Target,
+    limits: Limits,
+    format: Option<GraphFormat>,
+    identities: bool,
+    resolver: Option<Arc<ManifestResolver>>,
+) {
+    let forges = Arc::new(Forges::new(client));
+    let crawl = match target {
+        Target::Repository(link) => {
+            info!(
+                "Contributors: {:?}",
+                &forges.github().fetch_contributors(&link).await
+            );
+            let crawl = new_crawl(link.path(), identities);
+            add_repository(&forges, &RepositoryId::from(&link), 0, &crawl).await;
+            let queue = VecDeque::from([(link, 0)]);
+            crawl_dependencies(forges, resolver, queue, limits, crawl).await
+        }
+        Target::Sbom(path) => {
+            let crawl = new_crawl(path.clone(), identities);
+            sbom_dependencies(forges, resolver, &path, limits, crawl).await
+        }
+    };
+    let crawl = crawl.read().await;
 
-    info!("Dependencies: {:?}", dependencies.read().await);
+    info!("Dependencies: {:?}", crawl.dependencies);
+    info!("Total Dependencies: {:?}", crawl.dependencies.keys().len());
     info!(
-        "Total Dependencies: {:?}",
-        dependencies.read().await.keys().len()
+        "Total Contributors: {:?}",
+        crawl.dependencies.values().sum::<usize>()
     );
+    if let Some(identities) = &crawl.identities {
+        let bots = identities.values().filter(|bot| **bot).count();
+        info!("Unique Contributors: {}", identities.len() - bots);
+        info!("Unique Bots: {bots}");
+    }
+    info!("Truncated: {}", crawl.truncated);
+    if let Some(format) = format {
+        println!("{}", crawl.graph.export(format));
+    }
+}
+
+fn new_crawl(root: String, identities: bool) -> Arc<RwLock<Crawl>> {
+    Arc::new(RwLock::new(Crawl {
+        graph: DependencyGraph {
+            root,
+            ..Default::default()
+        },
+        identities: identities.then(HashMap::new),
+        ..Default::default()
+    }))
+}
+
+/// Crawls the repositories of the packages of an SBOM file, the root of the
+/// graph being the file itself, without contributors.
+async fn sbom_dependencies<F: GitHubFetcher>(
+    forges: Arc<Forges<F>>,
+    resolver: Option<Arc<ManifestResolver>>,
+    path: &str,
+    limits: Limits,
+    crawl: Arc<RwLock<Crawl>>,
+) -> Arc<RwLock<Crawl>> {
+    let sbom = match std::fs::read_to_string(path) {
+        Ok(content) => Sbom::parse(&content),
+        Err(e) => {
+            error!("Could not read {path}: {e}");
+            exit(1);
+        }
+    };
+    let sbom = sbom.unwrap_or_else(|e| {
+        error!("{e}");
+        exit(1);
+    });
     info!(
-        "Total Contributors: {:?}",
-        dependencies.read().await.values().sum::<usize>()
+        "{} packages in the {} SBOM of {}",
+        sbom.packages.len(),
+        sbom.format,
+        sbom.name.as_deref().unwrap_or(path)
     );
+    crawl.write().await.graph.nodes.push(GraphNode {
+        path: path.to_string(),
+        contributors: None,
+        depth: 0,
+    });
+    if limits.max_depth == Some(0) {
+        crawl.write().await.truncated = true;
+        return crawl;
+    }
+    // The packages are resolved through their registries even without `--manifests`
+    let registries = resolver.clone().unwrap_or_default();
+    let mut queue = VecDeque::new();
+    for package in sbom.packages {
+        let dependency = registries.resolve(package).await;
+        if !add_dependency(&forges, path, dependency, 1, limits, &crawl, &mut queue).await {
+            return crawl;
+        }
+    }
+    crawl_dependencies(forges, resolver, queue, limits, crawl).await
+}
+
+/// Crawls the dependency graph breadth first from the queued repositories,
+/// already added to the crawl, so that each repository is reached at its
+/// shortest depth.
+async fn crawl_dependencies<F: GitHubFetcher>(
+    forges: Arc<Forges<F>>,
+    resolver: Option<Arc<ManifestResolver>>,
+    mut queue: VecDeque<(GitHubLink, usize)>,
+    limits: Limits,
+    crawl: Arc<RwLock<Crawl>>,
+) -> Arc<RwLock<Crawl>> {
+    while let Some((link, depth)) = queue.pop_front() {
+        if !explore_dependencies(&forges, &resolver, &link, depth, limits, &crawl, &mut queue).await
+        {
+            break;
+        }
+    }
+    crawl
+}
+
+/// Adds the dependencies of a repository to the crawl, queuing the ones to
+/// explore. `false` when the crawl reached its maximum number of
+/// repositories.
+async fn explore_dependencies<F: GitHubFetcher>(
+    forges: &Forges<F>,
+    resolver: &Option<Arc<ManifestResolver>>,
+    link: &GitHubLink,
+    depth: usize,
+    limits: Limits,
+    crawl: &RwLock<Crawl>,
+    queue: &mut VecDeque<(GitHubLink, usize)>,
+) -> bool {
+    if limits.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+        crawl.write().await.truncated = true;
+        return true;
+    }
+    let client = forges.github();
+    let mut manifest_dependencies = match resolver {
+        Some(resolver) => match resolver.dependencies(client, link).await {
+            Ok(dependencies) => Some(dependencies.into_iter()),
+            Err(e) => {
+                error!("Error reading the manifests of {link}: {}", e);
+                Some(vec![].into_iter())
+            }
+        },
+        None => None,
+    };
+    let mut dep_iterator = client.dependencies(link);
+    loop {
+        let dep = match manifest_dependencies.as_mut() {
+            Some(dependencies) => dependencies.next().map(Ok),
+            None => dep_iterator.next().await,
+        };
+        let Some(dep) = dep else {
+            break;
+        };
+        match dep {
+            Ok(dependency) => {
+                let parent = link.path();
+                if !add_dependency(forges, &parent, dependency, depth + 1, limits, crawl, queue)
+                    .await
+                {
+                    return false;
+                }
+            }
+            Err(e) => error!("Dependency fetching error: {:?}", e),
+        }
+    }
+    true
+}
+
+/// Adds the repository of a dependency of `parent` to the crawl unless it
+/// already was, queuing it to be explored when its dependencies are known.
+/// `false` when the crawl reached its maximum number of repositories.
+async fn add_dependency<F: GitHubFetcher>(
+    forges: &Forges<F>,
+    parent: &str,
+    dependency: Dependency,
+    depth: usize,
+    limits: Limits,
+    crawl: &RwLock<Crawl>,
+    queue: &mut VecDeque<(GitHubLink, usize)>,
+) -> bool {
+    let Some(id) = dependency.repository_id() else {
+        info!(
+            "{} of {parent} does not resolve to a repository: {}",
+            dependency.package_name,
+            dependency.unresolved().unwrap()
+        );
+        return true;
+    };
+    let edge = (parent.to_string(), id.path());
+    if crawl.read().await.dependencies.contains_key(&id.path()) {
+        // Several manifests can depend on the same repository
+        let mut crawl = crawl.write().await;
+        if !crawl.graph.edges.contains(&edge) {
+            crawl.graph.edges.push(edge);
+        }
+        return true;
+    }
+    let count = crawl.read().await.dependencies.len();
+    if limits.max_nodes.is_some_and(|max_nodes| count >= max_nodes) {
+        crawl.write().await.truncated = true;
+        return false;
+    }
+    crawl.write().await.graph.edges.push(edge);
+    add_repository(forges, &id, depth, crawl).await;
+    // Only the dependencies of the GitHub repositories are known
+    if let Some(link) = id.github() {
+        queue.push_back((link, depth));
+    }
+    true
 }
 
-#[async_recursion(?Send)]
-async fn recursive_dependencies(
-    link: GitHubLink,
-    dependencies: Arc<RwLock<HashMap<String, usize>>>,
-) -> Arc<RwLock<HashMap<String, usize>>> {
-    let contributors = match link.fetch_contributors().await {
+/// Adds a repository reached at `depth` to the crawl with its contributors,
+/// and their identities when asked for.
+async fn add_repository<F: GitHubFetcher>(
+    forges: &Forges<F>,
+    id: &RepositoryId,
+    depth: usize,
+    crawl: &RwLock<Crawl>,
+) {
+    let contributors = match forges.fetch_contributors(id).await {
         Ok(c) => c,
         Err(e) => {
-            error!("Error fetching contributors: {}", e);
+            error!("Error fetching contributors of {id}: {}", e);
             1
         }
     };
-    dependencies.write().await.insert(link.path(), contributors);
-    let mut dep_iterator = link.dependencies();
-    while let Some(dep) = dep_iterator.next().await {
-        if let Ok(l) = dep {
-            if !dependencies.read().await.contains_key(&l.path()) {
-                // Avoid flooding GitHub with requests
-                sleep(Duration::from_secs(1)).await;
-                recursive_dependencies(l, dependencies.clone()).await;
+    {
+        let mut crawl = crawl.write().await;
+        crawl.dependencies.insert(id.path(), contributors);
+        crawl.graph.nodes.push(GraphNode {
+            path: id.path(),
+            contributors: Some(contributors),
+            depth,
+        });
+    }
+    let Some(link) = id.github() else {
+        return;
+    };
+    if crawl.read().await.identities.is_none() {
+        return;
+    }
+    match forges.github().fetch_contributions(&link).await {
+        Ok(contributions) => {
+            let bots = BotClassifier::default();
+            let classified: Vec<_> = contributions
+                .iter()
+                .map(|contribution| (contribution, bots.is_bot(&contribution.contributor)))
+                .collect();
+            if let Some(concentration) = Concentration::from_commits(
+                classified
+                    .iter()
+                    .filter(|(_, bot)| !bot)
+                    .map(|(contribution, _)| contribution.commits),
+            ) {
+                info!(
+                    "{link} bus factor: {}, top contributor share: {:.0}%",
+                    concentration.bus_factor,
+                    concentration.top_share * 100.0
+                );
+            }
+            if let Some(identities) = crawl.write().await.identities.as_mut() {
+                identities.extend(
+                    classified
+                        .iter()
+                        .map(|(contribution, bot)| (contribution.contributor.identity(), *bot)),
+                );
             }
-        } else {
-            error!("Dependency fetching error: {:?}", dep.unwrap_err());
         }
+        Err(e) => error!("Error fetching contributor identities: {}", e),
     }
-
-    dependencies
 }
@@ -0,0 +1,558 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value as JsonValue;
+use toml::{Table, Value as TomlValue};
+
+use crate::{Dependency, Ecosystem, GitHubError, RepositoryId};
+
+lazy_static! {
+    /// Name, extras and constraints of a PEP 508 requirement, such as
+    /// `requests[socks] >= 2.8`
+    static ref PYTHON_REQUIREMENT: Regex =
+        Regex::new(r"^([A-Za-z0-9][A-Za-z0-9._-]*)\s*(?:\[[^\]]*\])?\s*(.*)$").unwrap();
+}
+
+/// Tables of a Cargo manifest listing dependencies
+const CARGO_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+/// Objects of a `package.json` listing dependencies
+const NPM_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// A package as declared by a manifest, before it is resolved to a repository.
+struct Declared {
+    name: String,
+    requirements: Option<String>,
+    /// Repository the manifest fetches the package from, instead of a registry
+    url: Option<String>,
+}
+
+impl Declared {
+    fn new(name: &str, requirements: Option<&str>, url: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            requirements: requirements.map(str::to_string),
+            url: url.map(str::to_string),
+        }
+    }
+}
+
+/// A manifest or lock file declaring the dependencies of a repository, read
+/// at its root.
+///
+/// ```rust
+/// use github_scrapper::{Ecosystem, Manifest};
+///
+/// let packages = Manifest::GoMod
+///     .parse("module example.com/app\n\nrequire github.com/pkg/errors v0.9.1\n")
+///     .unwrap();
+/// assert_eq!(packages[0].package_name, "github.com/pkg/errors");
+/// assert_eq!(packages[0].ecosystem, Some(Ecosystem::Go));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Manifest {
+    CargoLock,
+    CargoToml,
+    PackageLock,
+    PackageJson,
+    GoMod,
+    PyProject,
+    Requirements,
+}
+
+impl Manifest {
+    /// Every manifest, the lock files before the manifests they pin.
+    pub const ALL: [Manifest; 7] = [
+        Manifest::CargoLock,
+        Manifest::CargoToml,
+        Manifest::PackageLock,
+        Manifest::PackageJson,
+        Manifest::GoMod,
+        Manifest::PyProject,
+        Manifest::Requirements,
+    ];
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            Manifest::CargoLock => "Cargo.lock",
+            Manifest::CargoToml => "Cargo.toml",
+            Manifest::PackageLock => "package-lock.json",
+            Manifest::PackageJson => "package.json",
+            Manifest::GoMod => "go.mod",
+            Manifest::PyProject => "pyproject.toml",
+            Manifest::Requirements => "requirements.txt",
+        }
+    }
+
+    pub fn ecosystem(&self) -> Ecosystem {
+        match self {
+            Manifest::CargoLock | Manifest::CargoToml => Ecosystem::Cargo,
+            Manifest::PackageLock | Manifest::PackageJson => Ecosystem::Npm,
+            Manifest::GoMod => Ecosystem::Go,
+            Manifest::PyProject | Manifest::Requirements => Ecosystem::Pip,
+        }
+    }
+
+    /// The manifest this lock file makes redundant, since the lock file also
+    /// lists the dependencies of the dependencies.
+    pub fn supersedes(&self) -> Option<Manifest> {
+        match self {
+            Manifest::CargoLock => Some(Manifest::CargoToml),
+            Manifest::PackageLock => Some(Manifest::PackageJson),
+            _ => None,
+        }
+    }
+
+    /// The packages declared by the content of the manifest, the local ones
+    /// such as the members of a workspace aside.
+    pub fn parse(&self, content: &str) -> Result<Vec<Dependency>, GitHubError> {
+        let declared = match self {
+            Manifest::CargoLock => cargo_lock(&self.toml(content)?),
+            Manifest::CargoToml => cargo_manifest(&self.toml(content)?),
+            Manifest::PackageLock => npm_lock(&self.json(content)?),
+            Manifest::PackageJson => npm_manifest(&self.json(content)?),
+            Manifest::GoMod => go_mod(content),
+            Manifest::PyProject => pyproject(&self.toml(content)?),
+            Manifest::Requirements => requirements(content),
+        };
+        Ok(declared
+            .into_iter()
+            .map(|package| Dependency {
+                ecosystem: Some(self.ecosystem()),
+                package_name: package.name,
+                requirements: package.requirements,
+                manifest: Some(self.path().to_string()),
+                repository: package
+                    .url
+                    .clone()
+                    .and_then(|url| RepositoryId::try_from(url).ok()),
+                url: package.url,
+            })
+            .collect())
+    }
+
+    fn invalid(&self, reason: impl ToString) -> GitHubError {
+        GitHubError::InvalidManifest {
+            path: self.path().to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn toml(&self, content: &str) -> Result<Table, GitHubError> {
+        content.parse().map_err(|e| self.invalid(e))
+    }
+
+    fn json(&self, content: &str) -> Result<JsonValue, GitHubError> {
+        serde_json::from_str(content).map_err(|e| self.invalid(e))
+    }
+}
+
+fn cargo_manifest(manifest: &Table) -> Vec<Declared> {
+    let tables_of = |table: &Table| -> Vec<Table> {
+        CARGO_TABLES
+            .iter()
+            .filter_map(|name| table.get(*name)?.as_table().cloned())
+            .collect()
+    };
+    let mut tables = tables_of(manifest);
+    // Platform specific dependencies, such as `[target.'cfg(unix)'.dependencies]`
+    if let Some(targets) = manifest.get("target").and_then(TomlValue::as_table) {
+        for target in targets.values().filter_map(TomlValue::as_table) {
+            tables.extend(tables_of(target));
+        }
+    }
+    // Dependencies shared by the members of a workspace
+    if let Some(TomlValue::Table(shared)) = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+    {
+        tables.push(shared.clone());
+    }
+    tables
+        .iter()
+        .flat_map(|table| table.iter())
+        .filter_map(|(key, spec)| match spec {
+            TomlValue::String(version) => Some(Declared::new(key, Some(version), None)),
+            TomlValue::Table(spec) => {
+                let field = |name: &str| spec.get(name).and_then(TomlValue::as_str);
+                // Local crates, and those inherited from the workspace listed
+                // with its own dependencies
+                if spec.contains_key("path")
+                    || (field("version").is_none() && field("git").is_none())
+                {
+                    return None;
+                }
+                let name = field("package").unwrap_or(key);
+                Some(Declared::new(name, field("version"), field("git")))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn cargo_lock(lock: &Table) -> Vec<Declared> {
+    let Some(packages) = lock.get("package").and_then(TomlValue::as_array) else {
+        return vec![];
+    };
+    packages
+        .iter()
+        .filter_map(TomlValue::as_table)
+        .filter_map(|package| {
+            let field = |name: &str| package.get(name).and_then(TomlValue::as_str);
+            // The crates of the workspace have no source
+            let source = field("source")?;
+            let git = source
+                .strip_prefix("git+")
+                .and_then(|url| url.split(['?', '#']).next());
+            Some(Declared::new(field("name")?, field("version"), git))
+        })
+        .collect()
+}
+
+fn npm_manifest(manifest: &JsonValue) -> Vec<Declared> {
+    NPM_FIELDS
+        .iter()
+        .filter_map(|field| manifest.get(field)?.as_object())
+        .flat_map(|dependencies| dependencies.iter())
+        .filter_map(|(name, spec)| {
+            let spec = spec.as_str()?;
+            if ["file:", "link:", "workspace:"]
+                .iter()
+                .any(|local| spec.starts_with(local))
+            {
+                return None;
+            }
+            let url = match spec.strip_prefix("github:") {
+                Some(path) => Some(format!("https://github.com/{path}")),
+                None => spec.starts_with("git").then(|| spec.to_string()),
+            };
+            let url = url.map(|url| url.split('#').next().unwrap_or_default().to_string());
+            Some(Declared::new(name, Some(spec), url.as_deref()))
+        })
+        .collect()
+}
+
+fn npm_lock(lock: &JsonValue) -> Vec<Declared> {
+    let entry = |name: &str, entry: &JsonValue| {
+        let field = |name: &str| entry.get(name).and_then(JsonValue::as_str);
+        let git = field("resolved").filter(|resolved| resolved.starts_with("git"));
+        Declared::new(name, field("version"), git)
+    };
+    // Lock files of version 2 and 3 list the installed packages by their path
+    if let Some(packages) = lock.get("packages").and_then(JsonValue::as_object) {
+        return packages
+            .iter()
+            .filter(|(_, package)| package.get("link").and_then(JsonValue::as_bool) != Some(true))
+            .filter_map(|(path, package)| {
+                // The root package and the members of the workspace are not
+                // installed in node_modules
+                let (_, name) = path.rsplit_once("node_modules/")?;
+                Some(entry(name, package))
+            })
+            .collect();
+    }
+    lock.get("dependencies")
+        .and_then(JsonValue::as_object)
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .map(|(name, package)| entry(name, package))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn go_mod(content: &str) -> Vec<Declared> {
+    let mut declared = vec![];
+    let mut in_block = false;
+    for line in content.lines() {
+        // `// indirect` marks the dependencies of the dependencies, kept as well
+        let line = line.split("//").next().unwrap_or_default().trim();
+        let requirement = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if line.starts_with("require") && line.ends_with('(') {
+            in_block = true;
+            continue;
+        } else if let Some(requirement) = line.strip_prefix("require ") {
+            requirement
+        } else {
+            continue;
+        };
+        let mut parts = requirement.split_whitespace();
+        if let Some(module) = parts.next() {
+            declared.push(Declared::new(module, parts.next(), None));
+        }
+    }
+    declared
+}
+
+/// A PEP 508 requirement, such as `requests >= 2.8; python_version > "3.8"`.
+fn python_requirement(requirement: &str) -> Option<Declared> {
+    // Environment markers do not tell the package apart
+    let requirement = requirement.split(';').next().unwrap_or_default().trim();
+    let captures = PYTHON_REQUIREMENT.captures(requirement)?;
+    let constraints = captures[2].trim();
+    if let Some(url) = constraints.strip_prefix('@') {
+        return Some(Declared::new(&captures[1], None, Some(url.trim())));
+    }
+    // Anything else, such as a `git+https://` line, is not a package name
+    if !constraints.is_empty() && !constraints.starts_with(['<', '>', '=', '!', '~', '(']) {
+        return None;
+    }
+    Some(Declared::new(
+        &captures[1],
+        Some(constraints).filter(|c| !c.is_empty()),
+        None,
+    ))
+}
+
+fn pyproject(pyproject: &Table) -> Vec<Declared> {
+    let table = |value: Option<&TomlValue>, key: &str| value?.get(key).cloned();
+    let project = pyproject.get("project");
+    let mut requirements: Vec<TomlValue> = table(project, "dependencies").into_iter().collect();
+    if let Some(TomlValue::Table(extras)) = table(project, "optional-dependencies") {
+        requirements.extend(extras.into_iter().map(|(_, extra)| extra));
+    }
+    let mut declared: Vec<Declared> = requirements
+        .iter()
+        .filter_map(TomlValue::as_array)
+        .flatten()
+        .filter_map(TomlValue::as_str)
+        .filter_map(python_requirement)
+        .collect();
+
+    // Poetry lists them as tables, in groups since its version 1.2
+    let poetry = pyproject.get("tool").and_then(|tool| tool.get("poetry"));
+    let mut tables: Vec<TomlValue> = ["dependencies", "dev-dependencies"]
+        .iter()
+        .filter_map(|key| table(poetry, key))
+        .collect();
+    if let Some(TomlValue::Table(groups)) = table(poetry, "group") {
+        tables.extend(
+            groups
+                .values()
+                .filter_map(|group| group.get("dependencies").cloned()),
+        );
+    }
+    for (name, spec) in tables.iter().filter_map(TomlValue::as_table).flatten() {
+        if name == "python" {
+            continue;
+        }
+        let field = |key: &str| spec.get(key).and_then(TomlValue::as_str);
+        match spec {
+            TomlValue::String(version) => declared.push(Declared::new(name, Some(version), None)),
+            TomlValue::Table(_) if spec.get("path").is_none() => {
+                declared.push(Declared::new(name, field("version"), field("git")))
+            }
+            _ => {}
+        }
+    }
+    declared
+}
+
+fn requirements(content: &str) -> Vec<Declared> {
+    content
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or_default().trim())
+        // Comments, and options such as `-r other.txt` or `-e .`
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '-']))
+        .filter_map(python_requirement)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The sorted names of the packages, the parsed tables not keeping their order
+    fn names(packages: &[Dependency]) -> Vec<&str> {
+        let mut names: Vec<&str> = packages
+            .iter()
+            .map(|package| package.package_name.as_str())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn package<'a>(packages: &'a [Dependency], name: &str) -> &'a Dependency {
+        packages
+            .iter()
+            .find(|package| package.package_name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cargo_manifests() {
+        let manifest = r#"
+            [package]
+            name = "app"
+
+            [dependencies]
+            serde = "1.0"
+            local = { path = "../local" }
+            inherited = { workspace = true }
+            rand_core = { package = "rand", version = "0.9" }
+            loom = { git = "https://github.com/tokio-rs/loom", branch = "master" }
+
+            [target.'cfg(unix)'.dependencies]
+            libc = "0.2"
+
+            [dev-dependencies]
+            tokio-test = { version = "0.4" }
+        "#;
+        let packages = Manifest::CargoToml.parse(manifest).unwrap();
+        assert_eq!(
+            names(&packages),
+            ["libc", "loom", "rand", "serde", "tokio-test"]
+        );
+        assert_eq!(
+            package(&packages, "rand").requirements.as_deref(),
+            Some("0.9")
+        );
+        let loom = package(&packages, "loom");
+        assert_eq!(loom.repository.as_ref().unwrap().path(), "tokio-rs/loom");
+        assert_eq!(loom.manifest.as_deref(), Some("Cargo.toml"));
+
+        let lock = r#"
+            version = 4
+
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+
+            [[package]]
+            name = "serde"
+            version = "1.0.219"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "loom"
+            version = "0.7.2"
+            source = "git+https://github.com/tokio-rs/loom?branch=master#0123456"
+        "#;
+        let packages = Manifest::CargoLock.parse(lock).unwrap();
+        assert_eq!(names(&packages), ["loom", "serde"]);
+        assert_eq!(package(&packages, "serde").url, None);
+        assert_eq!(
+            package(&packages, "loom").url.as_deref(),
+            Some("https://github.com/tokio-rs/loom")
+        );
+
+        assert!(matches!(
+            Manifest::CargoLock.parse("[[package]"),
+            Err(GitHubError::InvalidManifest { .. })
+        ));
+    }
+
+    #[test]
+    fn test_npm_manifests() {
+        let manifest = r#"{
+            "name": "app",
+            "dependencies": { "react": "^18.2.0", "utils": "file:../utils" },
+            "devDependencies": { "vitest": "1.0.0", "fork": "github:someone/fork#main" }
+        }"#;
+        let packages = Manifest::PackageJson.parse(manifest).unwrap();
+        assert_eq!(names(&packages), ["fork", "react", "vitest"]);
+        assert_eq!(
+            package(&packages, "react").requirements.as_deref(),
+            Some("^18.2.0")
+        );
+        let fork = package(&packages, "fork");
+        assert_eq!(fork.repository.as_ref().unwrap().path(), "someone/fork");
+
+        let lock = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/react": { "version": "18.2.0" },
+                "node_modules/@babel/core": { "version": "7.24.0" },
+                "node_modules/@babel/core/node_modules/semver": { "version": "6.3.1" },
+                "node_modules/utils": { "resolved": "../utils", "link": true }
+            }
+        }"#;
+        let packages = Manifest::PackageLock.parse(lock).unwrap();
+        assert_eq!(names(&packages), ["@babel/core", "react", "semver"]);
+    }
+
+    #[test]
+    fn test_go_mod() {
+        let go_mod = "module example.com/app
+
+            go 1.22
+
+            require github.com/pkg/errors v0.9.1
+
+            require (
+                golang.org/x/net v0.25.0
+                gopkg.in/yaml.v3 v3.0.1 // indirect
+            )
+
+            replace golang.org/x/net => ../net
+        ";
+        let packages = Manifest::GoMod.parse(go_mod).unwrap();
+        assert_eq!(
+            names(&packages),
+            [
+                "github.com/pkg/errors",
+                "golang.org/x/net",
+                "gopkg.in/yaml.v3"
+            ]
+        );
+        let yaml = package(&packages, "gopkg.in/yaml.v3");
+        assert_eq!(yaml.requirements.as_deref(), Some("v3.0.1"));
+    }
+
+    #[test]
+    fn test_python_manifests() {
+        let pyproject = r#"
+            [project]
+            dependencies = ["requests[socks] >= 2.8; python_version > '3.8'", "rich"]
+
+            [project.optional-dependencies]
+            docs = ["sphinx==7.0"]
+
+            [tool.poetry.dependencies]
+            python = "^3.10"
+            httpx = "^0.27"
+            local = { path = "../local" }
+
+            [tool.poetry.group.test.dependencies]
+            pytest = { version = "^8.0" }
+        "#;
+        let packages = Manifest::PyProject.parse(pyproject).unwrap();
+        assert_eq!(
+            names(&packages),
+            ["httpx", "pytest", "requests", "rich", "sphinx"]
+        );
+        assert_eq!(
+            package(&packages, "requests").requirements.as_deref(),
+            Some(">= 2.8")
+        );
+        assert_eq!(package(&packages, "rich").requirements, None);
+
+        let requirements = "# Pinned
+            -r base.txt
+            Django>=4.2,<5  # LTS
+            git+https://github.com/someone/package.git#egg=package
+            attrs @ https://github.com/python-attrs/attrs/archive/main.zip
+        ";
+        let packages = Manifest::Requirements.parse(requirements).unwrap();
+        assert_eq!(names(&packages), ["Django", "attrs"]);
+        assert_eq!(
+            package(&packages, "Django").requirements.as_deref(),
+            Some(">=4.2,<5")
+        );
+        let attrs = package(&packages, "attrs");
+        assert_eq!(
+            attrs.repository.as_ref().unwrap().path(),
+            "python-attrs/attrs"
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+use metrics::{counter, gauge};
+use rand::Rng;
+use reqwest::StatusCode;
+use reqwest::header::{AUTHORIZATION, HeaderMap, RETRY_AFTER};
+use tokio::time::{Instant, sleep, sleep_until};
+use tracing::warn;
+
+use crate::{FetchRequest, FetchResponse, GitHubError, GitHubFetcher, HttpFetcher};
+
+pub(crate) const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub(crate) const RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// GitHub asks to wait at least a minute after hitting a secondary rate limit
+/// that does not say for how long.
+const SECONDARY_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref GLOBAL_RATE_LIMITER: Arc<RateLimiter> =
+        Arc::new(RateLimiter::new(RateLimiterConfig::default()));
+}
+
+/// Settings of a [`RateLimiter`].
+#[derive(Debug, Clone)]
+pub struct RateLimiterConfig {
+    /// Sustained number of requests per second sent to GitHub.
+    pub requests_per_second: f64,
+    /// Number of requests that can be sent at once after a quiet period.
+    pub burst: u32,
+    /// Number of retries of a rate limited or failed request.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each following one.
+    pub base_backoff: Duration,
+    /// Upper bound of the delay between two retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RateLimiterConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 4.0 / 3.0,
+            burst: 5,
+            max_retries: 4,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    config: RateLimiterConfig,
+    tokens: f64,
+    last_refill: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl State {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.config.requests_per_second).min(self.config.burst as f64);
+        self.last_refill = now;
+    }
+}
+
+/// A token bucket pacing the requests sent to GitHub, which also pauses every
+/// request when GitHub reports that a rate limit was hit.
+///
+/// A single limiter is shared by the whole process, see [`RateLimiter::global`],
+/// so concurrent crawls do not multiply the pressure put on GitHub.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimiterConfig) -> Self {
+        Self {
+            state: Mutex::new(State {
+                tokens: config.burst as f64,
+                config,
+                last_refill: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    /// The limiter shared by all the [`RateLimitedFetcher`] of the process.
+    pub fn global() -> Arc<RateLimiter> {
+        GLOBAL_RATE_LIMITER.clone()
+    }
+
+    /// Replaces the settings of the limiter, keeping its current state.
+    pub fn reconfigure(&self, config: RateLimiterConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.refill(Instant::now());
+        state.tokens = state.tokens.min(config.burst as f64);
+        state.config = config;
+    }
+
+    fn config(&self) -> RateLimiterConfig {
+        self.state.lock().unwrap().config.clone()
+    }
+
+    /// Waits until a request can be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wake_up = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                state.refill(now);
+                gauge!("github_rate_limit_tokens").set(state.tokens);
+                match state.blocked_until {
+                    Some(until) if until > now => until,
+                    _ if state.tokens >= 1.0 => {
+                        state.tokens -= 1.0;
+                        return;
+                    }
+                    _ => {
+                        let missing = 1.0 - state.tokens;
+                        now + Duration::from_secs_f64(missing / state.config.requests_per_second)
+                    }
+                }
+            };
+            sleep_until(wake_up).await;
+        }
+    }
+
+    /// Pauses all requests for the given duration.
+    fn block_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.state.lock().unwrap();
+        if state.blocked_until.is_none_or(|blocked| blocked < until) {
+            state.blocked_until = Some(until);
+        }
+        gauge!("github_rate_limit_blocked_seconds").set(duration.as_secs_f64());
+    }
+
+    /// Records the quota advertised by the response headers, and pauses all
+    /// requests until the reset time once it is exhausted.
+    fn observe(&self, headers: &HeaderMap) {
+        let Some(remaining) = header_u64(headers, REMAINING_HEADER) else {
+            return;
+        };
+        gauge!("github_rate_limit_remaining").set(remaining as f64);
+        if remaining == 0
+            && let Some(reset) = until_reset(headers)
+        {
+            warn!("GitHub rate limit exhausted, pausing requests for {reset:?}");
+            self.block_for(reset);
+        }
+    }
+
+    /// Sends the request through the fetcher once allowed to, retrying it while
+    /// GitHub answers with a rate limit or a server error.
+    ///
+    /// The quota of an authenticated request belongs to its token: it does not
+    /// pause the other requests, and its exhaustion is left to the
+    /// [`AuthenticatedFetcher`](crate::AuthenticatedFetcher) to handle.
+    pub async fn execute<F: GitHubFetcher>(
+        &self,
+        fetcher: &F,
+        request: &FetchRequest,
+    ) -> Result<FetchResponse, GitHubError> {
+        let config = self.config();
+        let authenticated = request.headers.contains_key(AUTHORIZATION);
+        let mut attempt = 0;
+        loop {
+            self.acquire().await;
+            let outcome = fetcher.fetch(request).await;
+            let backoff = backoff(&config, attempt);
+
+            let (wait, rate_limited) = match &outcome {
+                Ok(response) => {
+                    if !authenticated {
+                        self.observe(&response.headers);
+                    }
+                    if let Some(wait) = rate_limit_wait(response, backoff, authenticated) {
+                        (wait, true)
+                    } else if response.status.is_server_error() {
+                        counter!("github_retries", "reason" => "server_error").increment(1);
+                        (backoff, false)
+                    } else {
+                        return outcome;
+                    }
+                }
+                Err(GitHubError::Request(e)) if e.is_timeout() || e.is_connect() => {
+                    counter!("github_retries", "reason" => "network").increment(1);
+                    (backoff, false)
+                }
+                Err(_) => return outcome,
+            };
+
+            if rate_limited {
+                counter!("github_retries", "reason" => "rate_limit").increment(1);
+                warn!("Rate limited on {}, retrying in {:?}", request.url, wait);
+                self.block_for(wait);
+            }
+            if attempt >= config.max_retries {
+                if rate_limited {
+                    return Err(GitHubError::RateLimited {
+                        url: request.url.clone(),
+                        retry_after: wait,
+                    });
+                }
+                return outcome;
+            }
+            if !rate_limited {
+                sleep(wait).await;
+            }
+            attempt += 1;
+        }
+    }
+}
+
+/// Paces and retries the requests of another fetcher with a [`RateLimiter`].
+#[derive(Debug, Clone)]
+pub struct RateLimitedFetcher<F = HttpFetcher> {
+    inner: F,
+    limiter: Arc<RateLimiter>,
+}
+
+impl Default for RateLimitedFetcher {
+    fn default() -> Self {
+        Self::new(HttpFetcher::default(), RateLimiter::global())
+    }
+}
+
+impl<F: GitHubFetcher> RateLimitedFetcher<F> {
+    pub fn new(inner: F, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl<F: GitHubFetcher> GitHubFetcher for RateLimitedFetcher<F> {
+    async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+        self.limiter.execute(&self.inner, request).await
+    }
+}
+
+/// How long to wait before retrying a rate limited response, `None` if it was not.
+///
+/// Follows GitHub's guidelines: wait for `Retry-After` if present, otherwise until
+/// the quota reset if it is exhausted, otherwise at least a minute.
+fn rate_limit_wait(
+    response: &FetchResponse,
+    backoff: Duration,
+    authenticated: bool,
+) -> Option<Duration> {
+    let status = response.status;
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::FORBIDDEN {
+        return None;
+    }
+    if let Some(retry_after) = header_u64(&response.headers, RETRY_AFTER.as_str()) {
+        counter!("github_rate_limited", "kind" => "secondary").increment(1);
+        return Some(Duration::from_secs(retry_after));
+    }
+    if header_u64(&response.headers, REMAINING_HEADER) == Some(0) {
+        counter!("github_rate_limited", "kind" => "primary").increment(1);
+        if authenticated {
+            return None;
+        }
+        return Some(until_reset(&response.headers).unwrap_or(backoff));
+    }
+    let secondary = status == StatusCode::TOO_MANY_REQUESTS
+        || response
+            .body
+            .to_lowercase()
+            .contains("secondary rate limit");
+    if secondary {
+        counter!("github_rate_limited", "kind" => "secondary").increment(1);
+        return Some(backoff.max(SECONDARY_LIMIT_WAIT));
+    }
+    None
+}
+
+/// Exponential backoff with jitter, so that concurrent retries spread out.
+fn backoff(config: &RateLimiterConfig, attempt: u32) -> Duration {
+    let exponential = config
+        .base_backoff
+        .saturating_mul(2_u32.saturating_pow(attempt))
+        .min(config.max_backoff);
+    let jitter = rand::rng().random_range(0.5..=1.0);
+    exponential.mul_f64(jitter)
+}
+
+pub(crate) fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok()
+}
+
+/// Time left before the quota reset, from the `X-RateLimit-Reset` epoch timestamp.
+pub(crate) fn until_reset(headers: &HeaderMap) -> Option<Duration> {
+    let reset = header_u64(headers, RESET_HEADER)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now).max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::collections::VecDeque;
+
+    /// Answers the scripted responses in order.
+    struct ScriptedFetcher {
+        responses: Mutex<VecDeque<FetchResponse>>,
+    }
+
+    impl ScriptedFetcher {
+        fn new(responses: Vec<FetchResponse>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+            }
+        }
+    }
+
+    impl GitHubFetcher for ScriptedFetcher {
+        async fn fetch(&self, _: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+            Ok(self.responses.lock().unwrap().pop_front().unwrap())
+        }
+    }
+
+    fn response(status: u16, headers: &[(&'static str, String)], body: &str) -> FetchResponse {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        FetchResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers: map,
+            body: body.to_string(),
+        }
+    }
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimiterConfig {
+            requests_per_second: 100.0,
+            burst: 100,
+            ..Default::default()
+        })
+    }
+
+    fn request() -> FetchRequest {
+        FetchRequest::get("https://github.com/OWNER/REPO")
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_bucket_pacing() {
+        let limiter = RateLimiter::new(RateLimiterConfig {
+            requests_per_second: 2.0,
+            burst: 1,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert!(start.elapsed() < Duration::from_millis(1100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after() {
+        let fetcher = ScriptedFetcher::new(vec![
+            response(429, &[("retry-after", "30".to_string())], ""),
+            response(200, &[], "ok"),
+        ]);
+        let limiter = limiter();
+        let start = Instant::now();
+
+        let output = limiter.execute(&fetcher, &request()).await.unwrap();
+        assert_eq!(output.body, "ok");
+        assert!(start.elapsed() >= Duration::from_secs(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_secondary_limit_waits_a_minute() {
+        let fetcher = ScriptedFetcher::new(vec![
+            response(403, &[], "You have exceeded a secondary rate limit."),
+            response(200, &[], "ok"),
+        ]);
+        let limiter = limiter();
+        let start = Instant::now();
+
+        let output = limiter.execute(&fetcher, &request()).await.unwrap();
+        assert_eq!(output.body, "ok");
+        assert!(start.elapsed() >= SECONDARY_LIMIT_WAIT);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_exhausted_quota_blocks_next_requests() {
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 120;
+        let fetcher = ScriptedFetcher::new(vec![
+            response(
+                200,
+                &[
+                    (REMAINING_HEADER, "0".to_string()),
+                    (RESET_HEADER, reset.to_string()),
+                ],
+                "last",
+            ),
+            response(200, &[], "after reset"),
+        ]);
+        let limiter = limiter();
+        let start = Instant::now();
+
+        assert_eq!(
+            limiter.execute(&fetcher, &request()).await.unwrap().body,
+            "last"
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let output = limiter.execute(&fetcher, &request()).await.unwrap();
+        assert_eq!(output.body, "after reset");
+        assert!(start.elapsed() >= Duration::from_secs(118));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up_after_max_retries() {
+        let responses = (0..5).map(|_| response(429, &[], "")).collect();
+        let fetcher = ScriptedFetcher::new(responses);
+
+        let output = limiter().execute(&fetcher, &request()).await;
+        assert!(matches!(output, Err(GitHubError::RateLimited { .. })));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_forbidden_is_not_retried() {
+        let fetcher = ScriptedFetcher::new(vec![response(403, &[], "Forbidden")]);
+
+        let output = limiter().execute(&fetcher, &request()).await.unwrap();
+        assert_eq!(output.status, StatusCode::FORBIDDEN);
+    }
+}
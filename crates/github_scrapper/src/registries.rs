@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use metrics::counter;
+use serde_json::Value;
+
+use crate::utils::check_status;
+use crate::{
+    Ecosystem, FetchRequest, GitHubError, GitHubFetcher, HttpFetcher, RateLimitedFetcher,
+    RateLimiter, RateLimiterConfig, RepositoryId,
+};
+
+/// Roots of the public registries
+const REGISTRY_URLS: [(Ecosystem, &str); 4] = [
+    (Ecosystem::Cargo, "https://crates.io/api/v1/crates"),
+    (Ecosystem::Npm, "https://registry.npmjs.org"),
+    (Ecosystem::Pip, "https://pypi.org/pypi"),
+    (Ecosystem::Go, "https://proxy.golang.org"),
+];
+
+/// Tells the repositories of the packages from their metadata.
+///
+/// This is the extension point used to resolve the packages of the manifests
+/// without reaching the public registries, for example in tests.
+pub trait PackageRegistry: Send + Sync {
+    /// The repository URL the registry lists for the package, `None` when it
+    /// lists none or does not know the ecosystem.
+    fn repository_url(
+        &self,
+        ecosystem: &Ecosystem,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<String>, GitHubError>> + Send;
+}
+
+/// The public registries: crates.io, npm, PyPI and the Go module proxy.
+#[derive(Debug, Clone)]
+pub struct Registries<F = RateLimitedFetcher> {
+    fetcher: F,
+    urls: HashMap<Ecosystem, String>,
+}
+
+impl Default for Registries {
+    /// Requests paced by their own [`RateLimiter`], apart from the GitHub ones.
+    fn default() -> Self {
+        let limiter = Arc::new(RateLimiter::new(RateLimiterConfig::default()));
+        Self::with_fetcher(RateLimitedFetcher::new(HttpFetcher::default(), limiter))
+    }
+}
+
+impl<F: GitHubFetcher> Registries<F> {
+    pub fn with_fetcher(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            urls: REGISTRY_URLS
+                .iter()
+                .map(|(ecosystem, url)| (ecosystem.clone(), url.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Overrides the root of the registry of the ecosystem, for example to
+    /// target a mock server.
+    pub fn with_url(mut self, ecosystem: Ecosystem, url: &str) -> Self {
+        self.urls
+            .insert(ecosystem, url.trim_end_matches('/').to_string());
+        self
+    }
+
+    async fn fetch_json(&self, url: &str) -> Result<Value, GitHubError> {
+        let response = check_status(self.fetcher.fetch(&FetchRequest::get(url)).await?, url)?;
+        Ok(serde_json::from_str(&response.body)?)
+    }
+
+    async fn crates_io(&self, root: &str, name: &str) -> Result<Option<String>, GitHubError> {
+        let metadata = self.fetch_json(&format!("{root}/{name}")).await?;
+        let field = |name: &str| metadata.pointer(&format!("/crate/{name}"))?.as_str();
+        Ok(pick(field("repository"), [field("homepage")]))
+    }
+
+    async fn npm(&self, root: &str, name: &str) -> Result<Option<String>, GitHubError> {
+        let metadata = self.fetch_json(&format!("{root}/{name}/latest")).await?;
+        // Either a URL or an object with one, possibly a shorthand such as `github:owner/repo`
+        let repository = metadata
+            .get("repository")
+            .and_then(|repository| repository.get("url").unwrap_or(repository).as_str())
+            .map(npm_repository_url);
+        Ok(pick(
+            repository.as_deref(),
+            [metadata.get("homepage").and_then(Value::as_str)],
+        ))
+    }
+
+    async fn pypi(&self, root: &str, name: &str) -> Result<Option<String>, GitHubError> {
+        let metadata = self
+            .fetch_json(&format!("{root}/{}/json", normalize_python_name(name)))
+            .await?;
+        let project_urls: Vec<(String, &str)> = metadata
+            .pointer("/info/project_urls")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(label, url)| Some((label.to_lowercase(), url.as_str()?)))
+            .collect();
+        let repository = project_urls
+            .iter()
+            .find(|(label, _)| {
+                ["source", "repository", "code"]
+                    .iter()
+                    .any(|l| label.contains(l))
+            })
+            .map(|(_, url)| *url);
+        let home_page = metadata.pointer("/info/home_page").and_then(Value::as_str);
+        Ok(pick(
+            repository,
+            project_urls
+                .iter()
+                .map(|(_, url)| Some(*url))
+                .chain([home_page]),
+        ))
+    }
+
+    async fn go_proxy(&self, root: &str, module: &str) -> Result<Option<String>, GitHubError> {
+        // The path of the modules hosted on GitHub starts with their repository
+        if module.starts_with("github.com/") {
+            return Ok(Some(format!("https://{module}")));
+        }
+        let metadata = self
+            .fetch_json(&format!("{root}/{}/@latest", escape_go_module(module)))
+            .await?;
+        Ok(metadata
+            .pointer("/Origin/URL")
+            .and_then(Value::as_str)
+            .map(str::to_string))
+    }
+}
+
+impl<F: GitHubFetcher> PackageRegistry for Registries<F> {
+    async fn repository_url(
+        &self,
+        ecosystem: &Ecosystem,
+        name: &str,
+    ) -> Result<Option<String>, GitHubError> {
+        let Some(root) = self.urls.get(ecosystem) else {
+            return Ok(None);
+        };
+        counter!("fetch", "type" => "package", "ecosystem" => ecosystem.to_string()).increment(1);
+        match ecosystem {
+            Ecosystem::Cargo => self.crates_io(root, name).await,
+            Ecosystem::Npm => self.npm(root, name).await,
+            Ecosystem::Pip => self.pypi(root, name).await,
+            Ecosystem::Go => self.go_proxy(root, name).await,
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The listed repository when it is one of a supported forge, or else the
+/// first of the other URLs that is, or else the listed repository as is.
+fn pick<'a>(
+    repository: Option<&'a str>,
+    others: impl IntoIterator<Item = Option<&'a str>>,
+) -> Option<String> {
+    let supported = |url: &&str| RepositoryId::try_from(url.to_string()).is_ok();
+    repository
+        .filter(supported)
+        .or_else(|| others.into_iter().flatten().find(supported))
+        .or(repository)
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// The URL of a repository of `package.json`, such as `git@github.com:owner/repo.git`
+/// or `owner/repo`, as an `https://` one.
+fn npm_repository_url(repository: &str) -> String {
+    let repository = repository.trim().trim_start_matches("git+");
+    for (prefix, host) in [
+        ("github:", "github.com"),
+        ("gitlab:", "gitlab.com"),
+        ("git@github.com:", "github.com"),
+        ("git@gitlab.com:", "gitlab.com"),
+        ("git://", ""),
+        ("ssh://git@", ""),
+    ] {
+        if let Some(path) = repository.strip_prefix(prefix) {
+            let path = path.trim_start_matches('/');
+            return match host {
+                "" => format!("https://{path}"),
+                host => format!("https://{host}/{path}"),
+            };
+        }
+    }
+    if !repository.contains(':') && repository.matches('/').count() == 1 {
+        return format!("https://github.com/{repository}");
+    }
+    repository.to_string()
+}
+
+/// The name of a Python package as normalized by PyPI, such as `zope-interface`
+/// for `Zope.Interface`.
+fn normalize_python_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// The path of a module as requested to the Go module proxy, its uppercase
+/// letters being escaped, such as `github.com/!azure/azure-sdk-for-go`.
+fn escape_go_module(module: &str) -> String {
+    let mut escaped = String::with_capacity(module.len());
+    for c in module.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+        }
+        escaped.push(c.to_ascii_lowercase());
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn registries(
+        server: &MockServer,
+        responses: &[(&str, Value)],
+    ) -> Registries<HttpFetcher> {
+        for (route, body) in responses {
+            Mock::given(method("GET"))
+                .and(path(*route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(server)
+                .await;
+        }
+        REGISTRY_URLS.iter().fold(
+            Registries::with_fetcher(HttpFetcher::default()),
+            |registries, (ecosystem, _)| {
+                let root = format!("{}/{ecosystem}", server.uri());
+                registries.with_url(ecosystem.clone(), &root)
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_repository_urls() {
+        let server = MockServer::start().await;
+        let registries = registries(
+            &server,
+            &[
+                (
+                    "/cargo/rug",
+                    json!({ "crate": { "repository": "https://gitlab.com/tspiteri/rug", "homepage": null } }),
+                ),
+                (
+                    "/cargo/tokio",
+                    json!({ "crate": { "repository": null, "homepage": "https://github.com/tokio-rs/tokio" } }),
+                ),
+                (
+                    "/npm/@babel/core/latest",
+                    json!({ "repository": { "type": "git", "url": "git+https://github.com/babel/babel.git" } }),
+                ),
+                ("/npm/left-pad/latest", json!({ "repository": "stevemao/left-pad" })),
+                (
+                    "/pip/zope-interface/json",
+                    json!({ "info": {
+                        "home_page": "https://zopeinterface.readthedocs.io",
+                        "project_urls": { "Source Code": "https://github.com/zopefoundation/zope.interface" }
+                    } }),
+                ),
+                (
+                    "/go/golang.org/x/net/@latest",
+                    json!({ "Version": "v0.25.0", "Origin": { "VCS": "git", "URL": "https://go.googlesource.com/net" } }),
+                ),
+            ],
+        )
+        .await;
+
+        for (ecosystem, name, url) in [
+            (
+                Ecosystem::Cargo,
+                "rug",
+                Some("https://gitlab.com/tspiteri/rug"),
+            ),
+            (
+                Ecosystem::Cargo,
+                "tokio",
+                Some("https://github.com/tokio-rs/tokio"),
+            ),
+            (
+                Ecosystem::Npm,
+                "@babel/core",
+                Some("https://github.com/babel/babel.git"),
+            ),
+            (
+                Ecosystem::Npm,
+                "left-pad",
+                Some("https://github.com/stevemao/left-pad"),
+            ),
+            (
+                Ecosystem::Pip,
+                "Zope.Interface",
+                Some("https://github.com/zopefoundation/zope.interface"),
+            ),
+            (
+                Ecosystem::Go,
+                "golang.org/x/net",
+                Some("https://go.googlesource.com/net"),
+            ),
+            // Resolved without any request
+            (
+                Ecosystem::Go,
+                "github.com/pkg/errors",
+                Some("https://github.com/pkg/errors"),
+            ),
+            (Ecosystem::Maven, "org.slf4j:slf4j-api", None),
+        ] {
+            let resolved = registries.repository_url(&ecosystem, name).await.unwrap();
+            assert_eq!(resolved.as_deref(), url, "{name}");
+        }
+        assert!(matches!(
+            registries
+                .repository_url(&Ecosystem::Cargo, "missing")
+                .await,
+            Err(GitHubError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            npm_repository_url("github:owner/repo"),
+            "https://github.com/owner/repo"
+        );
+        assert_eq!(
+            npm_repository_url("git@github.com:owner/repo.git"),
+            "https://github.com/owner/repo.git"
+        );
+        assert_eq!(
+            npm_repository_url("git://github.com/owner/repo.git"),
+            "https://github.com/owner/repo.git"
+        );
+        assert_eq!(normalize_python_name("Zope.Interface"), "zope-interface");
+        assert_eq!(
+            normalize_python_name("typing__extensions"),
+            "typing-extensions"
+        );
+        assert_eq!(
+            escape_go_module("github.com/Azure/azure-sdk-for-go"),
+            "github.com/!azure/azure-sdk-for-go"
+        );
+    }
+}
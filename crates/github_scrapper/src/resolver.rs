@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+
+use metrics::counter;
+use tracing::{debug, warn};
+
+use crate::{
+    Dependency, GitHubClient, GitHubError, GitHubFetcher, GitHubLink, Manifest, PackageRegistry,
+    Registries, RepositoryId,
+};
+
+/// Lists the dependencies of a repository from its manifests, as an
+/// alternative to its dependency graph page, which stays empty until GitHub
+/// builds the graph and misses some ecosystems.
+///
+/// The manifests at the root of the default branch are read, see [`Manifest`],
+/// and their packages are resolved to repositories through a [`PackageRegistry`],
+/// which costs one request per package.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use github_scrapper::{GitHubClient, GitHubLink, ManifestResolver};
+///
+/// async fn example() {
+///     let client = Arc::new(GitHubClient::default());
+///     let link = GitHubLink::try_from("https://github.com/tokio-rs/tokio".to_string()).unwrap();
+///     let dependencies = ManifestResolver::default().dependencies(&client, &link).await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManifestResolver<R = Registries> {
+    registries: R,
+}
+
+impl Default for ManifestResolver {
+    fn default() -> Self {
+        Self::new(Registries::default())
+    }
+}
+
+impl<R: PackageRegistry> ManifestResolver<R> {
+    pub fn new(registries: R) -> Self {
+        Self { registries }
+    }
+
+    /// The packages of the manifests of the repository, each listed once, the
+    /// lock files being preferred to the manifests they pin.
+    pub async fn dependencies<F: GitHubFetcher>(
+        &self,
+        client: &GitHubClient<F>,
+        link: &GitHubLink,
+    ) -> Result<Vec<Dependency>, GitHubError> {
+        counter!("fetch", "type" => "manifests").increment(1);
+        let mut packages = vec![];
+        let mut listed = HashSet::new();
+        let mut superseded = vec![];
+        for manifest in Manifest::ALL {
+            if superseded.contains(&manifest) {
+                continue;
+            }
+            let Some(content) = client.fetch_file(link, manifest.path()).await? else {
+                continue;
+            };
+            let declared = match manifest.parse(&content) {
+                Ok(declared) => declared,
+                Err(e) => {
+                    warn!("Skipping a manifest of {link}: {e}");
+                    continue;
+                }
+            };
+            debug!(
+                "Found {} packages in {} of {link}",
+                declared.len(),
+                manifest.path()
+            );
+            superseded.extend(manifest.supersedes());
+            for package in declared {
+                if listed.insert((package.ecosystem.clone(), package.package_name.clone())) {
+                    packages.push(package);
+                }
+            }
+        }
+
+        let mut dependencies = Vec::with_capacity(packages.len());
+        for package in packages {
+            let dependency = self.resolve(package).await;
+            // Such as the crates of a workspace published apart
+            if dependency
+                .repository
+                .as_ref()
+                .is_none_or(|repository| !repository.path().eq_ignore_ascii_case(&link.path()))
+            {
+                dependencies.push(dependency);
+            }
+        }
+        Ok(dependencies)
+    }
+
+    /// The package with the repository its registry lists, unless its manifest
+    /// already tells where it comes from. A package the registry fails to
+    /// describe is kept without any.
+    pub async fn resolve(&self, mut package: Dependency) -> Dependency {
+        let Some(ecosystem) = &package.ecosystem else {
+            return package;
+        };
+        if package.url.is_some() {
+            return package;
+        }
+        match self
+            .registries
+            .repository_url(ecosystem, &package.package_name)
+            .await
+        {
+            Ok(url) => {
+                package.repository = url.clone().and_then(|url| RepositoryId::try_from(url).ok());
+                package.url = url;
+            }
+            Err(e) => warn!(
+                "Could not resolve the {ecosystem} package {}: {e}",
+                package.package_name
+            ),
+        }
+        package
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ecosystem, FetchMode, HttpFetcher};
+    use std::collections::HashMap;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Serves the repositories of a few packages, whatever their ecosystem.
+    struct LocalRegistry(HashMap<&'static str, &'static str>);
+
+    impl PackageRegistry for LocalRegistry {
+        async fn repository_url(
+            &self,
+            _: &Ecosystem,
+            name: &str,
+        ) -> Result<Option<String>, GitHubError> {
+            match name {
+                "failing" => Err(GitHubError::NotFound(name.to_string())),
+                name => Ok(self.0.get(name).map(|url| url.to_string())),
+            }
+        }
+    }
+
+    async fn serve(server: &MockServer, file: &str, content: &str) {
+        Mock::given(method("GET"))
+            .and(path(format!("/tokio-rs/app/HEAD/{file}")))
+            .respond_with(ResponseTemplate::new(200).set_body_string(content))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_manifest_dependencies() {
+        let server = MockServer::start().await;
+        serve(
+            &server,
+            "Cargo.lock",
+            r#"
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+
+            [[package]]
+            name = "app-macros"
+            version = "0.1.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "rug"
+            version = "1.24.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "failing"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        )
+        .await;
+        // Superseded by the lock file
+        Mock::given(method("GET"))
+            .and(path("/tokio-rs/app/HEAD/Cargo.toml"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        serve(
+            &server,
+            "requirements.txt",
+            "requests>=2\nunknown\nrequests==2.31\n",
+        )
+        .await;
+
+        let client = GitHubClient::with_fetcher(HttpFetcher::default(), FetchMode::Html)
+            .with_raw_url(&server.uri());
+        let resolver = ManifestResolver::new(LocalRegistry(HashMap::from([
+            ("app-macros", "https://github.com/tokio-rs/app"),
+            ("rug", "https://gitlab.com/tspiteri/rug"),
+            ("requests", "https://github.com/psf/requests"),
+        ])));
+        let link = GitHubLink::try_from("https://github.com/tokio-rs/app".to_string()).unwrap();
+        let dependencies = resolver.dependencies(&client, &link).await.unwrap();
+
+        let summary: Vec<(&str, Option<String>, Option<&str>)> = dependencies
+            .iter()
+            .map(|dependency| {
+                (
+                    dependency.package_name.as_str(),
+                    dependency.repository_id().map(|id| id.path()),
+                    dependency.manifest.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "rug",
+                    Some("gitlab.com/tspiteri/rug".to_string()),
+                    Some("Cargo.lock")
+                ),
+                ("failing", None, Some("Cargo.lock")),
+                (
+                    "requests",
+                    Some("psf/requests".to_string()),
+                    Some("requirements.txt")
+                ),
+                ("unknown", None, Some("requirements.txt")),
+            ]
+        );
+        assert_eq!(dependencies[0].ecosystem, Some(Ecosystem::Cargo));
+        assert_eq!(dependencies[2].requirements.as_deref(), Some(">=2"));
+    }
+}
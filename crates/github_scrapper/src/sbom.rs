@@ -0,0 +1,589 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Dependency, Ecosystem, GitHubError, RepositoryId};
+
+/// Formats of the software bills of materials that can be imported, both as
+/// JSON documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl FromStr for SbomFormat {
+    type Err = GitHubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "spdx" => Ok(SbomFormat::Spdx),
+            "cyclonedx" => Ok(SbomFormat::CycloneDx),
+            _ => Err(GitHubError::InvalidSbom(format!("unknown format {s}"))),
+        }
+    }
+}
+
+impl fmt::Display for SbomFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SbomFormat::Spdx => write!(f, "spdx"),
+            SbomFormat::CycloneDx => write!(f, "cyclonedx"),
+        }
+    }
+}
+
+/// A software bill of materials, listing the packages a project is built
+/// from, such as a private one whose repository can not be crawled.
+///
+/// ```rust
+/// use github_scrapper::{Sbom, SbomFormat};
+///
+/// let sbom = Sbom::parse(r#"{
+///     "bomFormat": "CycloneDX",
+///     "specVersion": "1.5",
+///     "components": [{ "type": "library", "name": "serde", "purl": "pkg:cargo/serde@1.0.203" }]
+/// }"#).unwrap();
+/// assert_eq!(sbom.format, SbomFormat::CycloneDx);
+/// assert_eq!(sbom.packages[0].package_name, "serde");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sbom {
+    pub format: SbomFormat,
+    /// Name of the described project, when given
+    pub name: Option<String>,
+    /// Packages of the project, each listed once, with their repository when
+    /// the document tells it
+    pub packages: Vec<Dependency>,
+}
+
+impl Sbom {
+    /// Reads an SPDX or a CycloneDX JSON document, telling them apart from
+    /// their content.
+    pub fn parse(content: &str) -> Result<Self, GitHubError> {
+        let document: Value =
+            serde_json::from_str(content).map_err(|e| GitHubError::InvalidSbom(e.to_string()))?;
+        let invalid = |e: serde_json::Error| GitHubError::InvalidSbom(e.to_string());
+        if document.get("spdxVersion").is_some() {
+            Ok(spdx(serde_json::from_value(document).map_err(invalid)?))
+        } else if document
+            .get("bomFormat")
+            .and_then(Value::as_str)
+            .is_some_and(|format| format.eq_ignore_ascii_case("CycloneDX"))
+        {
+            Ok(cyclonedx(
+                serde_json::from_value(document).map_err(invalid)?,
+            ))
+        } else {
+            Err(GitHubError::InvalidSbom(
+                "neither an SPDX nor a CycloneDX JSON document".to_string(),
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxDocument {
+    name: Option<String>,
+    #[serde(default)]
+    packages: Vec<SpdxPackage>,
+    #[serde(default)]
+    document_describes: Vec<String>,
+    #[serde(default)]
+    relationships: Vec<SpdxRelationship>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxPackage {
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    name: String,
+    version_info: Option<String>,
+    download_location: Option<String>,
+    homepage: Option<String>,
+    #[serde(default)]
+    external_refs: Vec<SpdxExternalRef>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxExternalRef {
+    reference_type: String,
+    reference_locator: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxRelationship {
+    spdx_element_id: String,
+    relationship_type: String,
+    related_spdx_element: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxDocument {
+    metadata: Option<CycloneDxMetadata>,
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+struct CycloneDxMetadata {
+    component: Option<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxComponent {
+    name: String,
+    group: Option<String>,
+    version: Option<String>,
+    purl: Option<String>,
+    #[serde(default)]
+    external_references: Vec<CycloneDxReference>,
+    /// Components bundled in this one
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+struct CycloneDxReference {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+}
+
+/// The packages of an SPDX document, but the ones it describes, which are the
+/// project itself.
+fn spdx(document: SpdxDocument) -> Sbom {
+    let described: HashSet<&str> = document
+        .document_describes
+        .iter()
+        .map(String::as_str)
+        .chain(
+            document
+                .relationships
+                .iter()
+                .filter(|relationship| {
+                    relationship.spdx_element_id == "SPDXRef-DOCUMENT"
+                        && relationship.relationship_type == "DESCRIBES"
+                })
+                .map(|relationship| relationship.related_spdx_element.as_str()),
+        )
+        .collect();
+    let packages = document
+        .packages
+        .iter()
+        .filter(|package| !described.contains(package.spdx_id.as_str()))
+        .map(|package| {
+            let purl = package
+                .external_refs
+                .iter()
+                .find(|reference| reference.reference_type == "purl")
+                .map(|reference| reference.reference_locator.as_str());
+            // Either the sources, such as `git+https://github.com/owner/repo@v1.0`,
+            // or an archive of the package
+            let vcs = package
+                .download_location
+                .as_deref()
+                .filter(|location| location.starts_with("git"));
+            let others = [
+                package.download_location.as_deref(),
+                package.homepage.as_deref(),
+            ];
+            sbom_package(
+                SbomFormat::Spdx,
+                &package.name,
+                package.version_info.as_deref(),
+                purl,
+                vcs,
+                others.into_iter().flatten(),
+            )
+        });
+    Sbom {
+        format: SbomFormat::Spdx,
+        name: document.name,
+        packages: deduplicate(packages),
+    }
+}
+
+/// The components of a CycloneDX document, nested ones included.
+fn cyclonedx(document: CycloneDxDocument) -> Sbom {
+    let mut packages = vec![];
+    let mut components: Vec<&CycloneDxComponent> = document.components.iter().rev().collect();
+    while let Some(component) = components.pop() {
+        components.extend(component.components.iter().rev());
+        let reference = |kind: &str| {
+            component
+                .external_references
+                .iter()
+                .find(|reference| reference.kind == kind)
+                .map(|reference| reference.url.as_str())
+        };
+        let name = match &component.group {
+            Some(group) if !group.is_empty() => format!("{group}/{}", component.name),
+            _ => component.name.clone(),
+        };
+        packages.push(sbom_package(
+            SbomFormat::CycloneDx,
+            &name,
+            component.version.as_deref(),
+            component.purl.as_deref(),
+            reference("vcs"),
+            reference("website").into_iter(),
+        ));
+    }
+    Sbom {
+        format: SbomFormat::CycloneDx,
+        name: document
+            .metadata
+            .and_then(|metadata| metadata.component)
+            .map(|component| component.name),
+        packages: deduplicate(packages.into_iter()),
+    }
+}
+
+/// A package of an SBOM, named after its package URL when it has one, as its
+/// registry knows it.
+///
+/// Its repository is the first of its URLs that is one of a supported forge,
+/// or else its version control URL as is. It is left to the registries when
+/// there is none, see [`ManifestResolver::resolve`](crate::ManifestResolver::resolve).
+fn sbom_package<'a>(
+    format: SbomFormat,
+    name: &str,
+    version: Option<&str>,
+    purl: Option<&str>,
+    vcs: Option<&'a str>,
+    others: impl Iterator<Item = &'a str>,
+) -> Dependency {
+    let purl = purl.and_then(PackageUrl::parse);
+    let vcs = vcs.map(repository_url);
+    let candidates: Vec<String> = vcs
+        .iter()
+        .cloned()
+        .chain(purl.as_ref().and_then(|purl| purl.repository.clone()))
+        .chain(others.map(repository_url))
+        .collect();
+    let url = candidates
+        .iter()
+        .find(|url| RepositoryId::try_from(url.to_string()).is_ok())
+        .cloned()
+        .or(vcs);
+    Dependency {
+        ecosystem: purl.as_ref().and_then(|purl| purl.ecosystem.clone()),
+        package_name: purl
+            .map(|purl| purl.name)
+            .unwrap_or_else(|| name.to_string()),
+        requirements: version.map(str::to_string),
+        manifest: Some(
+            match format {
+                SbomFormat::Spdx => "SPDX",
+                SbomFormat::CycloneDx => "CycloneDX",
+            }
+            .to_string(),
+        ),
+        repository: url.clone().and_then(|url| RepositoryId::try_from(url).ok()),
+        url,
+    }
+}
+
+/// A URL of sources, such as `git+https://github.com/owner/repo.git@v1.0#subdir`,
+/// without its scheme prefix, revision nor subdirectory.
+fn repository_url(url: &str) -> String {
+    let url = url.trim().trim_start_matches("git+");
+    let url = url.split('#').next().unwrap_or_default();
+    match url.rsplit_once('/') {
+        Some((base, last)) if last.contains('@') && !base.ends_with('/') => {
+            format!("{base}/{}", last.split('@').next().unwrap_or_default())
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// The packages listed once per ecosystem and name, in their order.
+fn deduplicate(packages: impl Iterator<Item = Dependency>) -> Vec<Dependency> {
+    let mut listed = HashSet::new();
+    packages
+        .filter(|package| listed.insert((package.ecosystem.clone(), package.package_name.clone())))
+        .collect()
+}
+
+/// The parts of a package URL, such as `pkg:npm/%40babel/core@7.24.0`, that
+/// tell which package it is.
+#[derive(Debug, PartialEq, Eq)]
+struct PackageUrl {
+    /// `None` for the `github` type, naming a repository rather than a package
+    ecosystem: Option<Ecosystem>,
+    /// Name of the package in its registry, such as `@babel/core` or
+    /// `org.slf4j:slf4j-api`
+    name: String,
+    repository: Option<String>,
+}
+
+impl PackageUrl {
+    fn parse(purl: &str) -> Option<Self> {
+        let purl = purl.trim().strip_prefix("pkg:")?;
+        let purl = purl.split(['?', '#']).next()?;
+        let (kind, path) = purl.split_once('/')?;
+        let path = path.trim_matches('/');
+        let (namespace, name) = match path.rsplit_once('/') {
+            Some((namespace, name)) => (Some(decode(namespace)), name),
+            None => (None, path),
+        };
+        let name = decode(name.split('@').next()?);
+        if name.is_empty() {
+            return None;
+        }
+        let kind = kind.to_lowercase();
+        let (ecosystem, separator) = match kind.as_str() {
+            "github" => {
+                return Some(PackageUrl {
+                    ecosystem: None,
+                    repository: Some(format!("https://github.com/{}/{name}", namespace?)),
+                    name,
+                });
+            }
+            "maven" => (Ecosystem::Maven, ':'),
+            "gem" => (Ecosystem::RubyGems, '/'),
+            kind => (kind.parse().unwrap(), '/'),
+        };
+        Some(PackageUrl {
+            ecosystem: Some(ecosystem),
+            name: match namespace {
+                Some(namespace) => format!("{namespace}{separator}{name}"),
+                None => name,
+            },
+            repository: None,
+        })
+    }
+}
+
+/// A percent-encoded segment of a package URL, only the characters found in
+/// package names being decoded.
+fn decode(segment: &str) -> String {
+    segment
+        .replace("%40", "@")
+        .replace("%2F", "/")
+        .replace("%2f", "/")
+        .replace("%3A", ":")
+        .replace("%3a", ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(sbom: &Sbom) -> Vec<(Option<Ecosystem>, &str, Option<&str>)> {
+        sbom.packages
+            .iter()
+            .map(|package| {
+                (
+                    package.ecosystem.clone(),
+                    package.package_name.as_str(),
+                    package.url.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spdx() {
+        let sbom = Sbom::parse(
+            r#"{
+                "spdxVersion": "SPDX-2.3",
+                "SPDXID": "SPDXRef-DOCUMENT",
+                "name": "internal-app",
+                "documentDescribes": ["SPDXRef-app"],
+                "packages": [
+                    { "SPDXID": "SPDXRef-app", "name": "internal-app", "downloadLocation": "NOASSERTION" },
+                    {
+                        "SPDXID": "SPDXRef-tokio",
+                        "name": "tokio",
+                        "versionInfo": "1.38.0",
+                        "downloadLocation": "https://crates.io/api/v1/crates/tokio/1.38.0/download",
+                        "externalRefs": [{
+                            "referenceCategory": "PACKAGE-MANAGER",
+                            "referenceType": "purl",
+                            "referenceLocator": "pkg:cargo/tokio@1.38.0"
+                        }]
+                    },
+                    {
+                        "SPDXID": "SPDXRef-core",
+                        "name": "@babel/core",
+                        "downloadLocation": "git+https://github.com/babel/babel.git@v7.24.0",
+                        "externalRefs": [{
+                            "referenceCategory": "PACKAGE-MANAGER",
+                            "referenceType": "purl",
+                            "referenceLocator": "pkg:npm/%40babel/core@7.24.0"
+                        }]
+                    },
+                    {
+                        "SPDXID": "SPDXRef-rug",
+                        "name": "rug",
+                        "downloadLocation": "NOASSERTION",
+                        "homepage": "https://gitlab.com/tspiteri/rug"
+                    },
+                    {
+                        "SPDXID": "SPDXRef-tokio-again",
+                        "name": "tokio",
+                        "externalRefs": [{
+                            "referenceCategory": "PACKAGE-MANAGER",
+                            "referenceType": "purl",
+                            "referenceLocator": "pkg:cargo/tokio@1.38.0"
+                        }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(sbom.format, SbomFormat::Spdx);
+        assert_eq!(sbom.name.as_deref(), Some("internal-app"));
+        assert_eq!(
+            summary(&sbom),
+            [
+                (Some(Ecosystem::Cargo), "tokio", None),
+                (
+                    Some(Ecosystem::Npm),
+                    "@babel/core",
+                    Some("https://github.com/babel/babel.git")
+                ),
+                (None, "rug", Some("https://gitlab.com/tspiteri/rug")),
+            ]
+        );
+        assert_eq!(
+            sbom.packages[1].repository.as_ref().map(RepositoryId::path),
+            Some("babel/babel".to_string())
+        );
+        assert_eq!(sbom.packages[0].requirements.as_deref(), Some("1.38.0"));
+        assert_eq!(sbom.packages[0].manifest.as_deref(), Some("SPDX"));
+    }
+
+    #[test]
+    fn test_cyclonedx() {
+        let sbom = Sbom::parse(
+            r#"{
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "metadata": { "component": { "type": "application", "name": "internal-app" } },
+                "components": [
+                    {
+                        "type": "library",
+                        "group": "org.slf4j",
+                        "name": "slf4j-api",
+                        "version": "2.0.13",
+                        "purl": "pkg:maven/org.slf4j/slf4j-api@2.0.13",
+                        "externalReferences": [
+                            { "type": "vcs", "url": "https://github.com/qos-ch/slf4j" },
+                            { "type": "website", "url": "https://www.slf4j.org" }
+                        ]
+                    },
+                    {
+                        "type": "library",
+                        "name": "requests",
+                        "purl": "pkg:pypi/requests@2.31.0",
+                        "components": [
+                            { "type": "library", "name": "urllib3", "purl": "pkg:pypi/urllib3@2.2.1" }
+                        ]
+                    },
+                    {
+                        "type": "library",
+                        "name": "mercurial",
+                        "externalReferences": [
+                            { "type": "vcs", "url": "https://bitbucket.org/mercurial/mercurial" }
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(sbom.format, SbomFormat::CycloneDx);
+        assert_eq!(sbom.name.as_deref(), Some("internal-app"));
+        assert_eq!(
+            summary(&sbom),
+            [
+                (
+                    Some(Ecosystem::Maven),
+                    "org.slf4j:slf4j-api",
+                    Some("https://github.com/qos-ch/slf4j")
+                ),
+                (Some(Ecosystem::Pip), "requests", None),
+                (Some(Ecosystem::Pip), "urllib3", None),
+                (
+                    None,
+                    "mercurial",
+                    Some("https://bitbucket.org/mercurial/mercurial")
+                ),
+            ]
+        );
+        assert!(sbom.packages[3].repository_id().is_none());
+
+        assert!(matches!(
+            Sbom::parse(r#"{ "name": "app" }"#),
+            Err(GitHubError::InvalidSbom(_))
+        ));
+        assert!(matches!(
+            Sbom::parse("<bom/>"),
+            Err(GitHubError::InvalidSbom(_))
+        ));
+    }
+
+    #[test]
+    fn test_package_urls() {
+        for (purl, ecosystem, name, repository) in [
+            (
+                "pkg:cargo/serde@1.0.203",
+                Some(Ecosystem::Cargo),
+                "serde",
+                None,
+            ),
+            (
+                "pkg:npm/@types/node",
+                Some(Ecosystem::Npm),
+                "@types/node",
+                None,
+            ),
+            (
+                "pkg:golang/golang.org/x/net@v0.25.0?type=module",
+                Some(Ecosystem::Go),
+                "golang.org/x/net",
+                None,
+            ),
+            (
+                "pkg:gem/rails@7.1.3",
+                Some(Ecosystem::RubyGems),
+                "rails",
+                None,
+            ),
+            (
+                "pkg:github/tokio-rs/tokio@v1.38.0",
+                None,
+                "tokio",
+                Some("https://github.com/tokio-rs/tokio"),
+            ),
+        ] {
+            let parsed = PackageUrl::parse(purl).unwrap();
+            assert_eq!(parsed.ecosystem, ecosystem, "{purl}");
+            assert_eq!(parsed.name, name, "{purl}");
+            assert_eq!(parsed.repository.as_deref(), repository, "{purl}");
+        }
+        assert_eq!(PackageUrl::parse("https://crates.io/crates/serde"), None);
+        assert_eq!(
+            repository_url("git+ssh://git@github.com/owner/repo.git#subdir"),
+            "ssh://git@github.com/owner/repo.git"
+        );
+        assert_eq!(
+            "CycloneDX".parse::<SbomFormat>().unwrap(),
+            SbomFormat::CycloneDx
+        );
+        assert!("xml".parse::<SbomFormat>().is_err());
+    }
+}
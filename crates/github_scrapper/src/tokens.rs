@@ -0,0 +1,323 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use metrics::gauge;
+use reqwest::StatusCode;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::Deserialize;
+use tokio::time::{Instant, sleep_until};
+use tracing::{debug, warn};
+
+use crate::rate_limit::{REMAINING_HEADER, header_u64, until_reset};
+use crate::{FetchRequest, FetchResponse, GitHubError, GitHubFetcher, RateLimitedFetcher};
+
+/// How long a token rejected by GitHub is left aside.
+const REJECTED_TOKEN_COOLDOWN: Duration = Duration::from_secs(3600);
+
+/// A GitHub personal access token.
+///
+/// Its value is never displayed, its [`Debug`] implementation is redacted.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct GitHubToken(String);
+
+impl GitHubToken {
+    pub fn new(token: &str) -> Self {
+        Self(token.trim().to_string())
+    }
+
+    fn header_value(&self) -> Option<HeaderValue> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", self.0)).ok()?;
+        value.set_sensitive(true);
+        Some(value)
+    }
+}
+
+impl fmt::Debug for GitHubToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GitHubToken(<redacted>)")
+    }
+}
+
+impl FromStr for GitHubToken {
+    type Err = GitHubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(GitHubError::InvalidToken);
+        }
+        Ok(Self::new(s))
+    }
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    remaining: Option<u64>,
+    exhausted_until: Option<Instant>,
+}
+
+/// Tokens shared by the requests to the GitHub APIs.
+///
+/// Each request uses the token with the most quota left, and a token is set
+/// aside until its reset time once its quota is exhausted. Tokens are only
+/// identified by their index in logs and metrics.
+#[derive(Debug)]
+pub struct TokenPool {
+    tokens: Vec<GitHubToken>,
+    states: Mutex<Vec<TokenState>>,
+}
+
+impl TokenPool {
+    pub fn new(tokens: Vec<GitHubToken>) -> Self {
+        let states = tokens.iter().map(|_| TokenState::default()).collect();
+        Self {
+            tokens,
+            states: Mutex::new(states),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// The index of the token to use, or when the next one becomes available
+    /// if they are all exhausted.
+    fn pick(&self) -> Result<usize, Instant> {
+        let mut states = self.states.lock().unwrap();
+        let now = Instant::now();
+        let mut best: Option<(usize, u64)> = None;
+        let mut next_reset: Option<Instant> = None;
+        for (index, state) in states.iter_mut().enumerate() {
+            match state.exhausted_until {
+                Some(until) if until > now => {
+                    next_reset = Some(next_reset.map_or(until, |next| next.min(until)));
+                    continue;
+                }
+                Some(_) => *state = TokenState::default(),
+                None => {}
+            }
+            // A token never used yet is assumed to have its whole quota
+            let remaining = state.remaining.unwrap_or(u64::MAX);
+            if best.is_none_or(|(_, most)| remaining > most) {
+                best = Some((index, remaining));
+            }
+        }
+        best.map(|(index, _)| index)
+            .ok_or(next_reset.unwrap_or(now))
+    }
+
+    /// Records the quota left on the token from the response headers.
+    fn record(&self, index: usize, headers: &HeaderMap) {
+        let Some(remaining) = header_u64(headers, REMAINING_HEADER) else {
+            return;
+        };
+        gauge!("github_token_remaining", "token" => index.to_string()).set(remaining as f64);
+        let mut states = self.states.lock().unwrap();
+        states[index].remaining = Some(remaining);
+        if remaining == 0 {
+            let reset = until_reset(headers).unwrap_or(REJECTED_TOKEN_COOLDOWN);
+            warn!("GitHub token #{index} exhausted for {reset:?}");
+            states[index].exhausted_until = Some(Instant::now() + reset);
+        }
+    }
+
+    fn set_aside(&self, index: usize, duration: Duration) {
+        let mut states = self.states.lock().unwrap();
+        states[index].exhausted_until = Some(Instant::now() + duration);
+    }
+}
+
+/// Attaches a token of a [`TokenPool`] to the requests sent to the GitHub APIs,
+/// rotating to another token when one is exhausted or rejected.
+///
+/// Requests to the GitHub website are sent anonymously.
+#[derive(Debug)]
+pub struct AuthenticatedFetcher<F = RateLimitedFetcher> {
+    inner: F,
+    pool: TokenPool,
+}
+
+impl Default for AuthenticatedFetcher {
+    fn default() -> Self {
+        Self::new(RateLimitedFetcher::default(), TokenPool::new(vec![]))
+    }
+}
+
+impl<F: GitHubFetcher> AuthenticatedFetcher<F> {
+    pub fn new(inner: F, pool: TokenPool) -> Self {
+        Self { inner, pool }
+    }
+}
+
+impl<F: GitHubFetcher> GitHubFetcher for AuthenticatedFetcher<F> {
+    async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+        if !request.authenticated || self.pool.is_empty() {
+            return self.inner.fetch(request).await;
+        }
+        loop {
+            let index = match self.pool.pick() {
+                Ok(index) => index,
+                Err(available_at) => {
+                    warn!(
+                        "All GitHub tokens are exhausted, waiting {:?}",
+                        available_at.saturating_duration_since(Instant::now())
+                    );
+                    sleep_until(available_at).await;
+                    continue;
+                }
+            };
+            let Some(header) = self.pool.tokens[index].header_value() else {
+                warn!("GitHub token #{index} is not a valid header value");
+                self.pool.set_aside(index, REJECTED_TOKEN_COOLDOWN);
+                continue;
+            };
+            debug!("Using GitHub token #{index} for {}", request.url);
+            let mut authenticated = request.clone();
+            authenticated.headers.insert(AUTHORIZATION, header);
+
+            let response = self.inner.fetch(&authenticated).await?;
+            self.pool.record(index, &response.headers);
+            if response.status == StatusCode::UNAUTHORIZED {
+                warn!("GitHub token #{index} was rejected");
+                self.pool.set_aside(index, REJECTED_TOKEN_COOLDOWN);
+                continue;
+            }
+            let exhausted = header_u64(&response.headers, REMAINING_HEADER) == Some(0);
+            if exhausted && !response.status.is_success() {
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::RESET_HEADER;
+    use std::collections::VecDeque;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// Answers the scripted responses in order and keeps the tokens it received.
+    struct ScriptedFetcher {
+        responses: Mutex<VecDeque<FetchResponse>>,
+        tokens: Mutex<Vec<Option<String>>>,
+    }
+
+    impl GitHubFetcher for ScriptedFetcher {
+        async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, GitHubError> {
+            let token = request
+                .headers
+                .get(AUTHORIZATION)
+                .map(|value| value.to_str().unwrap().to_string());
+            self.tokens.lock().unwrap().push(token);
+            Ok(self.responses.lock().unwrap().pop_front().unwrap())
+        }
+    }
+
+    fn response(status: u16, remaining: u64) -> FetchResponse {
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 600;
+        let mut headers = HeaderMap::new();
+        headers.insert(REMAINING_HEADER, HeaderValue::from(remaining));
+        headers.insert(RESET_HEADER, HeaderValue::from(reset));
+        FetchResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers,
+            body: String::new(),
+        }
+    }
+
+    fn fetcher(responses: Vec<FetchResponse>) -> AuthenticatedFetcher<ScriptedFetcher> {
+        let pool = TokenPool::new(vec![GitHubToken::new("first"), GitHubToken::new("second")]);
+        let inner = ScriptedFetcher {
+            responses: Mutex::new(responses.into()),
+            tokens: Mutex::new(vec![]),
+        };
+        AuthenticatedFetcher::new(inner, pool)
+    }
+
+    fn api_request() -> FetchRequest {
+        FetchRequest::get("https://api.github.com/repos/OWNER/REPO/contributors").authenticated()
+    }
+
+    fn sent_tokens(fetcher: &AuthenticatedFetcher<ScriptedFetcher>) -> Vec<Option<String>> {
+        fetcher.inner.tokens.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_token_is_redacted() {
+        let token = GitHubToken::new("ghp_secret");
+        assert!(!format!("{token:?}").contains("secret"));
+        assert!(!format!("{:?}", TokenPool::new(vec![token])).contains("secret"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rotates_on_remaining_quota() {
+        let fetcher = fetcher(vec![
+            response(200, 10),
+            response(200, 4000),
+            response(200, 3999),
+        ]);
+        for _ in 0..3 {
+            fetcher.fetch(&api_request()).await.unwrap();
+        }
+
+        let bearer = |token: &str| Some(format!("Bearer {token}"));
+        assert_eq!(
+            sent_tokens(&fetcher),
+            vec![bearer("first"), bearer("second"), bearer("second")]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_exhausted_token_is_set_aside_until_reset() {
+        let fetcher = fetcher(vec![
+            response(403, 0),
+            response(200, 100),
+            response(403, 0),
+            response(200, 4999),
+        ]);
+        let start = Instant::now();
+
+        assert!(
+            fetcher
+                .fetch(&api_request())
+                .await
+                .unwrap()
+                .status
+                .is_success()
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // Both tokens end up exhausted, the request waits for the first reset
+        assert!(
+            fetcher
+                .fetch(&api_request())
+                .await
+                .unwrap()
+                .status
+                .is_success()
+        );
+        assert!(start.elapsed() >= Duration::from_secs(590));
+    }
+
+    #[tokio::test]
+    async fn test_web_requests_are_anonymous() {
+        let fetcher = fetcher(vec![response(200, 60)]);
+        fetcher
+            .fetch(&FetchRequest::get("https://github.com/OWNER/REPO"))
+            .await
+            .unwrap();
+
+        assert_eq!(sent_tokens(&fetcher), vec![None]);
+    }
+}
@@ -1,20 +1,44 @@
 use scraper::Html;
-use tracing::info;
 
-use crate::GitHubError;
+use crate::{FetchRequest, FetchResponse, GitHubError, GitHubFetcher};
 
-pub(crate) async fn fetch_page(link: &str) -> Result<Html, GitHubError> {
-    info!("Fetching: {}", link);
-    let response = reqwest::get(link).await?;
+pub(crate) async fn fetch_page<F: GitHubFetcher>(
+    fetcher: &F,
+    request: &FetchRequest,
+) -> Result<Html, GitHubError> {
+    let response = fetcher.fetch(request).await?;
+    let response = check_status(response, &request.url)?;
 
-    if response.status() == reqwest::StatusCode::NOT_FOUND {
+    let document = Html::parse_document(&response.body);
+    Ok(document)
+}
+
+/// Converts unsuccessful responses into a [`GitHubError`].
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_status(
+    response: FetchResponse,
+    link: &str,
+) -> Result<FetchResponse, GitHubError> {
+    if response.status == reqwest::StatusCode::NOT_FOUND {
         return Err(GitHubError::NotFound(link.to_string()));
     }
 
-    if !response.status().is_success() {
-        return Err(GitHubError::RequestResponse(response));
+    if !response.status.is_success() {
+        return Err(GitHubError::RequestResponse {
+            url: link.to_string(),
+            status: response.status.as_u16(),
+        });
     }
 
-    let document = Html::parse_document(&response.text().await?);
-    Ok(document)
+    Ok(response)
+}
+
+/// A client serving the responses recorded in the `fixtures` directory.
+#[cfg(test)]
+pub(crate) fn fixtures_client() -> std::sync::Arc<crate::GitHubClient<crate::ReplayFetcher>> {
+    let fetcher = crate::ReplayFetcher::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"));
+    std::sync::Arc::new(crate::GitHubClient::with_fetcher(
+        fetcher,
+        crate::FetchMode::Html,
+    ))
 }
@@ -8,7 +8,8 @@ CREATE USER "${BACKEND_USER}"
     NOSUPERUSER NOCREATEDB NOCREATEROLE NOINHERIT
     ENCRYPTED PASSWORD '${BACKEND_PASSWORD}';
 GRANT CONNECT ON DATABASE hmc TO "${BACKEND_USER}";
-GRANT USAGE ON SCHEMA public TO "${BACKEND_USER}";
+-- The backend creates and owns the tables through its migrations
+GRANT USAGE, CREATE ON SCHEMA public TO "${BACKEND_USER}";
 GRANT SELECT, INSERT, UPDATE, DELETE
     ON ALL TABLES IN SCHEMA public
     TO "${BACKEND_USER}";
@@ -22,5 +23,8 @@ GRANT USAGE ON SCHEMA public TO "${GRAFANA_USER}";
 GRANT SELECT
     ON ALL TABLES IN SCHEMA public
     TO "${GRAFANA_USER}";
+ALTER DEFAULT PRIVILEGES FOR ROLE "${BACKEND_USER}" IN SCHEMA public
+    GRANT SELECT ON TABLES
+    TO "${GRAFANA_USER}";
 
 EOSQL
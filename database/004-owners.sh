@@ -0,0 +1,25 @@
+#!/bin/bash
+# Hands the tables created before the backend migrations over to the backend,
+# which alters them and references them from its own tables.
+#
+# Nothing to do on a new volume, where the backend creates every table. On a
+# volume created before the migrations, run it once before upgrading:
+#   docker compose exec database bash /docker-entrypoint-initdb.d/004-owners.sh
+export PGUSER=postgres
+
+psql -v ON_ERROR_STOP=1 --username "$POSTGRES_USER" --dbname "hmc" <<-EOSQL
+
+GRANT USAGE, CREATE ON SCHEMA public TO "${BACKEND_USER}";
+
+DO \$\$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_tables WHERE schemaname = 'public' AND tablename = 'repositories') THEN
+        ALTER TABLE public.repositories OWNER TO "${BACKEND_USER}";
+    END IF;
+    IF EXISTS (SELECT 1 FROM pg_proc WHERE proname = 'update_modified_column') THEN
+        ALTER FUNCTION public.update_modified_column() OWNER TO "${BACKEND_USER}";
+    END IF;
+END
+\$\$;
+
+EOSQL